@@ -0,0 +1,49 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::{decode_hex, header_value};
+use crate::errors::api_error::ApiError;
+
+/// GitHub から届いた Webhook の `X-Hub-Signature-256` を Webhook Secret で検証する。
+///
+/// 署名は本文の HMAC-SHA256 (`sha256=<hex>`)。比較は定数時間で行う。
+pub fn verify(secret: &str, headers: &[(String, String)], body: &[u8]) -> Result<(), ApiError> {
+    let signature = header_value(headers, "x-hub-signature-256")
+        .ok_or_else(|| ApiError::Unauthorized("Missing X-Hub-Signature-256".to_string()))?
+        .trim();
+    let expected = signature
+        .strip_prefix("sha256=")
+        .and_then(decode_hex)
+        .ok_or_else(|| ApiError::Unauthorized("Malformed X-Hub-Signature-256".to_string()))?;
+
+    // HMAC は任意長の鍵を受け付けるので失敗しない
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected)
+        .map_err(|_| ApiError::Unauthorized("Invalid GitHub signature".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify;
+
+    // GitHub のドキュメントにある検証用の値
+    const SECRET: &str = "It's a Secret to Everybody";
+    const BODY: &[u8] = b"Hello, World!";
+    const SIGNATURE: &str =
+        "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    fn headers(signature: &str) -> Vec<(String, String)> {
+        vec![("X-Hub-Signature-256".to_string(), signature.to_string())]
+    }
+
+    #[test]
+    fn signature_is_verified() {
+        assert!(verify(SECRET, &headers(SIGNATURE), BODY).is_ok());
+        assert!(verify("other", &headers(SIGNATURE), BODY).is_err());
+        assert!(verify(SECRET, &headers(SIGNATURE), b"Hello, World?").is_err());
+        assert!(verify(SECRET, &headers("sha1=757107ea"), BODY).is_err());
+        assert!(verify(SECRET, &[], BODY).is_err());
+    }
+}
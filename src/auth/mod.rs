@@ -0,0 +1,302 @@
+pub mod github_signature;
+pub mod scope;
+pub mod slack_signature;
+
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::{
+    config::settings::{ApiKeySetting, Settings},
+    errors::api_error::ApiError,
+    service::slack_channels::normalize_channel_name,
+};
+use scope::{RequiredScope, Scope};
+
+const ANONYMOUS_KEY_NAME: &str = "anonymous";
+
+/// 認証済みの呼び出し元。ログにはキー本体ではなく `key_name` を出す。
+#[derive(Debug, Clone)]
+pub struct CallerIdentity {
+    pub key_name: String,
+    pub scopes: Vec<Scope>,
+}
+
+impl CallerIdentity {
+    /// 認証を無効化している場合と、認証不要なルートで使う呼び出し元。
+    pub fn anonymous() -> Self {
+        Self {
+            key_name: ANONYMOUS_KEY_NAME.to_string(),
+            scopes: vec![Scope::All],
+        }
+    }
+
+    pub fn require(&self, required: RequiredScope) -> Result<(), ApiError> {
+        if scope::grants(&self.scopes, &required) {
+            return Ok(());
+        }
+        warn!(
+            api_key = %self.key_name,
+            required_scope = %required,
+            "Caller is missing required scope"
+        );
+        Err(ApiError::Forbidden(format!("Missing scope '{required}'")))
+    }
+}
+
+/// チャンネルへの投稿に必要なスコープ
+pub fn slack_post(channel: String) -> RequiredScope {
+    RequiredScope::SlackPost { channel }
+}
+
+/// チャンネルの読み出しに必要なスコープ
+pub fn slack_read(channel: String) -> RequiredScope {
+    RequiredScope::SlackRead { channel }
+}
+
+/// 解決後のチャンネル ID、または `#name` で指定された場合は正規化した名前のどちらかにスコープがあるか確認する。
+/// 大文字や `#` の有無の違いでスコープの判定が変わらないよう、必ず解決してから呼ぶ。
+pub fn authorize_channel(
+    caller: &CallerIdentity,
+    requested: &str,
+    resolved: &str,
+    required: fn(String) -> RequiredScope,
+) -> Result<(), ApiError> {
+    if requested.starts_with('#')
+        && scope::grants(
+            &caller.scopes,
+            &required(format!("#{}", normalize_channel_name(requested))),
+        )
+    {
+        return Ok(());
+    }
+    caller.require(required(resolved.to_string()))
+}
+
+/// `Authorization: Bearer <key>` または `X-API-Key: <key>` で渡されたキーを検証する。
+pub fn authenticate(
+    settings: &Settings,
+    headers: &[(String, String)],
+) -> Result<CallerIdentity, ApiError> {
+    if settings.auth_disabled {
+        return Ok(CallerIdentity::anonymous());
+    }
+
+    let token = extract_token(headers)?;
+    let matched = find_key(&settings.api_keys, token)
+        .ok_or_else(|| ApiError::Unauthorized("Invalid API key".to_string()))?;
+
+    Ok(CallerIdentity {
+        key_name: matched.name.clone(),
+        scopes: matched.scopes.clone(),
+    })
+}
+
+fn extract_token(headers: &[(String, String)]) -> Result<&str, ApiError> {
+    if let Some(value) = header_value(headers, "authorization") {
+        let (scheme, token) = value
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| ApiError::Unauthorized("Malformed Authorization header".to_string()))?;
+        if !scheme.eq_ignore_ascii_case("bearer") {
+            return Err(ApiError::Unauthorized(
+                "Unsupported authorization scheme. Use Bearer".to_string(),
+            ));
+        }
+        let token = token.trim();
+        if token.is_empty() {
+            return Err(ApiError::Unauthorized(
+                "Malformed Authorization header".to_string(),
+            ));
+        }
+        return Ok(token);
+    }
+
+    if let Some(value) = header_value(headers, "x-api-key") {
+        let token = value.trim();
+        if !token.is_empty() {
+            return Ok(token);
+        }
+    }
+
+    Err(ApiError::Unauthorized("Missing API key".to_string()))
+}
+
+fn find_key<'a>(keys: &'a [ApiKeySetting], token: &str) -> Option<&'a ApiKeySetting> {
+    let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+    // 一致の有無でタイミングが変わらないよう全キーを比較する
+    let mut matched = None;
+    for key in keys {
+        if constant_time_eq(&key.key_sha256, &digest) && matched.is_none() {
+            matched = Some(key);
+        }
+    }
+    matched
+}
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter()
+        .zip(b.iter())
+        .fold(0_u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+pub(crate) fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+pub(crate) fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CallerIdentity, RequiredScope, Scope, authorize_channel, extract_token, find_key,
+        slack_post,
+    };
+    use crate::config::settings::{
+        parse_api_keys, parse_slack_command_scopes, parse_slack_command_users,
+    };
+    use proptest::{prelude::ProptestConfig, prop_assert_eq, proptest};
+
+    // sha256("secret-ci-key")
+    const CI_KEY_SHA256: &str = "39c996c90b8b5a2575e9226f490cc931529c6e47aeabf9730f3d3f1cf93d3617";
+
+    fn headers(name: &str, value: &str) -> Vec<(String, String)> {
+        vec![(name.to_string(), value.to_string())]
+    }
+
+    #[test]
+    fn bearer_token_is_extracted() {
+        let headers = headers("Authorization", "Bearer abc123");
+        assert_eq!(extract_token(&headers).expect("token"), "abc123");
+    }
+
+    #[test]
+    fn api_key_header_is_accepted() {
+        let headers = headers("X-API-Key", "abc123");
+        assert_eq!(extract_token(&headers).expect("token"), "abc123");
+    }
+
+    #[test]
+    fn non_bearer_scheme_is_rejected() {
+        let headers = headers("Authorization", "Basic dXNlcjpwYXNz");
+        let err = extract_token(&headers).expect_err("basic should be rejected");
+        assert_eq!(
+            err.to_string(),
+            "Unauthorized: Unsupported authorization scheme. Use Bearer"
+        );
+    }
+
+    #[test]
+    fn missing_credentials_are_rejected() {
+        let err = extract_token(&[]).expect_err("missing key should be rejected");
+        assert_eq!(err.to_string(), "Unauthorized: Missing API key");
+    }
+
+    #[test]
+    fn parse_api_keys_rejects_invalid_digest() {
+        assert!(parse_api_keys("ci:not-hex").is_err());
+        assert!(parse_api_keys("ci").is_err());
+        assert!(parse_api_keys("").is_err());
+        assert!(parse_api_keys(&format!("ci:{CI_KEY_SHA256}:*,ci:{CI_KEY_SHA256}:*")).is_err());
+    }
+
+    #[test]
+    fn slack_command_scopes_exclude_s3() {
+        assert!(parse_slack_command_scopes("slack:read:*").is_ok());
+        assert!(parse_slack_command_scopes("s3:read:reports/*").is_err());
+        assert!(parse_slack_command_scopes("*").is_err());
+        assert!(parse_slack_command_users("U1:s3:read:reports/*,T1:s3:admin").is_ok());
+        assert!(parse_slack_command_users("U1").is_err());
+        assert!(parse_slack_command_users("U1:").is_err());
+        assert!(parse_slack_command_users("ci:*").is_err());
+        assert!(parse_slack_command_users("U1:*,U1:s3:admin").is_err());
+    }
+
+    #[test]
+    fn only_configured_key_matches() {
+        let keys = parse_api_keys(&format!("ci:{CI_KEY_SHA256}:*")).expect("keys should parse");
+        assert!(find_key(&keys, "wrong-key").is_none());
+        assert_eq!(
+            find_key(&keys, "secret-ci-key").map(|key| key.name.as_str()),
+            Some("ci")
+        );
+    }
+
+    #[test]
+    fn parse_api_keys_reads_scopes() {
+        let keys = parse_api_keys(&format!(
+            "alerts:{CI_KEY_SHA256}:slack:post:#alerts s3:read:reports/*, ci:{CI_KEY_SHA256}:*"
+        ))
+        .expect("keys should parse");
+
+        assert_eq!(
+            keys[0].scopes,
+            vec![
+                Scope::SlackPost("#alerts".to_string()),
+                Scope::S3Read("reports/*".to_string()),
+            ]
+        );
+        assert_eq!(keys[1].scopes, vec![Scope::All]);
+        assert!(parse_api_keys(&format!("ci:{CI_KEY_SHA256}:s3:delete:x")).is_err());
+        assert!(parse_api_keys(&format!("ci:{CI_KEY_SHA256}")).is_err());
+    }
+
+    #[test]
+    fn missing_scope_is_forbidden() {
+        let caller = CallerIdentity {
+            key_name: "alerts".to_string(),
+            scopes: vec![Scope::SlackPost("#alerts".to_string())],
+        };
+        let err = caller
+            .require(RequiredScope::S3Admin)
+            .expect_err("admin should be denied");
+        assert_eq!(err.to_string(), "Forbidden: Missing scope 's3:admin'");
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn configured_key_matches_its_digest(token in "[A-Za-z0-9_-]{8,64}") {
+            use sha2::{Digest, Sha256};
+
+            let digest = Sha256::digest(token.as_bytes())
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<String>();
+            let keys = parse_api_keys(&format!("other:{CI_KEY_SHA256}:*, team:{digest}:*"))
+                .expect("keys should parse");
+
+            let matched = find_key(&keys, &token).map(|key| key.name.as_str());
+            prop_assert_eq!(matched, Some("team"));
+        }
+    }
+
+    #[test]
+    fn channel_scope_is_checked_against_resolved_channel() {
+        let caller = |scope: &str| CallerIdentity {
+            key_name: "test".to_string(),
+            scopes: vec![Scope::parse(scope).expect("scope")],
+        };
+
+        let by_name = caller("slack:post:#alerts");
+        assert!(authorize_channel(&by_name, "#Alerts", "C1", slack_post).is_ok());
+        assert!(authorize_channel(&by_name, "#deploys", "C2", slack_post).is_err());
+        assert!(authorize_channel(&by_name, "C2", "C2", slack_post).is_err());
+
+        let by_id = caller("slack:post:C1");
+        assert!(authorize_channel(&by_id, "#alerts", "C1", slack_post).is_ok());
+        assert!(authorize_channel(&by_id, "#deploys", "C2", slack_post).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt;
+
+/// API キーに付与する権限。
+///
+/// - `*`: すべて許可
+/// - `slack:post:<channel>`: チャンネルへの投稿・ファイル共有 (`#name` / ID / `*` glob)
+/// - `slack:dm:<user>`: ユーザーへの DM (ユーザー ID またはメールアドレス / `*` glob)
+/// - `slack:read:<channel>`: チャンネルのファイル・履歴の読み出し (`#name` / ID / `*` glob)
+/// - `s3:read:<bucket>[/<key>]`: 取得・一覧・プレビュー・署名付き GET
+/// - `s3:write:<bucket>[/<key>]`: 書き込み・削除・マルチパート・署名付き PUT
+/// - `s3:admin`: バケット操作を含む S3 の全操作
+///
+/// `<bucket>/<key>` は `*` を任意の文字列として扱う glob。`/<key>` を省略した場合はバケット全体。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    All,
+    SlackPost(String),
+    SlackDm(String),
+    SlackRead(String),
+    S3Read(String),
+    S3Write(String),
+    S3Admin,
+}
+
+impl Scope {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        if raw == "*" {
+            return Ok(Self::All);
+        }
+        if raw == "s3:admin" {
+            return Ok(Self::S3Admin);
+        }
+
+        if let Some(pattern) = raw.strip_prefix("slack:post:") {
+            return Ok(Self::SlackPost(non_empty_target(raw, pattern)?));
+        }
+        if let Some(pattern) = raw.strip_prefix("slack:dm:") {
+            return Ok(Self::SlackDm(non_empty_target(raw, pattern)?));
+        }
+        if let Some(pattern) = raw.strip_prefix("slack:read:") {
+            return Ok(Self::SlackRead(non_empty_target(raw, pattern)?));
+        }
+        if let Some(pattern) = raw.strip_prefix("s3:read:") {
+            return Ok(Self::S3Read(non_empty_target(raw, pattern)?));
+        }
+        if let Some(pattern) = raw.strip_prefix("s3:write:") {
+            return Ok(Self::S3Write(non_empty_target(raw, pattern)?));
+        }
+        Err(format!("unknown scope '{raw}'"))
+    }
+}
+
+fn non_empty_target(raw: &str, pattern: &str) -> Result<String, String> {
+    if pattern.is_empty() {
+        return Err(format!("scope '{raw}' has an empty target"));
+    }
+    Ok(pattern.to_string())
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::All => write!(f, "*"),
+            Self::SlackPost(channel) => write!(f, "slack:post:{channel}"),
+            Self::SlackDm(user) => write!(f, "slack:dm:{user}"),
+            Self::SlackRead(channel) => write!(f, "slack:read:{channel}"),
+            Self::S3Read(target) => write!(f, "s3:read:{target}"),
+            Self::S3Write(target) => write!(f, "s3:write:{target}"),
+            Self::S3Admin => write!(f, "s3:admin"),
+        }
+    }
+}
+
+/// リクエストが必要とする権限。拒否時のメッセージにそのまま使う。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequiredScope {
+    SlackPost { channel: String },
+    SlackDm { user: String },
+    SlackRead { channel: String },
+    S3Read { bucket: String, key: Option<String> },
+    S3Write { bucket: String, key: Option<String> },
+    S3Admin,
+}
+
+impl fmt::Display for RequiredScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SlackPost { channel } => write!(f, "slack:post:{channel}"),
+            Self::SlackDm { user } => write!(f, "slack:dm:{user}"),
+            Self::SlackRead { channel } => write!(f, "slack:read:{channel}"),
+            Self::S3Read { bucket, key } => write!(f, "s3:read:{}", s3_target(bucket, key)),
+            Self::S3Write { bucket, key } => write!(f, "s3:write:{}", s3_target(bucket, key)),
+            Self::S3Admin => write!(f, "s3:admin"),
+        }
+    }
+}
+
+fn s3_target(bucket: &str, key: &Option<String>) -> String {
+    match key {
+        Some(key) => format!("{bucket}/{key}"),
+        None => bucket.to_string(),
+    }
+}
+
+pub fn grants(scopes: &[Scope], required: &RequiredScope) -> bool {
+    scopes.iter().any(|scope| scope_grants(scope, required))
+}
+
+fn scope_grants(scope: &Scope, required: &RequiredScope) -> bool {
+    match (scope, required) {
+        (Scope::All, _) => true,
+        (Scope::S3Admin, RequiredScope::S3Read { .. } | RequiredScope::S3Write { .. }) => true,
+        (Scope::S3Admin, RequiredScope::S3Admin) => true,
+        (Scope::SlackPost(pattern), RequiredScope::SlackPost { channel }) => {
+            glob_match(pattern, channel)
+        }
+        (Scope::SlackDm(pattern), RequiredScope::SlackDm { user }) => glob_match(pattern, user),
+        (Scope::SlackRead(pattern), RequiredScope::SlackRead { channel }) => {
+            glob_match(pattern, channel)
+        }
+        (Scope::S3Read(pattern), RequiredScope::S3Read { bucket, key })
+        | (Scope::S3Write(pattern), RequiredScope::S3Write { bucket, key }) => {
+            s3_pattern_matches(pattern, bucket, key.as_deref())
+        }
+        _ => false,
+    }
+}
+
+/// `key` が `None` のバケット単位の操作は、バケット部分が一致すれば許可する。
+fn s3_pattern_matches(pattern: &str, bucket: &str, key: Option<&str>) -> bool {
+    let (bucket_pattern, key_pattern) = pattern.split_once('/').unwrap_or((pattern, "*"));
+    if !glob_match(bucket_pattern, bucket) {
+        return false;
+    }
+    match key {
+        Some(key) => glob_match(key_pattern, key),
+        None => true,
+    }
+}
+
+/// `*` のみを特殊文字として扱う glob。
+pub fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let value = value.as_bytes();
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == value[v] {
+            p += 1;
+            v += 1;
+        } else if let Some((star_p, star_v)) = backtrack {
+            p = star_p + 1;
+            v = star_v + 1;
+            backtrack = Some((star_p, star_v + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|b| *b == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RequiredScope, Scope, glob_match, grants};
+    use proptest::{prelude::ProptestConfig, prop_assert, proptest};
+
+    fn scopes(raw: &[&str]) -> Vec<Scope> {
+        raw.iter()
+            .map(|s| Scope::parse(s).expect("scope should parse"))
+            .collect()
+    }
+
+    fn read(bucket: &str, key: Option<&str>) -> RequiredScope {
+        RequiredScope::S3Read {
+            bucket: bucket.to_string(),
+            key: key.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn parse_rejects_unknown_scope() {
+        assert!(Scope::parse("s3:delete:reports").is_err());
+        assert!(Scope::parse("slack:post:").is_err());
+    }
+
+    #[test]
+    fn slack_post_is_limited_to_channel() {
+        let scopes = scopes(&["slack:post:#alerts"]);
+        let alerts = RequiredScope::SlackPost {
+            channel: "#alerts".to_string(),
+        };
+        let deploys = RequiredScope::SlackPost {
+            channel: "#deploys".to_string(),
+        };
+        assert!(grants(&scopes, &alerts));
+        assert!(!grants(&scopes, &deploys));
+    }
+
+    #[test]
+    fn slack_dm_does_not_grant_channel_post() {
+        let scopes = scopes(&["slack:dm:*@example.com"]);
+        let dm = |user: &str| RequiredScope::SlackDm {
+            user: user.to_string(),
+        };
+        assert!(grants(&scopes, &dm("oncall@example.com")));
+        assert!(!grants(&scopes, &dm("someone@other.example")));
+        assert!(!grants(
+            &scopes,
+            &RequiredScope::SlackPost {
+                channel: "C123".to_string()
+            }
+        ));
+    }
+
+    #[test]
+    fn slack_read_is_separate_from_post() {
+        let scopes = scopes(&["slack:read:C1*"]);
+        let read_channel = |channel: &str| RequiredScope::SlackRead {
+            channel: channel.to_string(),
+        };
+        assert!(grants(&scopes, &read_channel("C123")));
+        assert!(!grants(&scopes, &read_channel("D999")));
+        assert!(!grants(
+            &scopes,
+            &RequiredScope::SlackPost {
+                channel: "C123".to_string()
+            }
+        ));
+    }
+
+    #[test]
+    fn s3_read_pattern_covers_bucket_and_key() {
+        let scopes = scopes(&["s3:read:reports/2026/*"]);
+        assert!(grants(&scopes, &read("reports", Some("2026/04/a.pdf"))));
+        assert!(grants(&scopes, &read("reports", None)));
+        assert!(!grants(&scopes, &read("reports", Some("2025/a.pdf"))));
+        assert!(!grants(&scopes, &read("other", Some("2026/a.pdf"))));
+    }
+
+    #[test]
+    fn read_scope_does_not_grant_write() {
+        let scopes = scopes(&["s3:read:reports/*"]);
+        let write = RequiredScope::S3Write {
+            bucket: "reports".to_string(),
+            key: Some("a.pdf".to_string()),
+        };
+        assert!(!grants(&scopes, &write));
+        assert!(!grants(&scopes, &RequiredScope::S3Admin));
+    }
+
+    #[test]
+    fn admin_grants_all_s3_operations() {
+        let scopes = scopes(&["s3:admin"]);
+        assert!(grants(&scopes, &read("reports", Some("a.pdf"))));
+        assert!(grants(&scopes, &RequiredScope::S3Admin));
+        assert!(!grants(
+            &scopes,
+            &RequiredScope::SlackPost {
+                channel: "C123".to_string()
+            }
+        ));
+    }
+
+    #[test]
+    fn required_scope_display_names_target() {
+        let required = RequiredScope::S3Write {
+            bucket: "reports".to_string(),
+            key: Some("a.pdf".to_string()),
+        };
+        assert_eq!(required.to_string(), "s3:write:reports/a.pdf");
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn glob_star_matches_any_suffix(prefix in "[a-z/]{0,16}", suffix in "[ -~]{0,32}") {
+            let pattern = format!("{prefix}*");
+            let value = format!("{prefix}{suffix}");
+            prop_assert!(glob_match(&pattern, &value));
+        }
+
+        #[test]
+        fn glob_without_star_is_exact(value in "[a-z0-9/._-]{0,32}") {
+            prop_assert!(glob_match(&value, &value));
+            let other = format!("{value}x");
+            prop_assert!(!glob_match(&value, &other));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::{decode_hex, header_value};
+use crate::errors::api_error::ApiError;
+
+/// 署名のタイムスタンプとして受け付ける現在時刻からのずれ (5 分)。これより古いリクエストはリプレイとみなす。
+pub const MAX_TIMESTAMP_SKEW_SECS: i64 = 5 * 60;
+
+const SIGNATURE_VERSION: &str = "v0";
+
+/// Slack から届いたリクエストの `X-Slack-Signature` を Signing Secret で検証する。
+///
+/// 署名は `v0:{X-Slack-Request-Timestamp}:{本文}` の HMAC-SHA256。比較は定数時間で行う。
+pub fn verify(
+    signing_secret: &str,
+    headers: &[(String, String)],
+    body: &[u8],
+    now: i64,
+) -> Result<(), ApiError> {
+    let timestamp = header_value(headers, "x-slack-request-timestamp")
+        .ok_or_else(|| ApiError::Unauthorized("Missing X-Slack-Request-Timestamp".to_string()))?
+        .trim();
+    let signature = header_value(headers, "x-slack-signature")
+        .ok_or_else(|| ApiError::Unauthorized("Missing X-Slack-Signature".to_string()))?
+        .trim();
+
+    let timestamp_secs = timestamp
+        .parse::<i64>()
+        .map_err(|_| ApiError::Unauthorized("Malformed X-Slack-Request-Timestamp".to_string()))?;
+    if now.abs_diff(timestamp_secs) > MAX_TIMESTAMP_SKEW_SECS as u64 {
+        return Err(ApiError::Unauthorized(
+            "Slack request timestamp is too old".to_string(),
+        ));
+    }
+
+    let expected = signature
+        .strip_prefix(SIGNATURE_VERSION)
+        .and_then(|rest| rest.strip_prefix('='))
+        .and_then(decode_hex)
+        .ok_or_else(|| ApiError::Unauthorized("Malformed X-Slack-Signature".to_string()))?;
+
+    signing_mac(signing_secret, timestamp, body)
+        .verify_slice(&expected)
+        .map_err(|_| ApiError::Unauthorized("Invalid Slack signature".to_string()))
+}
+
+fn signing_mac(signing_secret: &str, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+    // HMAC は任意長の鍵を受け付けるので失敗しない
+    let mut mac = Hmac::<Sha256>::new_from_slice(signing_secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(SIGNATURE_VERSION.as_bytes());
+    mac.update(b":");
+    mac.update(timestamp.as_bytes());
+    mac.update(b":");
+    mac.update(body);
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_TIMESTAMP_SKEW_SECS, verify};
+
+    // `printf 'v0:1531420618:%s' "$BODY" | openssl dgst -sha256 -hmac "$SECRET"` と同じ値
+    const SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+    const TIMESTAMP: i64 = 1_531_420_618;
+    const BODY: &str =
+        r#"{"type":"event_callback","event_id":"Ev1","event":{"type":"app_mention"}}"#;
+    const SIGNATURE: &str = "v0=a1294e790ee07ea10a7389b67bb320f5a5df8119b32726d2c7f28e99fa7a501b";
+
+    fn headers(timestamp: &str, signature: &str) -> Vec<(String, String)> {
+        vec![
+            (
+                "X-Slack-Request-Timestamp".to_string(),
+                timestamp.to_string(),
+            ),
+            ("X-Slack-Signature".to_string(), signature.to_string()),
+        ]
+    }
+
+    #[test]
+    fn documented_signature_is_accepted() {
+        let headers = headers(&TIMESTAMP.to_string(), SIGNATURE);
+        assert!(verify(SECRET, &headers, BODY.as_bytes(), TIMESTAMP + 10).is_ok());
+    }
+
+    #[test]
+    fn tampered_body_or_secret_is_rejected() {
+        let signed = headers(&TIMESTAMP.to_string(), SIGNATURE);
+        let tampered = BODY.replace("app_mention", "message");
+        assert!(verify(SECRET, &signed, tampered.as_bytes(), TIMESTAMP).is_err());
+        assert!(verify("other-secret", &signed, BODY.as_bytes(), TIMESTAMP).is_err());
+
+        let malformed = headers(&TIMESTAMP.to_string(), "v1=abcd");
+        let err = verify(SECRET, &malformed, BODY.as_bytes(), TIMESTAMP).expect_err("v1");
+        assert_eq!(err.to_string(), "Unauthorized: Malformed X-Slack-Signature");
+    }
+
+    #[test]
+    fn stale_timestamp_is_rejected_as_replay() {
+        let headers = headers(&TIMESTAMP.to_string(), SIGNATURE);
+        let now = TIMESTAMP + MAX_TIMESTAMP_SKEW_SECS + 1;
+        let err = verify(SECRET, &headers, BODY.as_bytes(), now).expect_err("replay");
+        assert_eq!(
+            err.to_string(),
+            "Unauthorized: Slack request timestamp is too old"
+        );
+        assert!(verify(SECRET, &[], BODY.as_bytes(), TIMESTAMP).is_err());
+    }
+
+    #[test]
+    fn extreme_timestamp_is_rejected_without_overflow() {
+        for timestamp in [i64::MIN, i64::MAX] {
+            let headers = headers(&timestamp.to_string(), SIGNATURE);
+            let err = verify(SECRET, &headers, BODY.as_bytes(), TIMESTAMP).expect_err("extreme");
+            assert_eq!(
+                err.to_string(),
+                "Unauthorized: Slack request timestamp is too old"
+            );
+        }
+    }
+}
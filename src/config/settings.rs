@@ -1,25 +1,89 @@
-use std::env;
+use std::{collections::HashMap, env, str::FromStr, time::Duration};
+
+use crate::{
+    auth::{decode_hex, scope::Scope},
+    http_client::{PoolConfig, RetryPolicy, TimeoutConfig},
+    multipart::MultipartLimits,
+    service::{
+        file_type::ContentTypePolicy,
+        hook_routes::{HookRoute, parse_routes},
+        slack_events::EventSink,
+        slack_template::{TemplateLocation, parse_templates},
+    },
+};
 
 #[derive(Debug, Clone)]
 pub struct Settings {
     pub slack_bot_token: String,
     pub slack_api_base_url: String,
+    /// `#name` 解決に使うチャンネル一覧を保持する時間
+    pub slack_channel_cache_ttl: Duration,
+    /// `/slack/upload/file` で受け付ける `Content-Type`
+    pub slack_upload_content_types: ContentTypePolicy,
+    /// `multipart/form-data` の本文のサイズ上限
+    pub multipart_limits: MultipartLimits,
     pub s3_access_key_id: String,
     pub s3_secret_access_key: String,
     pub s3_region: String,
     pub s3_endpoint: Option<String>,
     pub s3_use_path_style: bool,
     pub s3_session_token: Option<String>,
+    /// S3 と Slack の間で受け渡すオブジェクトの上限 (バイト)
+    pub s3_transfer_max_bytes: usize,
+    /// `/slack/archive/files` で `bucket` を省略した場合の保存先
+    pub slack_archive_bucket: Option<String>,
+    /// Slack からのリクエストの署名検証に使う Signing Secret。未設定なら `/slack/events` は 404
+    pub slack_signing_secret: Option<String>,
+    /// `/slack/events` で受け取ったイベントの転送先
+    pub slack_event_sinks: Vec<EventSink>,
+    /// `/slack/commands` と `/slack/interactivity` のハンドラーに全員共通で与えるスコープ。S3 のスコープは含められない
+    pub slack_command_scopes: Vec<Scope>,
+    /// ユーザー・ワークスペースごとに `slack_command_scopes` へ追加するスコープ
+    pub slack_command_users: Vec<SlackCommandUser>,
+    /// `/hooks/*` の通知の送り先を決めるルール
+    pub hook_routes: Vec<HookRoute>,
+    /// `/slack/message/template` で使う名前付きテンプレート
+    pub slack_templates: HashMap<String, nojson::RawJsonOwned>,
+    /// `slack_templates` に無いテンプレートを読む S3 の場所
+    pub slack_template_s3: Option<TemplateLocation>,
+    /// `/hooks/github` の署名検証に使う Webhook Secret。設定すると API キーの代わりに署名で認証する
+    pub github_webhook_secret: Option<String>,
+    pub api_keys: Vec<ApiKeySetting>,
+    pub auth_disabled: bool,
+    pub http_pool: PoolConfig,
+    pub http_timeouts: TimeoutConfig,
+    pub http_retry: RetryPolicy,
+}
+
+/// 呼び出し元を識別する API キー。キー本体は保持せず SHA-256 ダイジェストのみ持つ。
+#[derive(Debug, Clone)]
+pub struct ApiKeySetting {
+    pub name: String,
+    pub key_sha256: [u8; 32],
+    pub scopes: Vec<Scope>,
+}
+
+/// スラッシュコマンドと操作で追加のスコープを持つ Slack のユーザーまたはワークスペース
+#[derive(Debug, Clone)]
+pub struct SlackCommandUser {
+    /// ユーザー ID (`U…` / `W…`) またはワークスペース ID (`T…`)
+    pub id: String,
+    pub scopes: Vec<Scope>,
 }
+
 #[derive(Debug)]
 pub enum SettingError {
     MissingEnvVar(String),
+    InvalidEnvVar(String, String),
 }
 
 impl std::fmt::Display for SettingError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::MissingEnvVar(name) => write!(f, "Missing environment variable {name}"),
+            Self::InvalidEnvVar(name, reason) => {
+                write!(f, "Invalid environment variable {name}: {reason}")
+            }
         }
     }
 }
@@ -34,6 +98,28 @@ impl Settings {
             .unwrap_or_else(|_| "https://slack.com/api".to_string())
             .trim_end_matches('/')
             .to_string();
+        let slack_channel_cache_ttl =
+            parse_timeout_env("SLACK_CHANNEL_CACHE_TTL_SECS", Duration::from_secs(300))?;
+        let slack_upload_content_types = ContentTypePolicy {
+            allowed: ContentTypePolicy::parse_list(
+                &env::var("SLACK_UPLOAD_ALLOWED_CONTENT_TYPES").unwrap_or_default(),
+            ),
+            denied: ContentTypePolicy::parse_list(
+                &env::var("SLACK_UPLOAD_DENIED_CONTENT_TYPES").unwrap_or_default(),
+            ),
+        };
+
+        let default_multipart = MultipartLimits::default();
+        let multipart_limits = MultipartLimits {
+            max_part_size: parse_number_env(
+                "MULTIPART_MAX_PART_BYTES",
+                default_multipart.max_part_size,
+            )?,
+            max_total_size: parse_number_env(
+                "MULTIPART_MAX_TOTAL_BYTES",
+                default_multipart.max_total_size,
+            )?,
+        };
 
         let s3_access_key_id = env::var("RUSTFS_S3_ACCESS_KEY_ID")
             .map_err(|_| SettingError::MissingEnvVar("RUSTFS_S3_ACCESS_KEY_ID".into()))?;
@@ -48,16 +134,137 @@ impl Settings {
         let s3_session_token = env::var("RUSTFS_S3_SESSION_TOKEN")
             .ok()
             .filter(|v| !v.is_empty());
+        let s3_transfer_max_bytes = parse_number_env("S3_TRANSFER_MAX_BYTES", 100 * 1024 * 1024)?;
+        let slack_archive_bucket = env::var("SLACK_ARCHIVE_BUCKET")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let slack_signing_secret = env::var("SLACK_SIGNING_SECRET")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let slack_event_sinks =
+            EventSink::parse_list(&env::var("SLACK_EVENT_SINKS").unwrap_or_else(|_| "log".into()))
+                .map_err(|reason| {
+                    SettingError::InvalidEnvVar("SLACK_EVENT_SINKS".into(), reason)
+                })?;
+        let slack_command_scopes =
+            parse_slack_command_scopes(&env::var("SLACK_COMMAND_SCOPES").unwrap_or_default())
+                .map_err(|reason| {
+                    SettingError::InvalidEnvVar("SLACK_COMMAND_SCOPES".into(), reason)
+                })?;
+        let slack_command_users =
+            parse_slack_command_users(&env::var("SLACK_COMMAND_USERS").unwrap_or_default())
+                .map_err(|reason| {
+                    SettingError::InvalidEnvVar("SLACK_COMMAND_USERS".into(), reason)
+                })?;
+        let hook_routes = parse_routes(&env::var("HOOK_ROUTES").unwrap_or_else(|_| "[]".into()))
+            .map_err(|reason| SettingError::InvalidEnvVar("HOOK_ROUTES".into(), reason))?;
+        let github_webhook_secret = env::var("GITHUB_WEBHOOK_SECRET")
+            .ok()
+            .filter(|v| !v.is_empty());
+        let slack_templates = match env::var("SLACK_TEMPLATES") {
+            Ok(raw) if !raw.trim().is_empty() => parse_templates(&raw)
+                .map_err(|reason| SettingError::InvalidEnvVar("SLACK_TEMPLATES".into(), reason))?,
+            _ => HashMap::new(),
+        };
+        let slack_template_s3 = env::var("SLACK_TEMPLATE_S3")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(|raw| TemplateLocation::parse(&raw))
+            .transpose()
+            .map_err(|reason| SettingError::InvalidEnvVar("SLACK_TEMPLATE_S3".into(), reason))?;
+
+        let auth_disabled = parse_bool_env("API_HUB_AUTH_DISABLED", false);
+        let api_keys = match env::var("API_HUB_API_KEYS") {
+            Ok(raw) => parse_api_keys(&raw)
+                .map_err(|reason| SettingError::InvalidEnvVar("API_HUB_API_KEYS".into(), reason))?,
+            Err(_) if auth_disabled => Vec::new(),
+            Err(_) => return Err(SettingError::MissingEnvVar("API_HUB_API_KEYS".into())),
+        };
+
+        let default_pool = PoolConfig::default();
+        let http_pool = PoolConfig {
+            max_idle_per_host: parse_number_env(
+                "HTTP_POOL_MAX_IDLE_PER_HOST",
+                default_pool.max_idle_per_host,
+            )?,
+            idle_timeout: Duration::from_secs(parse_number_env(
+                "HTTP_POOL_IDLE_TIMEOUT_SECS",
+                default_pool.idle_timeout.as_secs(),
+            )?),
+            max_per_host: parse_number_env("HTTP_POOL_MAX_PER_HOST", default_pool.max_per_host)?,
+        };
+        if http_pool.max_per_host == 0 {
+            return Err(SettingError::InvalidEnvVar(
+                "HTTP_POOL_MAX_PER_HOST".into(),
+                "must be at least 1".into(),
+            ));
+        }
+
+        let default_timeouts = TimeoutConfig::default();
+        let http_timeouts = TimeoutConfig {
+            connect: parse_timeout_env("HTTP_CONNECT_TIMEOUT_SECS", default_timeouts.connect)?,
+            tls_handshake: parse_timeout_env(
+                "HTTP_TLS_HANDSHAKE_TIMEOUT_SECS",
+                default_timeouts.tls_handshake,
+            )?,
+            first_byte: parse_timeout_env(
+                "HTTP_FIRST_BYTE_TIMEOUT_SECS",
+                default_timeouts.first_byte,
+            )?,
+            total: parse_timeout_env("HTTP_TOTAL_TIMEOUT_SECS", default_timeouts.total)?,
+            read_idle: parse_timeout_env(
+                "HTTP_READ_IDLE_TIMEOUT_SECS",
+                default_timeouts.read_idle,
+            )?,
+        };
+
+        let default_retry = RetryPolicy::default();
+        let http_retry = RetryPolicy {
+            max_attempts: parse_number_env("HTTP_RETRY_MAX_ATTEMPTS", default_retry.max_attempts)?,
+            base_delay: Duration::from_millis(parse_number_env(
+                "HTTP_RETRY_BASE_DELAY_MS",
+                default_retry.base_delay.as_millis() as u64,
+            )?),
+            max_delay: Duration::from_millis(parse_number_env(
+                "HTTP_RETRY_MAX_DELAY_MS",
+                default_retry.max_delay.as_millis() as u64,
+            )?),
+            deadline: parse_timeout_env("HTTP_RETRY_DEADLINE_SECS", default_retry.deadline)?,
+        };
+        if http_retry.max_attempts == 0 {
+            return Err(SettingError::InvalidEnvVar(
+                "HTTP_RETRY_MAX_ATTEMPTS".into(),
+                "must be at least 1".into(),
+            ));
+        }
 
         Ok(Self {
             slack_bot_token,
             slack_api_base_url,
+            slack_channel_cache_ttl,
+            slack_upload_content_types,
+            multipart_limits,
             s3_access_key_id,
             s3_secret_access_key,
             s3_region,
             s3_endpoint,
             s3_use_path_style,
             s3_session_token,
+            s3_transfer_max_bytes,
+            slack_archive_bucket,
+            slack_signing_secret,
+            slack_event_sinks,
+            slack_command_scopes,
+            slack_command_users,
+            hook_routes,
+            slack_templates,
+            slack_template_s3,
+            github_webhook_secret,
+            api_keys,
+            auth_disabled,
+            http_pool,
+            http_timeouts,
+            http_retry,
         })
     }
 }
@@ -68,3 +275,128 @@ fn parse_bool_env(name: &str, default_value: bool) -> bool {
         Err(_) => default_value,
     }
 }
+
+fn parse_number_env<T: FromStr>(name: &str, default_value: T) -> Result<T, SettingError> {
+    match env::var(name) {
+        Ok(v) => v.trim().parse().map_err(|_| {
+            SettingError::InvalidEnvVar(name.into(), format!("'{v}' is not a valid number"))
+        }),
+        Err(_) => Ok(default_value),
+    }
+}
+
+fn parse_timeout_env(name: &str, default_value: Duration) -> Result<Duration, SettingError> {
+    let secs = parse_number_env(name, default_value.as_secs())?;
+    if secs == 0 {
+        return Err(SettingError::InvalidEnvVar(
+            name.into(),
+            "must be at least 1 second".into(),
+        ));
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+/// `name:sha256hex:scope scope ...` をカンマ区切りで並べた形式をパースする。
+/// スコープの省略はエラーにする。すべて許可する場合は `*` を明示する。
+pub fn parse_api_keys(raw: &str) -> Result<Vec<ApiKeySetting>, String> {
+    let mut keys: Vec<ApiKeySetting> = Vec::new();
+
+    for entry in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let mut fields = entry.splitn(3, ':');
+        let name = fields.next().unwrap_or_default().trim();
+        let digest_hex = fields
+            .next()
+            .ok_or_else(|| format!("entry '{entry}' must be in 'name:sha256hex' form"))?;
+        if name.is_empty() {
+            return Err(format!("entry '{entry}' has an empty name"));
+        }
+        if keys.iter().any(|key| key.name == name) {
+            return Err(format!("duplicate key name '{name}'"));
+        }
+        let key_sha256 = decode_hex(digest_hex.trim())
+            .and_then(|digest| <[u8; 32]>::try_from(digest).ok())
+            .ok_or_else(|| format!("key '{name}' must be a 64-character hex SHA-256 digest"))?;
+        let scopes = match fields.next() {
+            Some(raw_scopes) => {
+                let scopes = raw_scopes
+                    .split_whitespace()
+                    .map(Scope::parse)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|reason| format!("key '{name}': {reason}"))?;
+                if scopes.is_empty() {
+                    return Err(format!("key '{name}' has an empty scope list"));
+                }
+                scopes
+            }
+            None => {
+                return Err(format!(
+                    "key '{name}' has no scopes; use '*' to allow everything"
+                ));
+            }
+        };
+        keys.push(ApiKeySetting {
+            name: name.to_string(),
+            key_sha256,
+            scopes,
+        });
+    }
+
+    if keys.is_empty() {
+        return Err("at least one key must be configured".to_string());
+    }
+
+    Ok(keys)
+}
+
+/// `SLACK_COMMAND_SCOPES` を読む。Slack のユーザーなら誰でも使えるため、S3 と `*` は許可しない。
+pub fn parse_slack_command_scopes(raw: &str) -> Result<Vec<Scope>, String> {
+    let scopes = raw
+        .split_whitespace()
+        .map(Scope::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(scope) = scopes.iter().find(|scope| {
+        matches!(
+            scope,
+            Scope::All | Scope::S3Read(_) | Scope::S3Write(_) | Scope::S3Admin
+        )
+    }) {
+        return Err(format!(
+            "scope '{scope}' is shared by every Slack user; grant it per user in SLACK_COMMAND_USERS"
+        ));
+    }
+    Ok(scopes)
+}
+
+/// `SLACK_COMMAND_USERS` を読む。`id:scope scope,...` 形式で、`id` は Slack のユーザー ID かワークスペース ID。
+pub fn parse_slack_command_users(raw: &str) -> Result<Vec<SlackCommandUser>, String> {
+    let mut users: Vec<SlackCommandUser> = Vec::new();
+
+    for entry in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (id, raw_scopes) = entry
+            .split_once(':')
+            .ok_or_else(|| format!("entry '{entry}' must be in 'id:scope' form"))?;
+        let id = id.trim();
+        if !id.starts_with(['U', 'W', 'T']) {
+            return Err(format!(
+                "'{id}' must be a Slack user ID (U…/W…) or team ID (T…)"
+            ));
+        }
+        if users.iter().any(|user| user.id == id) {
+            return Err(format!("duplicate Slack ID '{id}'"));
+        }
+        let scopes = raw_scopes
+            .split_whitespace()
+            .map(Scope::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|reason| format!("'{id}': {reason}"))?;
+        if scopes.is_empty() {
+            return Err(format!("'{id}' has an empty scope list"));
+        }
+        users.push(SlackCommandUser {
+            id: id.to_string(),
+            scopes,
+        });
+    }
+
+    Ok(users)
+}
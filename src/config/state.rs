@@ -1,8 +1,78 @@
 use crate::config::settings::Settings;
 use crate::http_client::HttpClient;
+use crate::service::hook_threads::HookThreads;
+use crate::service::slack_channels::ChannelDirectory;
+use crate::service::slack_export::ExportJobs;
+use crate::service::slack_interactions::SlackHandlerRegistry;
+use crate::service::slack_rate_limiter::SlackRateLimiter;
 
 #[derive(Clone)]
 pub struct AppState {
     pub settings: Settings,
     pub client: HttpClient,
+    pub slack_rate_limiter: SlackRateLimiter,
+    pub slack_channels: ChannelDirectory,
+    /// `/slack/commands` と `/slack/interactivity` のハンドラー
+    pub slack_handlers: SlackHandlerRegistry,
+    /// `/hooks/*` の通知をまとめるスレッド
+    pub hook_threads: HookThreads,
+    /// `/slack/export` のジョブ
+    pub export_jobs: ExportJobs,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// ハンドラーのテスト用の状態。Slack / S3 の接続先は到達しないローカルアドレスにする。
+    pub(crate) fn for_test() -> Self {
+        use crate::{
+            http_client::{PoolConfig, RetryPolicy, TimeoutConfig},
+            multipart::MultipartLimits,
+            service::{
+                file_type::ContentTypePolicy, hook_threads::THREAD_TTL,
+                slack_export::EXPORT_JOB_TTL,
+            },
+        };
+        use std::{collections::HashMap, time::Duration};
+
+        let settings = Settings {
+            slack_bot_token: "xoxb-test".to_string(),
+            slack_api_base_url: "http://127.0.0.1:9/api".to_string(),
+            slack_channel_cache_ttl: Duration::from_secs(300),
+            slack_upload_content_types: ContentTypePolicy {
+                allowed: Vec::new(),
+                denied: Vec::new(),
+            },
+            multipart_limits: MultipartLimits::default(),
+            s3_access_key_id: "test".to_string(),
+            s3_secret_access_key: "test".to_string(),
+            s3_region: "us-east-1".to_string(),
+            s3_endpoint: Some("http://127.0.0.1:9".to_string()),
+            s3_use_path_style: true,
+            s3_session_token: None,
+            s3_transfer_max_bytes: 100 * 1024 * 1024,
+            slack_archive_bucket: Some("archive".to_string()),
+            slack_signing_secret: None,
+            slack_event_sinks: Vec::new(),
+            slack_command_scopes: Vec::new(),
+            slack_command_users: Vec::new(),
+            hook_routes: Vec::new(),
+            slack_templates: HashMap::new(),
+            slack_template_s3: None,
+            github_webhook_secret: None,
+            api_keys: Vec::new(),
+            auth_disabled: true,
+            http_pool: PoolConfig::default(),
+            http_timeouts: TimeoutConfig::default(),
+            http_retry: RetryPolicy::default(),
+        };
+        Self {
+            client: HttpClient::new(),
+            slack_rate_limiter: SlackRateLimiter::new(),
+            slack_channels: ChannelDirectory::new(settings.slack_channel_cache_ttl),
+            slack_handlers: SlackHandlerRegistry::new(),
+            hook_threads: HookThreads::new(THREAD_TTL),
+            export_jobs: ExportJobs::new(EXPORT_JOB_TTL),
+            settings,
+        }
+    }
 }
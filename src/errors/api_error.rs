@@ -1,27 +1,50 @@
 use shiguredo_http11::Response;
 use tracing::error;
 
+use crate::multipart::MultipartError;
+
 #[derive(Debug)]
 pub enum ApiError {
     BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
     NotFound(String),
     MethodNotAllowed(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
     InternalServerError(String),
+    GatewayTimeout(String),
 }
 
 impl std::fmt::Display for ApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::BadRequest(message) => write!(f, "Bad Request: {message}"),
+            Self::Unauthorized(message) => write!(f, "Unauthorized: {message}"),
+            Self::Forbidden(message) => write!(f, "Forbidden: {message}"),
             Self::NotFound(message) => write!(f, "Not Found: {message}"),
             Self::MethodNotAllowed(message) => write!(f, "Method Not Allowed: {message}"),
+            Self::PayloadTooLarge(message) => write!(f, "Payload Too Large: {message}"),
+            Self::UnsupportedMediaType(message) => write!(f, "Unsupported Media Type: {message}"),
             Self::InternalServerError(_) => write!(f, "Internal Server Error"),
+            Self::GatewayTimeout(message) => write!(f, "Gateway Timeout: {message}"),
         }
     }
 }
 
 impl std::error::Error for ApiError {}
 
+/// サイズ超過は 413、それ以外は 400 として返す。
+impl From<MultipartError> for ApiError {
+    fn from(e: MultipartError) -> Self {
+        if e.is_too_large() {
+            Self::PayloadTooLarge(e.to_string())
+        } else {
+            Self::BadRequest(e.to_string())
+        }
+    }
+}
+
 fn problem_details_json(status_code: u16, detail: impl Into<String>) -> String {
     let title = reason_phrase(status_code).to_string();
     let detail = detail.into();
@@ -54,6 +77,26 @@ impl ApiError {
                 );
                 problem_details_response(400, message.clone())
             }
+            ApiError::Unauthorized(ref message) => {
+                error!(
+                    error_type = "unauthorized",
+                    message = %message,
+                    status = 401,
+                    "API error occurred"
+                );
+                let mut response = problem_details_response(401, message.clone());
+                response.add_header("WWW-Authenticate", "Bearer");
+                response
+            }
+            ApiError::Forbidden(ref message) => {
+                error!(
+                    error_type = "forbidden",
+                    message = %message,
+                    status = 403,
+                    "API error occurred"
+                );
+                problem_details_response(403, message.clone())
+            }
             ApiError::NotFound(ref message) => {
                 error!(
                     error_type = "not_found",
@@ -72,6 +115,24 @@ impl ApiError {
                 );
                 problem_details_response(405, message.clone())
             }
+            ApiError::PayloadTooLarge(ref message) => {
+                error!(
+                    error_type = "payload_too_large",
+                    message = %message,
+                    status = 413,
+                    "API error occurred"
+                );
+                problem_details_response(413, message.clone())
+            }
+            ApiError::UnsupportedMediaType(ref message) => {
+                error!(
+                    error_type = "unsupported_media_type",
+                    message = %message,
+                    status = 415,
+                    "API error occurred"
+                );
+                problem_details_response(415, message.clone())
+            }
             ApiError::InternalServerError(ref details) => {
                 error!(
                     error_type = "internal_server_error",
@@ -81,6 +142,15 @@ impl ApiError {
                 );
                 problem_details_response(500, "Internal Server Error")
             }
+            ApiError::GatewayTimeout(ref message) => {
+                error!(
+                    error_type = "gateway_timeout",
+                    message = %message,
+                    status = 504,
+                    "API error occurred"
+                );
+                problem_details_response(504, message.clone())
+            }
         }
     }
 }
@@ -90,10 +160,15 @@ pub fn reason_phrase(status_code: u16) -> &'static str {
         200 => "OK",
         204 => "No Content",
         400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
         404 => "Not Found",
         405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
         500 => "Internal Server Error",
         502 => "Bad Gateway",
+        504 => "Gateway Timeout",
         _ => "Unknown Error",
     }
 }
@@ -0,0 +1,177 @@
+use shiguredo_http11::Response;
+use tokio::time::Instant;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    auth::{CallerIdentity, github_signature, slack_post},
+    config::state::AppState,
+    errors::api_error::ApiError,
+    handlers::slack_handler::{
+        map_slack_error_to_api_error, resolve_authorized_channel, with_channel,
+    },
+    service::{
+        hook_messages::{self, HookNotification},
+        hook_routes::{self, HookSource},
+        slack_service::{self, MessageRef},
+    },
+};
+
+/// Alertmanager の Webhook receiver。アラートごとに fingerprint 単位のスレッドへ投稿する。
+#[instrument(skip(app_state, caller, body))]
+pub async fn alertmanager(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let notifications = hook_messages::from_alertmanager(utf8_body(body)?)?;
+    deliver(app_state, caller, HookSource::Alertmanager, notifications).await
+}
+
+/// Grafana Alerting の Webhook contact point。
+#[instrument(skip(app_state, caller, body))]
+pub async fn grafana(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let notifications = hook_messages::from_grafana(utf8_body(body)?)?;
+    deliver(app_state, caller, HookSource::Grafana, notifications).await
+}
+
+/// GitHub の Webhook。`GITHUB_WEBHOOK_SECRET` があれば `X-Hub-Signature-256` を検証する。
+#[instrument(skip(app_state, caller, headers, body))]
+pub async fn github(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    if let Some(secret) = &app_state.settings.github_webhook_secret {
+        github_signature::verify(secret, headers, body)?;
+    }
+    let event = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("x-github-event"))
+        .map(|(_, value)| value.trim())
+        .ok_or_else(|| ApiError::BadRequest("Missing X-GitHub-Event".to_string()))?;
+
+    let notifications = hook_messages::from_github(event, utf8_body(body)?)?;
+    deliver(app_state, caller, HookSource::Github, notifications).await
+}
+
+fn utf8_body(body: &[u8]) -> Result<&str, ApiError> {
+    std::str::from_utf8(body)
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))
+}
+
+/// 通知を `HOOK_ROUTES` で振り分けて投稿する。
+///
+/// どのルールにも一致しない通知は捨てて `unrouted` に数える (送信元が再送し続けないよう 200 を返す)。
+async fn deliver(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    source: HookSource,
+    notifications: Vec<HookNotification>,
+) -> Result<Response, ApiError> {
+    let mut routed = Vec::new();
+    let mut unrouted = 0;
+    for notification in notifications {
+        match hook_routes::route(
+            &app_state.settings.hook_routes,
+            source,
+            &notification.fields,
+        ) {
+            Some(channel) => routed.push((channel.to_string(), notification)),
+            None => unrouted += 1,
+        }
+    }
+    // 一部だけ投稿されないよう、投稿前にすべての送り先を解決して確認する
+    for (channel, _) in &routed {
+        resolve_authorized_channel(app_state, caller, channel, slack_post).await?;
+    }
+    if unrouted > 0 {
+        warn!(source = %source, unrouted, "No hook route matched notifications");
+    }
+
+    let mut delivered = Vec::new();
+    for (channel, notification) in routed {
+        let thread_ts = notification
+            .group_key
+            .as_deref()
+            .and_then(|key| app_state.hook_threads.get(key, &channel, Instant::now()));
+        let mut message = notification.message;
+        if thread_ts.is_some() {
+            message.thread_ts = thread_ts.clone();
+            message.reply_broadcast = notification.broadcast.then_some(true);
+        }
+
+        let message = &message;
+        let posted = with_channel(
+            app_state,
+            caller,
+            &channel,
+            slack_post,
+            |resolved| async move {
+                slack_service::post_message(
+                    &app_state.client,
+                    &app_state.slack_rate_limiter,
+                    &app_state.settings.slack_bot_token,
+                    &app_state.settings.slack_api_base_url,
+                    &resolved,
+                    message,
+                )
+                .await
+            },
+        )
+        .await
+        .map_err(|e| {
+            error!(
+                error = %e,
+                source = %source,
+                channel = %channel,
+                "Failed to post hook notification to Slack"
+            );
+            map_slack_error_to_api_error(e)
+        })?;
+
+        if let (Some(key), None) = (&notification.group_key, &thread_ts) {
+            app_state
+                .hook_threads
+                .insert(key, &channel, &posted.ts, Instant::now());
+        }
+        delivered.push((posted, thread_ts));
+    }
+
+    info!(
+        source = %source,
+        delivered = delivered.len(),
+        unrouted,
+        "Delivered hook notifications"
+    );
+    Ok(deliver_response(&delivered, unrouted))
+}
+
+fn deliver_response(delivered: &[(MessageRef, Option<String>)], unrouted: usize) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member(
+                "delivered",
+                nojson::array(|f| {
+                    for (posted, thread_ts) in delivered {
+                        f.element(nojson::object(|f| {
+                            f.member("channel", &posted.channel)?;
+                            f.member("ts", &posted.ts)?;
+                            f.member("thread_ts", thread_ts)
+                        }))?;
+                    }
+                    Ok(())
+                }),
+            )?;
+            f.member("unrouted", unrouted)
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
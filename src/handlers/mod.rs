@@ -1,4 +1,6 @@
 pub mod health_handler;
+pub mod hooks_handler;
 pub mod openapi_handler;
 pub mod s3_handler;
+pub mod slack_events_handler;
 pub mod slack_handler;
@@ -1,9 +1,11 @@
 use shiguredo_http11::Response;
 
 use crate::{
+    auth::{CallerIdentity, scope::RequiredScope},
     config::state::AppState,
     errors::api_error::ApiError,
     http_client::HttpResponseStream,
+    multipart::{self, MultipartLimits},
     service::s3_service::{
         self, AbortMultipartUploadInput, CompleteMultipartUploadInput, CompletePartInput,
         CreateBucketInput, CreateMultipartUploadInput, DeleteBucketInput,
@@ -28,6 +30,14 @@ pub struct PutObjectBase64Request {
     pub content_type: Option<String>,
 }
 
+/// `POST /s3/put_object_form` のフォーム。`key` を省略した場合はファイル名を使う。
+pub struct PutObjectFormRequest {
+    pub bucket: String,
+    pub key: String,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
 pub struct GetObjectRequest {
     pub bucket: String,
     pub key: String,
@@ -232,6 +242,55 @@ fn parse_put_object_base64_request(body: &str) -> Result<PutObjectBase64Request,
     })
 }
 
+fn parse_put_object_form_request(
+    content_type: &str,
+    body: &[u8],
+    limits: MultipartLimits,
+) -> Result<PutObjectFormRequest, ApiError> {
+    let boundary = multipart::boundary(content_type)?;
+    let mut bucket = None;
+    let mut key = None;
+    let mut object_content_type = None;
+    let mut file = None;
+
+    for part in multipart::parse(body, &boundary, limits)? {
+        if part.is_file() {
+            if file.is_some() {
+                return Err(ApiError::BadRequest(
+                    "Exactly one file part is allowed".to_string(),
+                ));
+            }
+            file = Some(part);
+            continue;
+        }
+        let value = part.text()?.trim().to_string();
+        match part.name.as_str() {
+            "bucket" => bucket = Some(value),
+            "key" => key = Some(value),
+            "content_type" => object_content_type = Some(value),
+            _ => {}
+        }
+    }
+
+    let file = file.ok_or_else(|| ApiError::BadRequest("A file part is required".to_string()))?;
+    let bucket = bucket
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("Missing required field 'bucket'".to_string()))?;
+    let key = key
+        .filter(|v| !v.is_empty())
+        .or_else(|| file.file_name.clone().filter(|v| !v.is_empty()))
+        .ok_or_else(|| ApiError::BadRequest("Missing required field 'key'".to_string()))?;
+
+    Ok(PutObjectFormRequest {
+        bucket,
+        key,
+        content_type: object_content_type
+            .filter(|v| !v.is_empty())
+            .or(file.content_type),
+        body: file.data,
+    })
+}
+
 fn parse_get_object_request(body: &str) -> Result<GetObjectRequest, ApiError> {
     let json = parse_json_body(body)?;
     let root = json.value();
@@ -418,9 +477,17 @@ fn parse_bucket_request(body: &str) -> Result<BucketRequest, ApiError> {
     })
 }
 
-pub async fn put_object_base64(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn put_object_base64(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_put_object_base64_request(&body)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let body = s3_service::decode_base64_payload(&payload.file_data_base64)?;
     let result = s3_service::put_object(
         &app_state.client,
@@ -430,15 +497,53 @@ pub async fn put_object_base64(app_state: &AppState, body: &[u8]) -> Result<Resp
             key: payload.key,
             body,
             content_type: payload.content_type,
+            metadata: Vec::new(),
+        },
+    )
+    .await?;
+    Ok(json_response(result))
+}
+
+pub async fn put_object_form(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let content_type = get_header_value(headers, "content-type")
+        .ok_or_else(|| ApiError::BadRequest("Missing Content-Type header".to_string()))?;
+    let payload =
+        parse_put_object_form_request(content_type, body, app_state.settings.multipart_limits)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
+    let result = s3_service::put_object(
+        &app_state.client,
+        &app_state.settings,
+        PutObjectInput {
+            bucket: payload.bucket,
+            key: payload.key,
+            body: payload.body,
+            content_type: payload.content_type,
+            metadata: Vec::new(),
         },
     )
     .await?;
     Ok(json_response(result))
 }
 
-pub async fn get_object_base64(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn get_object_base64(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_get_object_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::get_object(
         &app_state.client,
         &app_state.settings,
@@ -451,9 +556,17 @@ pub async fn get_object_base64(app_state: &AppState, body: &[u8]) -> Result<Resp
     Ok(json_response(result))
 }
 
-pub async fn head_object(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn head_object(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_head_object_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::head_object(
         &app_state.client,
         &app_state.settings,
@@ -466,9 +579,17 @@ pub async fn head_object(app_state: &AppState, body: &[u8]) -> Result<Response,
     Ok(json_response(result))
 }
 
-pub async fn delete_object(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn delete_object(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_delete_object_request(&body)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::delete_object(
         &app_state.client,
         &app_state.settings,
@@ -481,9 +602,19 @@ pub async fn delete_object(app_state: &AppState, body: &[u8]) -> Result<Response
     Ok(json_response(result))
 }
 
-pub async fn delete_objects(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn delete_objects(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_delete_objects_request(&body)?;
+    for object in &payload.objects {
+        caller.require(RequiredScope::S3Write {
+            bucket: payload.bucket.clone(),
+            key: Some(object.key.clone()),
+        })?;
+    }
     let objects = payload
         .objects
         .into_iter()
@@ -505,9 +636,17 @@ pub async fn delete_objects(app_state: &AppState, body: &[u8]) -> Result<Respons
     Ok(json_response(result))
 }
 
-pub async fn list_objects_v2(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn list_objects_v2(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_list_objects_v2_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.prefix.clone().unwrap_or_default()),
+    })?;
     let result = s3_service::list_objects_v2(
         &app_state.client,
         &app_state.settings,
@@ -525,10 +664,15 @@ pub async fn list_objects_v2(app_state: &AppState, body: &[u8]) -> Result<Respon
 
 pub async fn create_multipart_upload(
     app_state: &AppState,
+    caller: &CallerIdentity,
     body: &[u8],
 ) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_create_multipart_upload_request(&body)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::create_multipart_upload(
         &app_state.client,
         &app_state.settings,
@@ -542,9 +686,17 @@ pub async fn create_multipart_upload(
     Ok(json_response(result))
 }
 
-pub async fn upload_part_base64(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn upload_part_base64(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_upload_part_base64_request(&body)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let body = s3_service::decode_base64_payload(&payload.part_data_base64)?;
     let result = s3_service::upload_part(
         &app_state.client,
@@ -563,10 +715,15 @@ pub async fn upload_part_base64(app_state: &AppState, body: &[u8]) -> Result<Res
 
 pub async fn complete_multipart_upload(
     app_state: &AppState,
+    caller: &CallerIdentity,
     body: &[u8],
 ) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_complete_multipart_upload_request(&body)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let parts = payload
         .parts
         .into_iter()
@@ -592,10 +749,15 @@ pub async fn complete_multipart_upload(
 
 pub async fn abort_multipart_upload(
     app_state: &AppState,
+    caller: &CallerIdentity,
     body: &[u8],
 ) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_abort_multipart_upload_request(&body)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::abort_multipart_upload(
         &app_state.client,
         &app_state.settings,
@@ -609,9 +771,17 @@ pub async fn abort_multipart_upload(
     Ok(json_response(result))
 }
 
-pub async fn list_parts(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn list_parts(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_list_parts_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::list_parts(
         &app_state.client,
         &app_state.settings,
@@ -629,10 +799,15 @@ pub async fn list_parts(app_state: &AppState, body: &[u8]) -> Result<Response, A
 
 pub async fn list_multipart_uploads(
     app_state: &AppState,
+    caller: &CallerIdentity,
     body: &[u8],
 ) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_list_multipart_uploads_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.prefix.clone().unwrap_or_default()),
+    })?;
     let result = s3_service::list_multipart_uploads(
         &app_state.client,
         &app_state.settings,
@@ -649,9 +824,17 @@ pub async fn list_multipart_uploads(
     Ok(json_response(result))
 }
 
-pub async fn presigned_get_object(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn presigned_get_object(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_presigned_object_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::presigned_get(
         &app_state.settings,
         PresignedObjectInput {
@@ -663,9 +846,17 @@ pub async fn presigned_get_object(app_state: &AppState, body: &[u8]) -> Result<R
     Ok(json_response(result))
 }
 
-pub async fn presigned_put_object(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn presigned_put_object(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_presigned_object_request(&body)?;
+    caller.require(RequiredScope::S3Write {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
     let result = s3_service::presigned_put(
         &app_state.settings,
         PresignedObjectInput {
@@ -677,14 +868,23 @@ pub async fn presigned_put_object(app_state: &AppState, body: &[u8]) -> Result<R
     Ok(json_response(result))
 }
 
-pub async fn list_buckets(app_state: &AppState) -> Result<Response, ApiError> {
+pub async fn list_buckets(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+) -> Result<Response, ApiError> {
+    caller.require(RequiredScope::S3Admin)?;
     let result = s3_service::list_buckets(&app_state.client, &app_state.settings).await?;
     Ok(json_response(result))
 }
 
-pub async fn create_bucket(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn create_bucket(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_bucket_request(&body)?;
+    caller.require(RequiredScope::S3Admin)?;
     let result = s3_service::create_bucket(
         &app_state.client,
         &app_state.settings,
@@ -696,9 +896,17 @@ pub async fn create_bucket(app_state: &AppState, body: &[u8]) -> Result<Response
     Ok(json_response(result))
 }
 
-pub async fn head_bucket(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn head_bucket(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_bucket_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: None,
+    })?;
     let result = s3_service::head_bucket(
         &app_state.client,
         &app_state.settings,
@@ -710,9 +918,14 @@ pub async fn head_bucket(app_state: &AppState, body: &[u8]) -> Result<Response,
     Ok(json_response(result))
 }
 
-pub async fn delete_bucket(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+pub async fn delete_bucket(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = body_to_utf8(body)?;
     let payload = parse_bucket_request(&body)?;
+    caller.require(RequiredScope::S3Admin)?;
     let result = s3_service::delete_bucket(
         &app_state.client,
         &app_state.settings,
@@ -726,9 +939,14 @@ pub async fn delete_bucket(app_state: &AppState, body: &[u8]) -> Result<Response
 
 pub async fn preview_object(
     app_state: &AppState,
+    caller: &CallerIdentity,
     bucket: String,
     key: String,
 ) -> Result<Response, ApiError> {
+    caller.require(RequiredScope::S3Read {
+        bucket: bucket.clone(),
+        key: Some(key.clone()),
+    })?;
     let s3_response = s3_service::get_object_proxy(
         &app_state.client,
         &app_state.settings,
@@ -754,10 +972,15 @@ pub async fn preview_object(
 
 pub async fn preview_object_stream(
     app_state: &AppState,
+    caller: &CallerIdentity,
     bucket: String,
     key: String,
     request_headers: &[(String, String)],
 ) -> Result<PreviewObjectStreamResponse, ApiError> {
+    caller.require(RequiredScope::S3Read {
+        bucket: bucket.clone(),
+        key: Some(key.clone()),
+    })?;
     let s3_response = s3_service::get_object_proxy_stream(
         &app_state.client,
         &app_state.settings,
@@ -927,8 +1150,9 @@ pub fn s3_preflight() -> Response {
 mod tests {
     use super::{
         extract_forward_header, parse_delete_objects_request, parse_list_objects_v2_request,
-        parse_presigned_object_request,
+        parse_presigned_object_request, parse_put_object_form_request,
     };
+    use crate::{errors::api_error::ApiError, multipart::MultipartLimits};
     use proptest::{prelude::ProptestConfig, prop_assert_eq, proptest};
 
     #[test]
@@ -987,6 +1211,51 @@ mod tests {
         assert_eq!(parsed.expires_in_secs, None);
     }
 
+    #[test]
+    fn parse_put_object_form_defaults_key_to_file_name() {
+        let body = b"--b\r\n\
+Content-Disposition: form-data; name=\"bucket\"\r\n\r\nreports\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"a.csv\"\r\n\
+Content-Type: text/csv\r\n\r\na,b\r\n\
+--b--\r\n";
+
+        let parsed = parse_put_object_form_request(
+            "multipart/form-data; boundary=b",
+            body,
+            MultipartLimits::default(),
+        )
+        .expect("form should parse");
+
+        assert_eq!(parsed.bucket, "reports");
+        assert_eq!(parsed.key, "a.csv");
+        assert_eq!(parsed.content_type.as_deref(), Some("text/csv"));
+        assert_eq!(parsed.body, b"a,b");
+    }
+
+    #[test]
+    fn parse_put_object_form_maps_limit_and_boundary_errors() {
+        let body = b"--b\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\n\r\n0123456789\r\n\
+--b--\r\n";
+        let limits = MultipartLimits {
+            max_part_size: 4,
+            max_total_size: 1024,
+        };
+        assert!(matches!(
+            parse_put_object_form_request("multipart/form-data; boundary=b", body, limits),
+            Err(ApiError::PayloadTooLarge(_))
+        ));
+        assert!(matches!(
+            parse_put_object_form_request(
+                "multipart/form-data; boundary=other",
+                body,
+                MultipartLimits::default()
+            ),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(64))]
 
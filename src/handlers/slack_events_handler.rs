@@ -0,0 +1,247 @@
+use shiguredo_http11::Response;
+use tracing::{info, instrument, warn};
+
+use crate::{
+    auth::{CallerIdentity, slack_signature},
+    config::state::AppState,
+    errors::api_error::ApiError,
+    service::{
+        slack_events::{self, SlackEnvelope},
+        slack_interactions::{self, BoxFuture, Interaction, Reply, ReplyMessage, SlashCommand},
+        slack_schedule,
+    },
+};
+
+/// Slack から届いたリクエストの署名を検証する。Signing Secret が未設定なら 404。
+fn verify_slack_request(
+    app_state: &AppState,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<(), ApiError> {
+    let signing_secret = app_state
+        .settings
+        .slack_signing_secret
+        .as_deref()
+        .ok_or_else(|| ApiError::NotFound("SLACK_SIGNING_SECRET is not configured".to_string()))?;
+    slack_signature::verify(signing_secret, headers, body, slack_schedule::unix_now())
+}
+
+/// Events API の受け口。`event_callback` は転送先への配送を待たずに 200 を返す (Slack は 3 秒以内の応答を求める)。
+#[instrument(skip(app_state, headers, body))]
+pub async fn receive_event(
+    app_state: &AppState,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    verify_slack_request(app_state, headers, body)?;
+
+    let body = std::str::from_utf8(body)
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    match slack_events::parse_envelope(body)? {
+        SlackEnvelope::UrlVerification { challenge } => {
+            let body =
+                nojson::json(|f| f.object(|f| f.member("challenge", &challenge))).to_string();
+            Ok(Response::new(200, "OK")
+                .header("Content-Type", "application/json")
+                .body(body.into_bytes()))
+        }
+        SlackEnvelope::EventCallback(event) => {
+            let retry_num = headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("x-slack-retry-num"))
+                .map(|(_, value)| value.clone());
+            info!(
+                event_id = %event.event_id,
+                event_type = %event.event_type,
+                retry_num = ?retry_num,
+                "Accepted Slack event"
+            );
+            let app_state = app_state.clone();
+            let body = body.as_bytes().to_vec();
+            tokio::spawn(async move {
+                slack_events::dispatch(&app_state.client, &app_state.settings, &event, &body).await;
+            });
+            Ok(Response::new(200, "OK"))
+        }
+        SlackEnvelope::Other(envelope_type) => {
+            info!(envelope_type = %envelope_type, "Ignored Slack callback");
+            Ok(Response::new(200, "OK"))
+        }
+    }
+}
+
+/// スラッシュコマンドの受け口。コマンド名で登録されたハンドラーを呼ぶ。
+///
+/// ハンドラーのエラーや未登録のコマンドは 200 のエフェメラルメッセージとして返す (Slack は 200 以外を汎用エラーとして表示する)。
+#[instrument(skip(app_state, headers, body))]
+pub async fn receive_command(
+    app_state: &AppState,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    verify_slack_request(app_state, headers, body)?;
+
+    let body = std::str::from_utf8(body)
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let command = SlashCommand::from_form(&slack_interactions::parse_form(body)?)?;
+    info!(
+        command = %command.command,
+        user_id = %command.user_id,
+        channel_id = %command.channel_id,
+        "Received Slack command"
+    );
+
+    let Some(handler) = app_state.slack_handlers.command(&command.command) else {
+        return Ok(reply_response(&ReplyMessage::ephemeral(format!(
+            "Unknown command {}",
+            command.command
+        ))));
+    };
+    let caller = slack_caller(app_state, &command.user_id, command.team_id.as_deref());
+    match handler.handle(app_state, &caller, &command).await {
+        Ok(Reply::Now(message)) => Ok(reply_response(&message)),
+        Ok(Reply::Later(message)) => {
+            spawn_reply(app_state, command.response_url, message);
+            Ok(Response::new(200, "OK"))
+        }
+        Err(e) => {
+            warn!(error = %e, command = %command.command, "Slack command failed");
+            Ok(reply_response(&ReplyMessage::ephemeral(e.to_string())))
+        }
+    }
+}
+
+/// ボタンなどの操作の受け口。`action_id` / `callback_id` で登録されたハンドラーを呼び、応答は `response_url` に投稿する。
+#[instrument(skip(app_state, headers, body))]
+pub async fn receive_interaction(
+    app_state: &AppState,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    verify_slack_request(app_state, headers, body)?;
+
+    let body = std::str::from_utf8(body)
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let interaction = Interaction::from_form(&slack_interactions::parse_form(body)?)?;
+    info!(
+        interaction_type = %interaction.interaction_type,
+        handler_id = %interaction.handler_id,
+        user_id = %interaction.user_id,
+        "Received Slack interaction"
+    );
+
+    let Some(handler) = app_state
+        .slack_handlers
+        .interaction(&interaction.handler_id)
+    else {
+        info!(handler_id = %interaction.handler_id, "No handler for Slack interaction");
+        return Ok(Response::new(200, "OK"));
+    };
+    let caller = slack_caller(
+        app_state,
+        &interaction.user_id,
+        interaction.team_id.as_deref(),
+    );
+    let message: BoxFuture<'static, ReplyMessage> =
+        match handler.handle(app_state, &caller, &interaction).await {
+            Ok(Reply::Now(message)) => Box::pin(async move { message }),
+            Ok(Reply::Later(message)) => message,
+            Err(e) => {
+                warn!(error = %e, handler_id = %interaction.handler_id, "Slack interaction failed");
+                let message = ReplyMessage::ephemeral(e.to_string());
+                Box::pin(async move { message })
+            }
+        };
+    if let Some(response_url) = interaction.response_url {
+        spawn_reply(app_state, response_url, message);
+    }
+    Ok(Response::new(200, "OK"))
+}
+
+/// ハンドラーを呼ぶときの呼び出し元。共通のスコープに、ユーザーとワークスペースに設定したスコープを加える。
+fn slack_caller(app_state: &AppState, user_id: &str, team_id: Option<&str>) -> CallerIdentity {
+    let settings = &app_state.settings;
+    let scopes = settings
+        .slack_command_users
+        .iter()
+        .filter(|user| user.id == user_id || Some(user.id.as_str()) == team_id)
+        .flat_map(|user| user.scopes.iter().cloned())
+        .chain(settings.slack_command_scopes.iter().cloned())
+        .collect();
+    CallerIdentity {
+        key_name: format!("slack:{user_id}"),
+        scopes,
+    }
+}
+
+fn reply_response(message: &ReplyMessage) -> Response {
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(message.to_json().into_bytes())
+}
+
+/// 応答ができたら `response_url` に投稿する。
+fn spawn_reply(
+    app_state: &AppState,
+    response_url: String,
+    message: BoxFuture<'static, ReplyMessage>,
+) {
+    let client = app_state.client.clone();
+    tokio::spawn(async move {
+        let message = message.await;
+        if let Err(e) =
+            slack_interactions::post_to_response_url(&client, &response_url, &message).await
+        {
+            warn!(error = %e, "Failed to post to Slack response_url");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slack_caller;
+    use crate::{
+        auth::scope::{RequiredScope, Scope},
+        config::{settings::parse_slack_command_users, state::AppState},
+    };
+
+    #[test]
+    fn s3_scopes_are_granted_only_to_configured_users_and_teams() {
+        let mut app_state = AppState::for_test();
+        app_state.settings.slack_command_scopes = vec![Scope::SlackRead("*".to_string())];
+        app_state.settings.slack_command_users =
+            parse_slack_command_users("U1:s3:read:reports/*, T2:s3:read:public/*").expect("users");
+        let reports = || RequiredScope::S3Read {
+            bucket: "reports".to_string(),
+            key: Some("daily.csv".to_string()),
+        };
+
+        assert!(
+            slack_caller(&app_state, "U1", None)
+                .require(reports())
+                .is_ok()
+        );
+        assert!(
+            slack_caller(&app_state, "U9", Some("T1"))
+                .require(reports())
+                .is_err()
+        );
+        let member = slack_caller(&app_state, "U9", Some("T2"));
+        assert_eq!(member.key_name, "slack:U9");
+        assert!(
+            member
+                .require(RequiredScope::S3Read {
+                    bucket: "public".to_string(),
+                    key: None,
+                })
+                .is_ok()
+        );
+        assert!(
+            member
+                .require(RequiredScope::SlackRead {
+                    channel: "C1".to_string(),
+                })
+                .is_ok()
+        );
+    }
+}
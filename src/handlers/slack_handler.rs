@@ -1,13 +1,142 @@
 use shiguredo_http11::Response;
 use shiguredo_http11::uri::percent_decode;
-use std::time::Instant;
+use std::{
+    borrow::Cow, collections::HashMap, error::Error as StdError, future::Future, time::Instant,
+};
 use tracing::{debug, error, info, instrument, warn};
 
-use crate::{config::state::AppState, errors::api_error::ApiError, service::slack_service};
+use crate::{
+    auth::{
+        CallerIdentity, authorize_channel, header_value, scope::RequiredScope, slack_post,
+        slack_read,
+    },
+    config::state::AppState,
+    errors::api_error::ApiError,
+    http_client::{HttpClientError, StreamedBody},
+    multipart::{self, MultipartLimits},
+    service::{
+        file_type::{self, ContentTypePolicy},
+        json::{
+            get_optional_bool, get_optional_json, get_optional_string, get_required_string,
+            optional_member,
+        },
+        s3_service::{self, GetObjectInput, PutObjectInput},
+        slack_archive,
+        slack_export::{
+            self, ExportJob, ExportStatus, ExportSummary, ExportTarget, ExportedMessage,
+            HistoryRange, JsonlUpload,
+        },
+        slack_items::{self, Bookmark, NewBookmark, PinnedMessage, Reaction},
+        slack_markdown::{self, MessageFormat},
+        slack_message::SlackMessage,
+        slack_schedule::{self, ScheduledMessage},
+        slack_service::{
+            self, FileListFilter, FileToUpload, MessageRef, SlackApiError, SlackFile,
+            UploadPlacement, UploadedFile,
+        },
+        slack_template,
+    },
+};
 
 pub struct SlackMessageRequest {
     pub channel: String,
-    pub text: String,
+    pub message: SlackMessage,
+    pub format: MessageFormat,
+}
+
+/// `POST /slack/message/template` の本文
+pub struct SlackTemplateRequest {
+    pub channel: String,
+    pub template: String,
+    /// テンプレートに渡す値。省略時は `{}`
+    pub context: nojson::RawJsonOwned,
+    pub thread_ts: Option<String>,
+    pub reply_broadcast: Option<bool>,
+}
+
+pub struct SlackEphemeralRequest {
+    pub channel: String,
+    pub user: String,
+    pub message: SlackMessage,
+}
+
+/// DM の宛先。メールアドレスは `users.lookupByEmail` でユーザー ID に解決する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DmRecipient {
+    User(String),
+    Email(String),
+}
+
+impl DmRecipient {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::User(user) | Self::Email(user) => user,
+        }
+    }
+}
+
+pub struct SlackDmRequest {
+    pub recipient: DmRecipient,
+    pub message: SlackMessage,
+}
+
+pub struct SlackUpdateRequest {
+    pub target: MessageRef,
+    pub message: SlackMessage,
+}
+
+pub struct SlackScheduleRequest {
+    pub channel: String,
+    /// 検証済みの投稿予定時刻 (Unix 秒)
+    pub post_at: i64,
+    pub message: SlackMessage,
+}
+
+pub struct ScheduledListRequest {
+    pub channel: String,
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+pub struct ScheduledMessageTarget {
+    pub channel: String,
+    pub scheduled_message_id: String,
+}
+
+/// `/slack/reactions` の本文。`name` はコロンを外した絵文字名
+pub struct SlackReactionRequest {
+    pub target: MessageRef,
+    pub name: String,
+}
+
+/// `/slack/pins/list` と `/slack/bookmarks/list` の本文
+pub struct SlackChannelRequest {
+    pub channel: String,
+}
+
+/// `POST /slack/bookmarks` の本文
+pub struct SlackBookmarkRequest {
+    pub channel: String,
+    pub bookmark: NewBookmark,
+}
+
+/// `DELETE /slack/bookmarks` の本文
+pub struct SlackBookmarkTarget {
+    pub channel: String,
+    pub bookmark_id: String,
+}
+
+/// `POST /slack/export` の本文。`thread_ts` を指定するとそのスレッドだけを書き出す。
+pub struct SlackExportRequest {
+    pub channel: String,
+    pub thread_ts: Option<String>,
+    pub range: HistoryRange,
+    /// チャンネル全体のときにスレッドの返信も含める
+    pub include_replies: bool,
+    /// 省略時は `SLACK_ARCHIVE_BUCKET`
+    pub bucket: Option<String>,
+    /// 省略時は `slack_export::export_key`
+    pub key: Option<String>,
 }
 
 struct UploadQuery {
@@ -15,29 +144,631 @@ struct UploadQuery {
     pub file_name: Option<String>,
 }
 
+/// `POST /slack/upload/from_s3` の本文
+pub struct SlackUploadFromS3Request {
+    pub bucket: String,
+    pub key: String,
+    pub channel: String,
+    pub title: Option<String>,
+    pub file_name: Option<String>,
+}
+
+/// アーカイブ対象。`file_id` 1件か、`files.list` の絞り込み条件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveTarget {
+    File {
+        file_id: String,
+        channel: Option<String>,
+    },
+    List(FileListFilter),
+}
+
+/// `POST /slack/archive/files` の本文。`bucket` を省略した場合は `SLACK_ARCHIVE_BUCKET` を使う。
+pub struct SlackArchiveRequest {
+    pub target: ArchiveTarget,
+    pub bucket: Option<String>,
+}
+
+/// 単一ファイルのアップロード。生の本文とクエリ、または `multipart/form-data` のフォームから読む。
+struct SingleUpload<'a> {
+    query: UploadQuery,
+    content_type: Option<String>,
+    data: Cow<'a, [u8]>,
+}
+
 fn parse_message_request(body: &str) -> Result<SlackMessageRequest, ApiError> {
     let json = nojson::RawJson::parse(body)
         .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
     let root = json.value();
-    let channel: String = root
-        .to_member("channel")
-        .map_err(|e| ApiError::BadRequest(e.to_string()))?
-        .required()
-        .map_err(|e| ApiError::BadRequest(e.to_string()))?
-        .try_into()
-        .map_err(|e| ApiError::BadRequest(format!("Invalid 'channel': {e}")))?;
-    let text: String = root
-        .to_member("text")
-        .map_err(|e| ApiError::BadRequest(e.to_string()))?
-        .required()
-        .map_err(|e| ApiError::BadRequest(e.to_string()))?
-        .try_into()
-        .map_err(|e| ApiError::BadRequest(format!("Invalid 'text': {e}")))?;
-
-    Ok(SlackMessageRequest { channel, text })
-}
-
-fn parse_upload_query(raw_query: Option<&str>) -> Result<UploadQuery, ApiError> {
+    let channel = get_required_string(root, "channel")?;
+    let message = parse_message_fields(root)?;
+    let format = match get_optional_string(root, "format")? {
+        Some(raw) => MessageFormat::parse(&raw).ok_or_else(|| {
+            ApiError::BadRequest(
+                "'format' must be 'mrkdwn', 'markdown' or 'markdown_blocks'".to_string(),
+            )
+        })?,
+        None => MessageFormat::default(),
+    };
+    if format != MessageFormat::Mrkdwn
+        && (message.text.is_none() || message.blocks.is_some() || message.attachments.is_some())
+    {
+        return Err(ApiError::BadRequest(
+            "'format' requires 'text' and cannot be combined with 'blocks' or 'attachments'"
+                .to_string(),
+        ));
+    }
+
+    Ok(SlackMessageRequest {
+        channel,
+        message,
+        format,
+    })
+}
+
+fn parse_template_request(body: &str) -> Result<SlackTemplateRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let template = get_required_string(root, "template")?;
+    if !slack_template::is_valid_template_name(&template) {
+        return Err(ApiError::BadRequest(
+            "'template' must consist of letters, digits, '_' and '-'".to_string(),
+        ));
+    }
+    let context = match optional_member(root, "context")? {
+        Some(context) if context.kind() == nojson::JsonValueKind::Object => {
+            context.extract().into_owned()
+        }
+        Some(_) => {
+            return Err(ApiError::BadRequest(
+                "'context' must be a JSON object".to_string(),
+            ));
+        }
+        None => nojson::RawJsonOwned::parse("{}")
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?,
+    };
+
+    Ok(SlackTemplateRequest {
+        channel: get_required_string(root, "channel")?,
+        template,
+        context,
+        thread_ts: get_optional_string(root, "thread_ts")?,
+        reply_broadcast: get_optional_bool(root, "reply_broadcast")?,
+    })
+}
+
+fn parse_ephemeral_request(body: &str) -> Result<SlackEphemeralRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    Ok(SlackEphemeralRequest {
+        channel: get_required_string(root, "channel")?,
+        user: get_required_string(root, "user")?,
+        message: parse_message_fields(root)?,
+    })
+}
+
+fn parse_upload_from_s3_request(body: &str) -> Result<SlackUploadFromS3Request, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    Ok(SlackUploadFromS3Request {
+        bucket: get_required_string(root, "bucket")?,
+        key: get_required_string(root, "key")?,
+        channel: get_required_string(root, "channel")?,
+        title: get_optional_string(root, "title")?.filter(|v| !v.is_empty()),
+        file_name: get_optional_string(root, "file_name")?.filter(|v| !v.is_empty()),
+    })
+}
+
+/// `file_id` を指定した場合、`channel` はキーに使う共有先の指定としてのみ扱い、他の絞り込み条件は受け付けない。
+/// `file_id` が無い場合は `channel` が必須。
+fn parse_archive_request(body: &str) -> Result<SlackArchiveRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let file_id = get_optional_string(root, "file_id")?.filter(|v| !v.is_empty());
+    let channel = get_optional_string(root, "channel")?.filter(|v| !v.is_empty());
+    let filter = FileListFilter {
+        channel,
+        ts_from: get_optional_timestamp(root, "ts_from")?,
+        ts_to: get_optional_timestamp(root, "ts_to")?,
+        types: get_optional_string(root, "types")?.filter(|v| !v.is_empty()),
+    };
+
+    let target = match file_id {
+        Some(file_id) => {
+            if filter.ts_from.is_some() || filter.ts_to.is_some() || filter.types.is_some() {
+                return Err(ApiError::BadRequest(
+                    "'ts_from', 'ts_to' and 'types' cannot be combined with 'file_id'".to_string(),
+                ));
+            }
+            ArchiveTarget::File {
+                file_id,
+                channel: filter.channel,
+            }
+        }
+        None if filter.channel.is_none() => {
+            return Err(ApiError::BadRequest(
+                "Either 'file_id' or 'channel' is required".to_string(),
+            ));
+        }
+        None => ArchiveTarget::List(filter),
+    };
+
+    Ok(SlackArchiveRequest {
+        target,
+        bucket: get_optional_string(root, "bucket")?.filter(|v| !v.is_empty()),
+    })
+}
+
+/// 宛先は `user` (ユーザー ID) と `email` のどちらか一方のみを受け付ける。
+fn parse_dm_request(body: &str) -> Result<SlackDmRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let user = get_optional_string(root, "user")?.filter(|v| !v.is_empty());
+    let email = get_optional_string(root, "email")?.filter(|v| !v.is_empty());
+    let recipient = match (user, email) {
+        (Some(user), None) => DmRecipient::User(user),
+        (None, Some(email)) if email.contains('@') => DmRecipient::Email(email),
+        (None, Some(_)) => {
+            return Err(ApiError::BadRequest(
+                "'email' must be an email address".to_string(),
+            ));
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "Exactly one of 'user' or 'email' is required".to_string(),
+            ));
+        }
+    };
+
+    Ok(SlackDmRequest {
+        recipient,
+        message: parse_message_fields(root)?,
+    })
+}
+
+/// `chat.postMessage` / `chat.scheduleMessage` 共通の本文と表示オプションを読み、検証する。
+fn parse_message_fields(root: nojson::RawJsonValue<'_, '_>) -> Result<SlackMessage, ApiError> {
+    let message = read_message_fields(root)?;
+    message.validate().map_err(ApiError::BadRequest)?;
+    Ok(message)
+}
+
+fn read_message_fields(root: nojson::RawJsonValue<'_, '_>) -> Result<SlackMessage, ApiError> {
+    Ok(SlackMessage {
+        text: get_optional_string(root, "text")?,
+        blocks: get_optional_json(root, "blocks")?,
+        attachments: get_optional_json(root, "attachments")?,
+        unfurl_links: get_optional_bool(root, "unfurl_links")?,
+        unfurl_media: get_optional_bool(root, "unfurl_media")?,
+        mrkdwn: get_optional_bool(root, "mrkdwn")?,
+        username: get_optional_string(root, "username")?,
+        icon_emoji: get_optional_string(root, "icon_emoji")?,
+        thread_ts: get_optional_string(root, "thread_ts")?,
+        reply_broadcast: get_optional_bool(root, "reply_broadcast")?,
+    })
+}
+
+/// テンプレートを描画してメッセージにする。描画・検証のエラーはテンプレート名を付けて 400 にする。
+fn render_template_message(
+    template: &nojson::RawJsonOwned,
+    request: &SlackTemplateRequest,
+) -> Result<SlackMessage, ApiError> {
+    let name = &request.template;
+    let rendered = slack_template::render(template.value(), request.context.value())
+        .map_err(|e| ApiError::BadRequest(format!("Template '{name}' failed to render: {e}")))?;
+    let json = nojson::RawJson::parse(&rendered)
+        .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+    let mut message = read_message_fields(json.value()).map_err(|e| {
+        ApiError::BadRequest(format!(
+            "Template '{name}' rendered an invalid message: {e}"
+        ))
+    })?;
+    if request.thread_ts.is_some() {
+        message.thread_ts = request.thread_ts.clone();
+    }
+    if request.reply_broadcast.is_some() {
+        message.reply_broadcast = request.reply_broadcast;
+    }
+    message.validate().map_err(|e| {
+        ApiError::BadRequest(format!(
+            "Template '{name}' rendered an invalid message: {e}"
+        ))
+    })?;
+    Ok(message)
+}
+
+fn parse_schedule_request(body: &str, now: i64) -> Result<SlackScheduleRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let channel = get_required_string(root, "channel")?;
+    let post_at = parse_post_at(root)?;
+    let post_at = slack_schedule::validate_post_at(post_at, now).map_err(ApiError::BadRequest)?;
+    let message = parse_message_fields(root)?;
+
+    Ok(SlackScheduleRequest {
+        channel,
+        post_at,
+        message,
+    })
+}
+
+/// `post_at` は Unix 秒の数値、または RFC 3339 形式の文字列を受け付ける。
+fn parse_post_at(root: nojson::RawJsonValue<'_, '_>) -> Result<i64, ApiError> {
+    get_optional_timestamp(root, "post_at")?
+        .ok_or_else(|| ApiError::BadRequest("Missing required member 'post_at'".to_string()))
+}
+
+/// Unix 秒 (数値) または RFC 3339 の文字列を Unix 秒として読む。
+fn get_optional_timestamp(
+    root: nojson::RawJsonValue<'_, '_>,
+    name: &str,
+) -> Result<Option<i64>, ApiError> {
+    let invalid = || {
+        ApiError::BadRequest(format!(
+            "'{name}' must be Unix seconds or an RFC 3339 timestamp"
+        ))
+    };
+    let Some(value) = optional_member(root, name)? else {
+        return Ok(None);
+    };
+    if value.kind().is_string() {
+        let raw = value.to_unquoted_string_str().map_err(|_| invalid())?;
+        slack_schedule::parse_rfc3339(raw.trim())
+            .map(Some)
+            .ok_or_else(invalid)
+    } else {
+        i64::try_from(value).map(Some).map_err(|_| invalid())
+    }
+}
+
+fn parse_scheduled_list_request(body: &str) -> Result<ScheduledListRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let limit = optional_member(root, "limit")?
+        .map(|v| {
+            u32::try_from(v).map_err(|e| ApiError::BadRequest(format!("Invalid 'limit': {e}")))
+        })
+        .transpose()?;
+    Ok(ScheduledListRequest {
+        channel: get_required_string(root, "channel")?,
+        cursor: get_optional_string(root, "cursor")?,
+        limit,
+    })
+}
+
+fn parse_scheduled_message_target(body: &str) -> Result<ScheduledMessageTarget, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    Ok(ScheduledMessageTarget {
+        channel: get_required_string(root, "channel")?,
+        scheduled_message_id: get_required_string(root, "scheduled_message_id")?,
+    })
+}
+
+/// `chat.update` が受け付ける本文 (`text` / `blocks` / `attachments`) のみを読む。
+fn parse_update_request(body: &str) -> Result<SlackUpdateRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let target = MessageRef {
+        channel: get_required_string(root, "channel")?,
+        ts: get_required_string(root, "ts")?,
+    };
+    let message = SlackMessage {
+        text: get_optional_string(root, "text")?,
+        blocks: get_optional_json(root, "blocks")?,
+        attachments: get_optional_json(root, "attachments")?,
+        ..SlackMessage::default()
+    };
+    message.validate().map_err(ApiError::BadRequest)?;
+
+    Ok(SlackUpdateRequest { target, message })
+}
+
+fn parse_message_target(body: &str) -> Result<MessageRef, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    Ok(MessageRef {
+        channel: get_required_string(root, "channel")?,
+        ts: get_required_string(root, "ts")?,
+    })
+}
+
+/// リアクション・ピン留めの対象。`ts` の形式も確認する。
+fn parse_item_target(root: nojson::RawJsonValue<'_, '_>) -> Result<MessageRef, ApiError> {
+    let target = MessageRef {
+        channel: get_required_string(root, "channel")?,
+        ts: get_required_string(root, "ts")?,
+    };
+    slack_items::validate_message_ts(&target.ts).map_err(ApiError::BadRequest)?;
+    Ok(target)
+}
+
+fn parse_reaction_request(body: &str) -> Result<SlackReactionRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let target = parse_item_target(root)?;
+    let name = slack_items::normalize_reaction_name(&get_required_string(root, "name")?)
+        .map_err(ApiError::BadRequest)?;
+    Ok(SlackReactionRequest { target, name })
+}
+
+fn parse_pin_request(body: &str) -> Result<MessageRef, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    parse_item_target(json.value())
+}
+
+fn parse_channel_request(body: &str) -> Result<SlackChannelRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    Ok(SlackChannelRequest {
+        channel: get_required_string(json.value(), "channel")?,
+    })
+}
+
+fn parse_bookmark_request(body: &str) -> Result<SlackBookmarkRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let title = get_required_string(root, "title")?;
+    if title.trim().is_empty() {
+        return Err(ApiError::BadRequest(
+            "'title' must not be empty".to_string(),
+        ));
+    }
+    let link = get_required_string(root, "link")?;
+    slack_items::validate_bookmark_link(&link).map_err(ApiError::BadRequest)?;
+    let emoji = get_optional_string(root, "emoji")?
+        .map(|raw| {
+            slack_items::normalize_reaction_name(&raw)
+                .map(|name| format!(":{name}:"))
+                .map_err(|_| {
+                    ApiError::BadRequest(format!("'emoji' is not a valid emoji name: '{raw}'"))
+                })
+        })
+        .transpose()?;
+    Ok(SlackBookmarkRequest {
+        channel: get_required_string(root, "channel")?,
+        bookmark: NewBookmark { title, link, emoji },
+    })
+}
+
+fn parse_export_request(body: &str) -> Result<SlackExportRequest, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let thread_ts = get_optional_string(root, "thread_ts")?.filter(|v| !v.is_empty());
+    if let Some(thread_ts) = &thread_ts {
+        slack_items::validate_message_ts(thread_ts)
+            .map_err(|e| ApiError::BadRequest(e.replace("'ts'", "'thread_ts'")))?;
+    }
+    let range = HistoryRange {
+        oldest: get_optional_timestamp(root, "oldest")?,
+        latest: get_optional_timestamp(root, "latest")?,
+    };
+    let include_replies = get_optional_bool(root, "include_replies")?.unwrap_or(false);
+    if thread_ts.is_some() && (range != HistoryRange::default() || include_replies) {
+        return Err(ApiError::BadRequest(
+            "'oldest', 'latest' and 'include_replies' cannot be combined with 'thread_ts'"
+                .to_string(),
+        ));
+    }
+    if let (Some(oldest), Some(latest)) = (range.oldest, range.latest)
+        && oldest > latest
+    {
+        return Err(ApiError::BadRequest(
+            "'oldest' must not be after 'latest'".to_string(),
+        ));
+    }
+
+    Ok(SlackExportRequest {
+        channel: get_required_string(root, "channel")?,
+        thread_ts,
+        range,
+        include_replies,
+        bucket: get_optional_string(root, "bucket")?.filter(|v| !v.is_empty()),
+        key: get_optional_string(root, "key")?.filter(|v| !v.is_empty()),
+    })
+}
+
+/// `POST /slack/export/status` の本文から `job_id` を読む。
+fn parse_export_status_request(body: &str) -> Result<String, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    get_required_string(json.value(), "job_id")
+}
+
+fn parse_bookmark_target(body: &str) -> Result<SlackBookmarkTarget, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    Ok(SlackBookmarkTarget {
+        channel: get_required_string(root, "channel")?,
+        bookmark_id: get_required_string(root, "bookmark_id")?,
+    })
+}
+
+/// S3 から読み込むテンプレートの上限 (バイト)
+const MAX_TEMPLATE_BYTES: usize = 256 * 1024;
+
+/// `files.completeUploadExternal` で1メッセージにまとめられるファイル数の上限
+const MAX_MULTIPART_FILES: usize = 10;
+
+/// `POST /slack/upload/files` のフォーム。`title` / `alt_text` は N 番目の値を N 番目のファイルに割り当てる。
+pub struct MultipartUploadRequest {
+    pub channel: String,
+    pub placement: UploadPlacement,
+    pub files: Vec<FileToUpload>,
+}
+
+fn parse_multipart_upload(
+    content_type: &str,
+    body: &[u8],
+    policy: &ContentTypePolicy,
+    limits: MultipartLimits,
+) -> Result<MultipartUploadRequest, ApiError> {
+    let parts = parse_multipart_body(content_type, body, limits)?;
+
+    let mut channel = None;
+    let mut placement = UploadPlacement::default();
+    let mut titles = Vec::new();
+    let mut alt_texts = Vec::new();
+    let mut files = Vec::new();
+
+    for part in parts {
+        if part.is_file() {
+            files.push(part);
+            continue;
+        }
+        let value = part.text()?.trim().to_string();
+        match part.name.as_str() {
+            "channel" => channel = Some(value),
+            "initial_comment" => placement.initial_comment = Some(value).filter(|v| !v.is_empty()),
+            "thread_ts" => placement.thread_ts = Some(value).filter(|v| !v.is_empty()),
+            "title" => titles.push(value),
+            "alt_text" => alt_texts.push(value),
+            _ => {}
+        }
+    }
+
+    let channel = channel
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| ApiError::BadRequest("Missing required field 'channel'".to_string()))?;
+    if files.is_empty() {
+        return Err(ApiError::BadRequest(
+            "At least one file part is required".to_string(),
+        ));
+    }
+    if files.len() > MAX_MULTIPART_FILES {
+        return Err(ApiError::BadRequest(format!(
+            "At most {MAX_MULTIPART_FILES} files can be uploaded at once"
+        )));
+    }
+
+    let mut titles = titles.into_iter();
+    let mut alt_texts = alt_texts.into_iter();
+    let files = files
+        .into_iter()
+        .enumerate()
+        .map(|(index, part)| {
+            if part.data.is_empty() {
+                return Err(ApiError::BadRequest(format!(
+                    "File part #{} must not be empty",
+                    index + 1
+                )));
+            }
+            let declared = part
+                .content_type
+                .as_deref()
+                .map(normalize_media_type)
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+            let (_, extension) = check_upload_content(&declared, &part.data, policy)?;
+            let file_name = part
+                .file_name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| {
+                    build_default_name(&format!("file-upload-{}", index + 1), extension)
+                });
+            let title = titles
+                .next()
+                .filter(|title| !title.is_empty())
+                .unwrap_or_else(|| file_name.clone());
+            Ok(FileToUpload {
+                file_name,
+                title,
+                alt_text: alt_texts.next().filter(|alt| !alt.is_empty()),
+                data: part.data,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok(MultipartUploadRequest {
+        channel,
+        placement,
+        files,
+    })
+}
+
+fn parse_multipart_body(
+    content_type: &str,
+    body: &[u8],
+    limits: MultipartLimits,
+) -> Result<Vec<multipart::Part>, ApiError> {
+    let boundary = multipart::boundary(content_type)?;
+    multipart::parse(body, &boundary, limits).map_err(ApiError::from)
+}
+
+/// `Content-Type` が `multipart/form-data` なら `channel` / `file_name` フィールドと1つのファイルパートを、
+/// それ以外は本文そのものとクエリを読む。フォームのフィールドはクエリより優先する。
+fn read_single_upload<'a>(
+    raw_query: Option<&str>,
+    headers: &[(String, String)],
+    body: &'a [u8],
+    limits: MultipartLimits,
+) -> Result<SingleUpload<'a>, ApiError> {
+    let (mut channel, mut file_name) = parse_upload_query_fields(raw_query)?;
+    let Some(raw_content_type) =
+        header_value(headers, "content-type").filter(|value| is_multipart(value))
+    else {
+        let content_type = match header_value(headers, "content-type") {
+            Some(_) => Some(content_type(headers)?),
+            None => None,
+        };
+        return Ok(SingleUpload {
+            query: build_upload_query(channel, file_name)?,
+            content_type,
+            data: Cow::Borrowed(body),
+        });
+    };
+
+    let mut file = None;
+    for part in parse_multipart_body(raw_content_type, body, limits)? {
+        if part.is_file() {
+            if file.is_some() {
+                return Err(ApiError::BadRequest(
+                    "Exactly one file part is allowed; use /slack/upload/files for several files"
+                        .to_string(),
+                ));
+            }
+            file = Some(part);
+            continue;
+        }
+        let value = part.text()?.to_string();
+        match part.name.as_str() {
+            "channel" => channel = Some(value),
+            "file_name" => file_name = Some(value),
+            _ => {}
+        }
+    }
+    let file = file.ok_or_else(|| ApiError::BadRequest("A file part is required".to_string()))?;
+
+    Ok(SingleUpload {
+        query: build_upload_query(channel, file_name.or(file.file_name))?,
+        content_type: file.content_type.as_deref().map(normalize_media_type),
+        data: Cow::Owned(file.data),
+    })
+}
+
+fn is_multipart(content_type: &str) -> bool {
+    normalize_media_type(content_type) == "multipart/form-data"
+}
+
+/// クエリの `channel` / `file_name` を読む。
+fn parse_upload_query_fields(
+    raw_query: Option<&str>,
+) -> Result<(Option<String>, Option<String>), ApiError> {
     let query = raw_query.unwrap_or_default();
     let mut channel = None;
     let mut file_name = None;
@@ -54,6 +785,13 @@ fn parse_upload_query(raw_query: Option<&str>) -> Result<UploadQuery, ApiError>
         }
     }
 
+    Ok((channel, file_name))
+}
+
+fn build_upload_query(
+    channel: Option<String>,
+    file_name: Option<String>,
+) -> Result<UploadQuery, ApiError> {
     let channel = channel.filter(|c| !c.trim().is_empty()).ok_or_else(|| {
         ApiError::BadRequest("Missing required query parameter 'channel'".to_string())
     })?;
@@ -75,52 +813,26 @@ fn decode_query_component(value: &str) -> Result<String, ApiError> {
     percent_decode(&replaced).map_err(|_| ApiError::BadRequest("Invalid query string".to_string()))
 }
 
-fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
-    headers
-        .iter()
-        .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
-        .map(|(_, value)| value.as_str())
-}
-
 fn content_type(headers: &[(String, String)]) -> Result<String, ApiError> {
     let content_type = header_value(headers, "content-type")
-        .ok_or_else(|| ApiError::BadRequest("Missing Content-Type header".to_string()))?
-        .trim();
+        .ok_or_else(|| ApiError::BadRequest("Missing Content-Type header".to_string()))?;
+    Ok(normalize_media_type(content_type))
+}
 
-    let normalized = content_type
+/// パラメータを除いて小文字にする。
+fn normalize_media_type(content_type: &str) -> String {
+    content_type
         .split(';')
         .next()
         .unwrap_or(content_type)
         .trim()
-        .to_ascii_lowercase();
-
-    Ok(normalized)
-}
-
-fn looks_like_pdf(data: &[u8]) -> bool {
-    data.starts_with(b"%PDF-")
-}
-
-fn looks_like_png(data: &[u8]) -> bool {
-    data.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'])
-}
-
-fn looks_like_jpeg(data: &[u8]) -> bool {
-    data.starts_with(&[0xFF, 0xD8, 0xFF])
-}
-
-fn looks_like_gif(data: &[u8]) -> bool {
-    data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")
-}
-
-fn looks_like_webp(data: &[u8]) -> bool {
-    data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP"
+        .to_ascii_lowercase()
 }
 
 fn ensure_image_content(content_type: &str, data: &[u8]) -> Result<&'static str, ApiError> {
     let extension = match content_type {
         "image/png" => {
-            if !looks_like_png(data) {
+            if !file_type::PNG.matches(data) {
                 return Err(ApiError::BadRequest(
                     "Body is not a valid PNG image".to_string(),
                 ));
@@ -128,7 +840,7 @@ fn ensure_image_content(content_type: &str, data: &[u8]) -> Result<&'static str,
             "png"
         }
         "image/jpeg" => {
-            if !looks_like_jpeg(data) {
+            if !file_type::JPEG.matches(data) {
                 return Err(ApiError::BadRequest(
                     "Body is not a valid JPEG image".to_string(),
                 ));
@@ -136,7 +848,7 @@ fn ensure_image_content(content_type: &str, data: &[u8]) -> Result<&'static str,
             "jpg"
         }
         "image/gif" => {
-            if !looks_like_gif(data) {
+            if !file_type::GIF.matches(data) {
                 return Err(ApiError::BadRequest(
                     "Body is not a valid GIF image".to_string(),
                 ));
@@ -144,7 +856,7 @@ fn ensure_image_content(content_type: &str, data: &[u8]) -> Result<&'static str,
             "gif"
         }
         "image/webp" => {
-            if !looks_like_webp(data) {
+            if !file_type::WEBP.matches(data) {
                 return Err(ApiError::BadRequest(
                     "Body is not a valid WEBP image".to_string(),
                 ));
@@ -162,187 +874,2930 @@ fn ensure_image_content(content_type: &str, data: &[u8]) -> Result<&'static str,
     Ok(extension)
 }
 
+/// 任意形式のアップロードを検証し、実際の `Content-Type` と既定の拡張子を返す。
+/// 許可・拒否リストは宣言された形式と、宣言に関わらず中身から推定した形式の両方に適用する。
+fn check_upload_content(
+    content_type: &str,
+    data: &[u8],
+    policy: &ContentTypePolicy,
+) -> Result<(String, &'static str), ApiError> {
+    let (detected, extension) =
+        file_type::check_content(content_type, data).map_err(ApiError::BadRequest)?;
+    let sniffed = file_type::sniff(data).map(|file_type| file_type.content_type);
+    for candidate in [Some(content_type), Some(detected.as_str()), sniffed]
+        .into_iter()
+        .flatten()
+    {
+        if !policy.permits(candidate) {
+            return Err(ApiError::UnsupportedMediaType(format!(
+                "Content-Type '{candidate}' is not allowed for upload"
+            )));
+        }
+    }
+    Ok((detected, extension))
+}
+
 fn build_default_name(prefix: &str, extension: &str) -> String {
     format!("{prefix}.{extension}")
 }
 
-fn validate_file_not_empty(file_data: &[u8]) -> Result<(), ApiError> {
-    if file_data.is_empty() {
-        return Err(ApiError::BadRequest(
-            "Request body must not be empty".to_string(),
-        ));
+/// S3 キーの最後の要素をファイル名にする。拡張子が無ければ形式から補う。
+fn file_name_from_key(key: &str, extension: &str) -> String {
+    let base = key.rsplit('/').next().unwrap_or_default();
+    if base.is_empty() {
+        build_default_name("file-upload", extension)
+    } else if base.contains('.') || extension == "bin" {
+        base.to_string()
+    } else {
+        build_default_name(base, extension)
+    }
+}
+
+fn validate_file_not_empty(file_data: &[u8]) -> Result<(), ApiError> {
+    if file_data.is_empty() {
+        return Err(ApiError::BadRequest(
+            "Request body must not be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn json_string_response(value: String) -> Response {
+    let body = nojson::json(|f| f.value(&value)).to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+/// `channel` の `#name` を ID に解決し、スコープを確認して返す。
+pub(crate) async fn resolve_authorized_channel(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    channel: &str,
+    required: fn(String) -> RequiredScope,
+) -> Result<String, ApiError> {
+    let resolved = slack_service::resolve_channel(
+        &app_state.client,
+        &app_state.slack_rate_limiter,
+        &app_state.settings.slack_bot_token,
+        &app_state.settings.slack_api_base_url,
+        &app_state.slack_channels,
+        channel,
+        false,
+    )
+    .await
+    .map_err(map_slack_error_to_api_error)?;
+    authorize_channel(caller, channel, &resolved, required)?;
+    Ok(resolved)
+}
+
+/// `channel` の `#name` を ID に解決し、スコープを確認してから `call` を実行する。
+/// 一覧を取り直して再実行する場合も、新しい ID で確認し直す。
+pub(crate) async fn with_channel<T, F, Fut>(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    channel: &str,
+    required: fn(String) -> RequiredScope,
+    call: F,
+) -> Result<T, Box<dyn StdError>>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn StdError>>>,
+{
+    let call = &call;
+    slack_service::with_resolved_channel(
+        &app_state.client,
+        &app_state.slack_rate_limiter,
+        &app_state.settings.slack_bot_token,
+        &app_state.settings.slack_api_base_url,
+        &app_state.slack_channels,
+        channel,
+        |resolved| {
+            let authorized = authorize_channel(caller, channel, &resolved, required);
+            async move {
+                authorized?;
+                call(resolved).await
+            }
+        },
+    )
+    .await
+}
+
+/// Slack API 呼び出しのタイムアウトは 504、対象が見つからないエラーは 404、それ以外は 500 として返す。
+pub(crate) fn map_slack_error_to_api_error(e: Box<dyn StdError>) -> ApiError {
+    // `with_channel` のスコープ確認の結果はそのまま返す
+    let e = match e.downcast::<ApiError>() {
+        Ok(api_error) => return *api_error,
+        Err(e) => e,
+    };
+    if let Some(HttpClientError::Timeout(_)) = e.downcast_ref::<HttpClientError>() {
+        return ApiError::GatewayTimeout(format!("Slack API request timed out: {e}"));
+    }
+    match e.downcast_ref::<SlackApiError>().map(|e| e.code.as_str()) {
+        Some(
+            "channel_not_found"
+            | "message_not_found"
+            | "user_not_found"
+            | "users_not_found"
+            | "file_not_found"
+            | "invalid_scheduled_message_id"
+            | "no_reaction"
+            | "not_pinned"
+            | "not_found",
+        ) => ApiError::NotFound(format!("Slack API error: {e}")),
+        _ => ApiError::InternalServerError(e.to_string()),
+    }
+}
+
+fn message_ref_response(message: &MessageRef) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &message.channel)?;
+            f.member("ts", &message.ts)
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn post_message(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_message_request(&body)?;
+
+    debug!(
+        channel = %payload.channel,
+        text_length = payload.message.text.as_deref().map_or(0, str::len),
+        has_blocks = payload.message.blocks.is_some(),
+        has_attachments = payload.message.attachments.is_some(),
+        "Processing Slack message request"
+    );
+
+    let start = Instant::now();
+
+    let mut parts = slack_markdown::split_message(&payload.message, payload.format).into_iter();
+    let first = parts.next().ok_or_else(|| {
+        ApiError::BadRequest("'text' is empty after Markdown conversion".to_string())
+    })?;
+    let message = &first;
+    let posted = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::post_message(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                message,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            "Failed to post message to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    // 1 通に収まらない分は最初のメッセージのスレッドへ続けて投稿する。
+    // 最初のメッセージは投稿済みなので、続きで失敗しても投稿できた分を返す
+    let thread_ts = first.thread_ts.clone().unwrap_or_else(|| posted.ts.clone());
+    let mut follow_ups = Vec::new();
+    let mut follow_up_error = None;
+    for mut part in parts {
+        part.thread_ts = Some(thread_ts.clone());
+        match slack_service::post_message(
+            &app_state.client,
+            &app_state.slack_rate_limiter,
+            &app_state.settings.slack_bot_token,
+            &app_state.settings.slack_api_base_url,
+            &posted.channel,
+            &part,
+        )
+        .await
+        {
+            Ok(follow_up) => follow_ups.push(follow_up.ts),
+            Err(e) => {
+                error!(
+                    error = %e,
+                    channel = %posted.channel,
+                    ts = %posted.ts,
+                    posted_follow_ups = follow_ups.len(),
+                    "Failed to post follow-up message to Slack"
+                );
+                follow_up_error = Some(map_slack_error_to_api_error(e).to_string());
+                break;
+            }
+        }
+    }
+
+    let duration = start.elapsed();
+    info!(
+        channel = %posted.channel,
+        ts = %posted.ts,
+        thread_ts = ?payload.message.thread_ts,
+        follow_ups = follow_ups.len(),
+        duration_ms = duration.as_millis() as u64,
+        "Successfully posted message to Slack"
+    );
+
+    if payload.format == MessageFormat::Mrkdwn {
+        return Ok(message_ref_response(&posted));
+    }
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &posted.channel)?;
+            f.member("ts", &posted.ts)?;
+            f.member("follow_up_ts", &follow_ups)?;
+            if let Some(error) = &follow_up_error {
+                f.member("follow_up_error", error)?;
+            }
+            Ok(())
+        })
+    })
+    .to_string();
+    Ok(Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes()))
+}
+
+/// `SLACK_TEMPLATES` から、無ければ `SLACK_TEMPLATE_S3` からテンプレートを読む。
+async fn load_template(app_state: &AppState, name: &str) -> Result<nojson::RawJsonOwned, ApiError> {
+    let settings = &app_state.settings;
+    if let Some(template) = settings.slack_templates.get(name) {
+        return Ok(template.clone());
+    }
+    let not_found = || ApiError::NotFound(format!("Template '{name}' not found"));
+    let location = settings.slack_template_s3.as_ref().ok_or_else(not_found)?;
+
+    let object = s3_service::read_object(
+        &app_state.client,
+        settings,
+        GetObjectInput {
+            bucket: location.bucket.clone(),
+            key: location.key(name),
+        },
+        MAX_TEMPLATE_BYTES,
+    )
+    .await
+    .map_err(|e| match e {
+        ApiError::NotFound(_) => not_found(),
+        e => e,
+    })?;
+    let text = String::from_utf8(object.body).map_err(|_| {
+        ApiError::InternalServerError(format!("Template '{name}' is not valid UTF-8"))
+    })?;
+    nojson::RawJsonOwned::parse(text).map_err(|e| {
+        ApiError::InternalServerError(format!("Template '{name}' is not valid JSON: {e}"))
+    })
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn post_template_message(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_template_request(&body)?;
+
+    // テンプレートの読み込み (S3 を含む) より前に投稿先の権限を確認する
+    resolve_authorized_channel(app_state, caller, &payload.channel, slack_post).await?;
+    let template = load_template(app_state, &payload.template).await?;
+    let message = render_template_message(&template, &payload)?;
+
+    let start = Instant::now();
+
+    let message = &message;
+    let posted = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::post_message(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                message,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            template = %payload.template,
+            "Failed to post template message to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        channel = %posted.channel,
+        ts = %posted.ts,
+        template = %payload.template,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully posted template message to Slack"
+    );
+
+    Ok(message_ref_response(&posted))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn update_message(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_update_request(&body)?;
+
+    let start = Instant::now();
+
+    let (ts, message) = (&payload.target.ts, &payload.message);
+    let updated = with_channel(
+        app_state,
+        caller,
+        &payload.target.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::update_message(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &MessageRef {
+                    channel,
+                    ts: ts.clone(),
+                },
+                message,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.target.channel,
+            ts = %payload.target.ts,
+            "Failed to update Slack message"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        channel = %updated.channel,
+        ts = %updated.ts,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully updated Slack message"
+    );
+
+    Ok(message_ref_response(&updated))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn delete_message(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let target = parse_message_target(&body)?;
+
+    let start = Instant::now();
+
+    let ts = &target.ts;
+    let deleted = with_channel(
+        app_state,
+        caller,
+        &target.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::delete_message(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &MessageRef {
+                    channel,
+                    ts: ts.clone(),
+                },
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %target.channel,
+            ts = %target.ts,
+            "Failed to delete Slack message"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        channel = %deleted.channel,
+        ts = %deleted.ts,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully deleted Slack message"
+    );
+
+    Ok(message_ref_response(&deleted))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn post_ephemeral(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_ephemeral_request(&body)?;
+
+    let start = Instant::now();
+
+    let (user, message) = (&payload.user, &payload.message);
+    let message_ts = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::post_ephemeral(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                user,
+                message,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            user = %payload.user,
+            "Failed to post ephemeral message to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        channel = %payload.channel,
+        user = %payload.user,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully posted ephemeral message to Slack"
+    );
+
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &payload.channel)?;
+            f.member("user", &payload.user)?;
+            f.member("message_ts", &message_ts)
+        })
+    })
+    .to_string();
+    Ok(Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes()))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn post_direct_message(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_dm_request(&body)?;
+    caller.require(RequiredScope::SlackDm {
+        user: payload.recipient.as_str().to_string(),
+    })?;
+
+    let start = Instant::now();
+    let settings = &app_state.settings;
+
+    let user = match &payload.recipient {
+        DmRecipient::User(user) => user.clone(),
+        DmRecipient::Email(email) => slack_service::lookup_user_by_email(
+            &app_state.client,
+            &app_state.slack_rate_limiter,
+            &settings.slack_bot_token,
+            &settings.slack_api_base_url,
+            email,
+        )
+        .await
+        .map_err(|e| {
+            warn!(error = %e, "Failed to resolve Slack user by email");
+            if e.to_string() == "users_not_found" {
+                ApiError::NotFound(format!("No Slack user found for '{email}'"))
+            } else {
+                map_slack_error_to_api_error(e)
+            }
+        })?,
+    };
+
+    let channel = slack_service::open_direct_message(
+        &app_state.client,
+        &app_state.slack_rate_limiter,
+        &settings.slack_bot_token,
+        &settings.slack_api_base_url,
+        &user,
+    )
+    .await
+    .map_err(|e| {
+        error!(error = %e, user = %user, "Failed to open Slack DM");
+        map_slack_error_to_api_error(e)
+    })?;
+
+    let posted = slack_service::post_message(
+        &app_state.client,
+        &app_state.slack_rate_limiter,
+        &settings.slack_bot_token,
+        &settings.slack_api_base_url,
+        &channel,
+        &payload.message,
+    )
+    .await
+    .map_err(|e| {
+        error!(error = %e, user = %user, channel = %channel, "Failed to post Slack DM");
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        user = %user,
+        channel = %posted.channel,
+        ts = %posted.ts,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully posted Slack DM"
+    );
+
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("user", &user)?;
+            f.member("channel", &posted.channel)?;
+            f.member("ts", &posted.ts)
+        })
+    })
+    .to_string();
+    Ok(Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes()))
+}
+
+fn scheduled_message_response(channel: &str, scheduled_message_id: &str, post_at: i64) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", channel)?;
+            f.member("scheduled_message_id", scheduled_message_id)?;
+            f.member("post_at", post_at)
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+fn scheduled_list_response(messages: &[ScheduledMessage], next_cursor: Option<&str>) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member(
+                "scheduled_messages",
+                nojson::array(|f| {
+                    for message in messages {
+                        f.element(nojson::object(|f| {
+                            f.member("id", &message.id)?;
+                            f.member("channel", &message.channel)?;
+                            f.member("post_at", message.post_at)?;
+                            if let Some(date_created) = message.date_created {
+                                f.member("date_created", date_created)?;
+                            }
+                            if let Some(text) = &message.text {
+                                f.member("text", text)?;
+                            }
+                            Ok(())
+                        }))?;
+                    }
+                    Ok(())
+                }),
+            )?;
+            if let Some(next_cursor) = next_cursor {
+                f.member("next_cursor", next_cursor)?;
+            }
+            Ok(())
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn schedule_message(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_schedule_request(&body, slack_schedule::unix_now())?;
+
+    let start = Instant::now();
+
+    let message = &payload.message;
+    let scheduled_message_id = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::schedule_message(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                payload.post_at,
+                message,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            post_at = payload.post_at,
+            "Failed to schedule Slack message"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        channel = %payload.channel,
+        scheduled_message_id = %scheduled_message_id,
+        post_at = payload.post_at,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully scheduled Slack message"
+    );
+
+    Ok(scheduled_message_response(
+        &payload.channel,
+        &scheduled_message_id,
+        payload.post_at,
+    ))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn list_scheduled_messages(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_scheduled_list_request(&body)?;
+
+    let cursor = payload.cursor.as_deref();
+    let (messages, next_cursor) = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::list_scheduled_messages(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                cursor,
+                payload.limit,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            "Failed to list scheduled Slack messages"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    Ok(scheduled_list_response(&messages, next_cursor.as_deref()))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn delete_scheduled_message(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let target = parse_scheduled_message_target(&body)?;
+
+    let scheduled_message_id = &target.scheduled_message_id;
+    with_channel(
+        app_state,
+        caller,
+        &target.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::delete_scheduled_message(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                scheduled_message_id,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %target.channel,
+            scheduled_message_id = %target.scheduled_message_id,
+            "Failed to delete scheduled Slack message"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        channel = %target.channel,
+        scheduled_message_id = %target.scheduled_message_id,
+        "Deleted scheduled Slack message"
+    );
+
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &target.channel)?;
+            f.member("scheduled_message_id", &target.scheduled_message_id)
+        })
+    })
+    .to_string();
+    Ok(Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes()))
+}
+
+fn reaction_response(target: &MessageRef, name: &str) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &target.channel)?;
+            f.member("ts", &target.ts)?;
+            f.member("name", name)
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+/// `reactions.add`。既に付いている場合 (`already_reacted`) も成功として扱う。
+#[instrument(skip(app_state, caller, body))]
+pub async fn add_reaction(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_reaction_request(&body)?;
+
+    let (ts, name) = (&payload.target.ts, &payload.name);
+    let channel = with_channel(app_state, caller, &payload.target.channel, slack_post, |channel| async move {
+        let target = MessageRef {
+            channel,
+            ts: ts.clone(),
+        };
+        match slack_service::add_reaction(
+            &app_state.client,
+            &app_state.slack_rate_limiter,
+            &app_state.settings.slack_bot_token,
+            &app_state.settings.slack_api_base_url,
+            &target,
+            name,
+        )
+        .await
+        {
+            Err(e) if slack_service::is_slack_error(e.as_ref(), "already_reacted") => {
+                debug!(channel = %target.channel, ts = %target.ts, name = %name, "Reaction already added");
+                Ok(target.channel)
+            }
+            result => result.map(|()| target.channel),
+        }
+    })
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.target.channel,
+            ts = %payload.target.ts,
+            name = %payload.name,
+            "Failed to add Slack reaction"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(channel = %channel, ts = %ts, name = %name, "Added Slack reaction");
+    Ok(reaction_response(
+        &MessageRef {
+            channel,
+            ts: ts.clone(),
+        },
+        name,
+    ))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn remove_reaction(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_reaction_request(&body)?;
+
+    let (ts, name) = (&payload.target.ts, &payload.name);
+    let channel = with_channel(
+        app_state,
+        caller,
+        &payload.target.channel,
+        slack_post,
+        |channel| async move {
+            let target = MessageRef {
+                channel,
+                ts: ts.clone(),
+            };
+            slack_service::remove_reaction(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &target,
+                name,
+            )
+            .await
+            .map(|()| target.channel)
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.target.channel,
+            ts = %payload.target.ts,
+            name = %payload.name,
+            "Failed to remove Slack reaction"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(channel = %channel, ts = %ts, name = %name, "Removed Slack reaction");
+    Ok(reaction_response(
+        &MessageRef {
+            channel,
+            ts: ts.clone(),
+        },
+        name,
+    ))
+}
+
+fn reactions_response(target: &MessageRef, reactions: &[Reaction]) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &target.channel)?;
+            f.member("ts", &target.ts)?;
+            f.member(
+                "reactions",
+                nojson::array(|f| {
+                    for reaction in reactions {
+                        f.element(nojson::object(|f| {
+                            f.member("name", &reaction.name)?;
+                            f.member("count", reaction.count)?;
+                            f.member("users", &reaction.users)
+                        }))?;
+                    }
+                    Ok(())
+                }),
+            )
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn get_reactions(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let target = parse_pin_request(&body)?;
+
+    let ts = &target.ts;
+    let (channel, reactions) = with_channel(
+        app_state,
+        caller,
+        &target.channel,
+        slack_read,
+        |channel| async move {
+            let resolved = MessageRef {
+                channel,
+                ts: ts.clone(),
+            };
+            let reactions = slack_service::get_reactions(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &resolved,
+            )
+            .await?;
+            Ok((resolved.channel, reactions))
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %target.channel,
+            ts = %target.ts,
+            "Failed to get Slack reactions"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    Ok(reactions_response(
+        &MessageRef {
+            channel,
+            ts: ts.clone(),
+        },
+        &reactions,
+    ))
+}
+
+/// `pins.add`。既にピン留めされている場合 (`already_pinned`) も成功として扱う。
+#[instrument(skip(app_state, caller, body))]
+pub async fn add_pin(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let target = parse_pin_request(&body)?;
+
+    let ts = &target.ts;
+    let pinned = with_channel(app_state, caller, &target.channel, slack_post, |channel| async move {
+        let resolved = MessageRef {
+            channel,
+            ts: ts.clone(),
+        };
+        match slack_service::add_pin(
+            &app_state.client,
+            &app_state.slack_rate_limiter,
+            &app_state.settings.slack_bot_token,
+            &app_state.settings.slack_api_base_url,
+            &resolved,
+        )
+        .await
+        {
+            Err(e) if slack_service::is_slack_error(e.as_ref(), "already_pinned") => {
+                debug!(channel = %resolved.channel, ts = %resolved.ts, "Message already pinned");
+                Ok(resolved)
+            }
+            result => result.map(|()| resolved),
+        }
+    })
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %target.channel,
+            ts = %target.ts,
+            "Failed to pin Slack message"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(channel = %pinned.channel, ts = %pinned.ts, "Pinned Slack message");
+    Ok(message_ref_response(&pinned))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn remove_pin(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let target = parse_pin_request(&body)?;
+
+    let ts = &target.ts;
+    let unpinned = with_channel(
+        app_state,
+        caller,
+        &target.channel,
+        slack_post,
+        |channel| async move {
+            let resolved = MessageRef {
+                channel,
+                ts: ts.clone(),
+            };
+            slack_service::remove_pin(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &resolved,
+            )
+            .await
+            .map(|()| resolved)
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %target.channel,
+            ts = %target.ts,
+            "Failed to unpin Slack message"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(channel = %unpinned.channel, ts = %unpinned.ts, "Unpinned Slack message");
+    Ok(message_ref_response(&unpinned))
+}
+
+fn pins_response(channel: &str, pins: &[PinnedMessage]) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", channel)?;
+            f.member(
+                "pins",
+                nojson::array(|f| {
+                    for pin in pins {
+                        f.element(nojson::object(|f| {
+                            f.member("ts", &pin.ts)?;
+                            if let Some(text) = &pin.text {
+                                f.member("text", text)?;
+                            }
+                            if let Some(user) = &pin.user {
+                                f.member("user", user)?;
+                            }
+                            if let Some(created) = pin.created {
+                                f.member("created", created)?;
+                            }
+                            if let Some(created_by) = &pin.created_by {
+                                f.member("created_by", created_by)?;
+                            }
+                            Ok(())
+                        }))?;
+                    }
+                    Ok(())
+                }),
+            )
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn list_pins(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_channel_request(&body)?;
+
+    let (channel, pins) = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_read,
+        |channel| async move {
+            let pins = slack_service::list_pins(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+            )
+            .await?;
+            Ok((channel, pins))
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            "Failed to list Slack pins"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    Ok(pins_response(&channel, &pins))
+}
+
+fn write_bookmark(
+    f: &mut nojson::JsonObjectFormatter<'_, '_, '_>,
+    bookmark: &Bookmark,
+) -> std::fmt::Result {
+    f.member("id", &bookmark.id)?;
+    f.member("title", &bookmark.title)?;
+    if let Some(link) = &bookmark.link {
+        f.member("link", link)?;
+    }
+    if let Some(emoji) = &bookmark.emoji {
+        f.member("emoji", emoji)?;
+    }
+    if let Some(date_created) = bookmark.date_created {
+        f.member("date_created", date_created)?;
+    }
+    Ok(())
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn add_bookmark(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_bookmark_request(&body)?;
+
+    let new_bookmark = &payload.bookmark;
+    let (channel, bookmark) = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            let bookmark = slack_service::add_bookmark(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                new_bookmark,
+            )
+            .await?;
+            Ok((channel, bookmark))
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            title = %payload.bookmark.title,
+            "Failed to add Slack bookmark"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(channel = %channel, bookmark_id = %bookmark.id, "Added Slack bookmark");
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &channel)?;
+            write_bookmark(f, &bookmark)
+        })
+    })
+    .to_string();
+    Ok(Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes()))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn list_bookmarks(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_channel_request(&body)?;
+
+    let (channel, bookmarks) = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_read,
+        |channel| async move {
+            let bookmarks = slack_service::list_bookmarks(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+            )
+            .await?;
+            Ok((channel, bookmarks))
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            "Failed to list Slack bookmarks"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &channel)?;
+            f.member(
+                "bookmarks",
+                nojson::array(|f| {
+                    for bookmark in &bookmarks {
+                        f.element(nojson::object(|f| write_bookmark(f, bookmark)))?;
+                    }
+                    Ok(())
+                }),
+            )
+        })
+    })
+    .to_string();
+    Ok(Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes()))
+}
+
+#[instrument(skip(app_state, caller, body))]
+pub async fn remove_bookmark(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let target = parse_bookmark_target(&body)?;
+
+    let bookmark_id = &target.bookmark_id;
+    let channel = with_channel(
+        app_state,
+        caller,
+        &target.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::remove_bookmark(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                bookmark_id,
+            )
+            .await
+            .map(|()| channel)
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %target.channel,
+            bookmark_id = %target.bookmark_id,
+            "Failed to remove Slack bookmark"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(channel = %channel, bookmark_id = %bookmark_id, "Removed Slack bookmark");
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &channel)?;
+            f.member("bookmark_id", bookmark_id)
+        })
+    })
+    .to_string();
+    Ok(Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes()))
+}
+
+#[instrument(skip(app_state, caller, headers, body))]
+pub async fn upload_image_raw(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    raw_query: Option<&str>,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let upload = read_single_upload(
+        raw_query,
+        headers,
+        body,
+        app_state.settings.multipart_limits,
+    )?;
+    let payload = upload.query;
+    let content_type = upload
+        .content_type
+        .ok_or_else(|| ApiError::BadRequest("Missing Content-Type header".to_string()))?;
+    let body: &[u8] = &upload.data;
+    validate_file_not_empty(body)?;
+    let extension = ensure_image_content(&content_type, body)?;
+    let file_name = payload
+        .file_name
+        .unwrap_or_else(|| build_default_name("image-upload", extension));
+
+    debug!(
+        file_name = %file_name,
+        channel = %payload.channel,
+        content_type = %content_type,
+        file_size = body.len(),
+        "Processing raw image upload request"
+    );
+
+    let start = Instant::now();
+
+    let file_name = file_name.as_str();
+    let response_text = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::send_single_file_to_slack(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                body,
+                file_name,
+                &channel,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            file_name = %file_name,
+            channel = %payload.channel,
+            "Failed to upload image to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    let duration = start.elapsed();
+    info!(
+        file_name = %file_name,
+        channel = %payload.channel,
+        file_size = body.len(),
+        duration_ms = duration.as_millis() as u64,
+        "Successfully uploaded image to Slack"
+    );
+
+    Ok(json_string_response(response_text))
+}
+
+#[instrument(skip(app_state, caller, headers, body))]
+pub async fn upload_pdf_raw(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    raw_query: Option<&str>,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let upload = read_single_upload(
+        raw_query,
+        headers,
+        body,
+        app_state.settings.multipart_limits,
+    )?;
+    let payload = upload.query;
+    let content_type = upload
+        .content_type
+        .ok_or_else(|| ApiError::BadRequest("Missing Content-Type header".to_string()))?;
+    let body: &[u8] = &upload.data;
+    validate_file_not_empty(body)?;
+
+    if content_type != "application/pdf" {
+        return Err(ApiError::BadRequest(
+            "Unsupported Content-Type. Use application/pdf".to_string(),
+        ));
+    }
+
+    if !file_type::PDF.matches(body) {
+        warn!(channel = %payload.channel, "PDF signature check failed");
+        return Err(ApiError::BadRequest(
+            "Body is not a valid PDF document".to_string(),
+        ));
+    }
+
+    let file_name = payload
+        .file_name
+        .unwrap_or_else(|| build_default_name("document", "pdf"));
+
+    debug!(
+        file_name = %file_name,
+        channel = %payload.channel,
+        file_size = body.len(),
+        "Processing raw PDF upload request"
+    );
+
+    let start = Instant::now();
+
+    let file_name = file_name.as_str();
+    let response_text = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::send_single_file_to_slack(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                body,
+                file_name,
+                &channel,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            file_name = %file_name,
+            channel = %payload.channel,
+            "Failed to upload PDF to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    let duration = start.elapsed();
+    info!(
+        file_name = %file_name,
+        channel = %payload.channel,
+        file_size = body.len(),
+        duration_ms = duration.as_millis() as u64,
+        "Successfully uploaded PDF to Slack"
+    );
+
+    Ok(json_string_response(response_text))
+}
+
+#[instrument(skip(app_state, caller, headers, body))]
+pub async fn upload_file_raw(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    raw_query: Option<&str>,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let upload = read_single_upload(
+        raw_query,
+        headers,
+        body,
+        app_state.settings.multipart_limits,
+    )?;
+    let payload = upload.query;
+    let declared = upload
+        .content_type
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let body: &[u8] = &upload.data;
+    validate_file_not_empty(body)?;
+    let (content_type, extension) = check_upload_content(
+        &declared,
+        body,
+        &app_state.settings.slack_upload_content_types,
+    )?;
+    let file_name = payload
+        .file_name
+        .unwrap_or_else(|| build_default_name("file-upload", extension));
+
+    debug!(
+        file_name = %file_name,
+        channel = %payload.channel,
+        content_type = %content_type,
+        file_size = body.len(),
+        "Processing raw file upload request"
+    );
+
+    let start = Instant::now();
+
+    let file_name = file_name.as_str();
+    let response_text = with_channel(
+        app_state,
+        caller,
+        &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::send_single_file_to_slack(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                body,
+                file_name,
+                &channel,
+            )
+            .await
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            file_name = %file_name,
+            channel = %payload.channel,
+            "Failed to upload file to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    info!(
+        file_name = %file_name,
+        channel = %payload.channel,
+        content_type = %content_type,
+        file_size = body.len(),
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully uploaded file to Slack"
+    );
+
+    Ok(json_string_response(response_text))
+}
+
+/// アーカイブ済みのファイル
+struct ArchivedFile {
+    file_id: String,
+    key: String,
+    size: usize,
+}
+
+fn archive_response(
+    bucket: &str,
+    archived: &[ArchivedFile],
+    failed: &[(String, String)],
+    truncated: bool,
+) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("bucket", bucket)?;
+            f.member(
+                "archived",
+                nojson::array(|f| {
+                    for file in archived {
+                        f.element(nojson::object(|f| {
+                            f.member("file_id", &file.file_id)?;
+                            f.member("key", &file.key)?;
+                            f.member("size", file.size)
+                        }))?;
+                    }
+                    Ok(())
+                }),
+            )?;
+            f.member(
+                "failed",
+                nojson::array(|f| {
+                    for (file_id, error) in failed {
+                        f.element(nojson::object(|f| {
+                            f.member("file_id", file_id)?;
+                            f.member("error", error)
+                        }))?;
+                    }
+                    Ok(())
+                }),
+            )?;
+            f.member("truncated", truncated)
+        })
+    })
+    .to_string();
+    Response::new(200, "OK")
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
+
+/// `filter.channel` (`#name` は ID に解決する) のファイルを `MAX_ARCHIVE_FILES` 件まで集める。
+/// 解決したチャンネル ID、ファイル、上限で打ち切ったかどうかを返す。
+async fn list_archive_files(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    filter: &FileListFilter,
+) -> Result<(String, Vec<SlackFile>, bool), Box<dyn StdError>> {
+    let channel = filter.channel.as_deref().unwrap_or_default();
+    with_channel(
+        app_state,
+        caller,
+        channel,
+        slack_read,
+        |channel| async move {
+            let filter = FileListFilter {
+                channel: Some(channel.clone()),
+                ..filter.clone()
+            };
+            let mut files = Vec::new();
+            let mut page = 1;
+            loop {
+                let (mut listed, pages) = slack_service::list_files(
+                    &app_state.client,
+                    &app_state.slack_rate_limiter,
+                    &app_state.settings.slack_bot_token,
+                    &app_state.settings.slack_api_base_url,
+                    &filter,
+                    page,
+                )
+                .await?;
+                files.append(&mut listed);
+                if files.len() > slack_archive::MAX_ARCHIVE_FILES {
+                    files.truncate(slack_archive::MAX_ARCHIVE_FILES);
+                    return Ok((channel, files, true));
+                }
+                if page >= pages {
+                    return Ok((channel, files, false));
+                }
+                page += 1;
+            }
+        },
+    )
+    .await
+}
+
+/// Slack のファイルを S3 に保存する。個々のファイルの失敗は `failed` に入れて続行する。
+#[instrument(skip(app_state, caller, body))]
+pub async fn archive_files(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_archive_request(&body)?;
+    let bucket = payload
+        .bucket
+        .or_else(|| app_state.settings.slack_archive_bucket.clone())
+        .ok_or_else(|| {
+            ApiError::BadRequest(
+                "'bucket' is required when SLACK_ARCHIVE_BUCKET is not set".to_string(),
+            )
+        })?;
+    let settings = &app_state.settings;
+
+    let start = Instant::now();
+
+    let (channel, files, truncated) = match &payload.target {
+        ArchiveTarget::File { file_id, channel } => {
+            let resolved = match channel {
+                Some(channel) => {
+                    Some(resolve_authorized_channel(app_state, caller, channel, slack_read).await?)
+                }
+                None => None,
+            };
+            let file = slack_service::file_info(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &settings.slack_bot_token,
+                &settings.slack_api_base_url,
+                file_id,
+            )
+            .await
+            .map_err(|e| {
+                error!(error = %e, file_id = %file_id, "Failed to get Slack file info");
+                map_slack_error_to_api_error(e)
+            })?;
+            let channel = match resolved {
+                Some(resolved) => {
+                    if !file.channels.contains(&resolved) {
+                        return Err(ApiError::NotFound(format!(
+                            "File {file_id} is not shared in {}",
+                            channel.as_deref().unwrap_or_default()
+                        )));
+                    }
+                    resolved
+                }
+                None => {
+                    let channel = slack_archive::archive_channel(None, &file);
+                    caller.require(RequiredScope::SlackRead {
+                        channel: channel.clone(),
+                    })?;
+                    channel
+                }
+            };
+            (channel, vec![file], false)
+        }
+        ArchiveTarget::List(filter) => list_archive_files(app_state, caller, filter)
+            .await
+            .map_err(|e| {
+                error!(error = %e, channel = ?filter.channel, "Failed to list Slack files");
+                map_slack_error_to_api_error(e)
+            })?,
+    };
+
+    let keys = files
+        .iter()
+        .map(|file| slack_archive::archive_key(&channel, file))
+        .collect::<Vec<_>>();
+    for key in &keys {
+        caller.require(RequiredScope::S3Write {
+            bucket: bucket.clone(),
+            key: Some(key.clone()),
+        })?;
+    }
+
+    let max_size = settings.s3_transfer_max_bytes;
+    let mut archived = Vec::new();
+    let mut failed = Vec::new();
+    for (file, key) in files.into_iter().zip(keys) {
+        let Some(url) = file.url_private_download.as_deref() else {
+            failed.push((file.id, "File has no downloadable content".to_string()));
+            continue;
+        };
+        if file.size.is_some_and(|size| size > max_size as i64) {
+            failed.push((
+                file.id,
+                format!("File exceeds the transfer limit of {max_size} bytes"),
+            ));
+            continue;
+        }
+        let data = match slack_service::download_file(
+            &app_state.client,
+            &settings.slack_bot_token,
+            &settings.slack_api_base_url,
+            url,
+            max_size,
+        )
+        .await
+        {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(error = %e, file_id = %file.id, "Failed to download Slack file");
+                failed.push((file.id, e.to_string()));
+                continue;
+            }
+        };
+        let size = data.len();
+        let stored = s3_service::put_object(
+            &app_state.client,
+            settings,
+            PutObjectInput {
+                bucket: bucket.clone(),
+                key: key.clone(),
+                body: data,
+                content_type: file.mimetype.clone(),
+                metadata: slack_archive::archive_metadata(&channel, &file),
+            },
+        )
+        .await;
+        match stored {
+            Ok(_) => archived.push(ArchivedFile {
+                file_id: file.id,
+                key,
+                size,
+            }),
+            Err(e) => {
+                warn!(error = %e, file_id = %file.id, key = %key, "Failed to store Slack file in S3");
+                failed.push((file.id, e.to_string()));
+            }
+        }
+    }
+
+    info!(
+        bucket = %bucket,
+        channel = %channel,
+        archived = archived.len(),
+        failed = failed.len(),
+        truncated,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Archived Slack files to S3"
+    );
+
+    Ok(archive_response(&bucket, &archived, &failed, truncated))
+}
+
+/// スレッドの返信を `cursor` でたどってすべて取得する。
+async fn fetch_thread(
+    app_state: &AppState,
+    channel: &str,
+    thread_ts: &str,
+    limit: usize,
+) -> Result<(Vec<ExportedMessage>, bool), Box<dyn StdError>> {
+    let settings = &app_state.settings;
+    let mut messages = Vec::new();
+    let mut cursor = None;
+    loop {
+        let (page, next_cursor) = slack_service::conversation_replies(
+            &app_state.client,
+            &app_state.slack_rate_limiter,
+            &settings.slack_bot_token,
+            &settings.slack_api_base_url,
+            channel,
+            thread_ts,
+            cursor.as_deref(),
+        )
+        .await?;
+        messages.extend(page);
+        if messages.len() >= limit {
+            return Ok((messages, next_cursor.is_some()));
+        }
+        match next_cursor {
+            Some(next) => cursor = Some(next),
+            None => return Ok((messages, false)),
+        }
+    }
+}
+
+/// エクスポートするメッセージを集めて古い順に並べる。`MAX_EXPORT_MESSAGES` に達したら打ち切り、true を返す。
+async fn fetch_export_messages(
+    app_state: &AppState,
+    channel: &str,
+    payload: &SlackExportRequest,
+) -> Result<(Vec<ExportedMessage>, bool), Box<dyn StdError>> {
+    let limit = slack_export::MAX_EXPORT_MESSAGES;
+    if let Some(thread_ts) = &payload.thread_ts {
+        let (mut messages, mut truncated) =
+            fetch_thread(app_state, channel, thread_ts, limit).await?;
+        truncated |= messages.len() > limit;
+        slack_export::sort_messages(&mut messages);
+        messages.truncate(limit);
+        return Ok((messages, truncated));
+    }
+
+    let settings = &app_state.settings;
+    let mut messages = Vec::new();
+    let mut cursor = None;
+    let mut truncated = false;
+    loop {
+        let (page, next_cursor) = slack_service::conversation_history(
+            &app_state.client,
+            &app_state.slack_rate_limiter,
+            &settings.slack_bot_token,
+            &settings.slack_api_base_url,
+            channel,
+            payload.range,
+            cursor.as_deref(),
+        )
+        .await?;
+        for message in page {
+            let has_replies = message.reply_count.is_some_and(|count| count > 0)
+                && message.thread_ts.as_deref() == Some(message.ts.as_str());
+            let thread_ts = message.ts.clone();
+            messages.push(message);
+            if payload.include_replies && has_replies && messages.len() < limit {
+                let (replies, thread_truncated) =
+                    fetch_thread(app_state, channel, &thread_ts, limit - messages.len()).await?;
+                // 返信の先頭は親メッセージ自身
+                messages.extend(replies.into_iter().filter(|reply| reply.ts != thread_ts));
+                truncated |= thread_truncated;
+            }
+        }
+        if messages.len() >= limit {
+            truncated |= next_cursor.is_some() || messages.len() > limit;
+            break;
+        }
+        match next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    // 新しい順に集めているので、上限を超えた古い側を捨てる
+    messages.truncate(limit);
+    slack_export::sort_messages(&mut messages);
+    Ok((messages, truncated))
+}
+
+/// 投稿者とリアクションしたユーザーの名前を引く。引けなかったユーザーは ID のみ書き出す。
+async fn resolve_user_names(
+    app_state: &AppState,
+    messages: &[ExportedMessage],
+) -> HashMap<String, String> {
+    let settings = &app_state.settings;
+    let mut names = HashMap::new();
+    for user in slack_export::collect_user_ids(messages) {
+        match slack_service::user_name(
+            &app_state.client,
+            &app_state.slack_rate_limiter,
+            &settings.slack_bot_token,
+            &settings.slack_api_base_url,
+            &user,
+        )
+        .await
+        {
+            Ok(Some(name)) => {
+                names.insert(user, name);
+            }
+            Ok(None) => {}
+            Err(e) => warn!(error = %e, user = %user, "Failed to resolve Slack user name"),
+        }
     }
-    Ok(())
+    names
 }
 
-fn json_string_response(value: String) -> Response {
-    let body = nojson::json(|f| f.value(&value)).to_string();
-    Response::new(200, "OK")
-        .header("Content-Type", "application/json")
-        .body(body.into_bytes())
+/// チャンネルの履歴、またはスレッドを JSONL (1行1メッセージ、古い順) で S3 に書き出すジョブを始める。
+/// 権限を確認したら 202 でジョブ ID を返し、進み具合は `export_status` で確認する。
+#[instrument(skip(app_state, caller, body))]
+pub async fn export_conversation(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_export_request(&body)?;
+    let bucket = payload
+        .bucket
+        .clone()
+        .or_else(|| app_state.settings.slack_archive_bucket.clone())
+        .ok_or_else(|| {
+            ApiError::BadRequest(
+                "'bucket' is required when SLACK_ARCHIVE_BUCKET is not set".to_string(),
+            )
+        })?;
+
+    let channel =
+        resolve_authorized_channel(app_state, caller, &payload.channel, slack_read).await?;
+    let key = payload.key.clone().unwrap_or_else(|| {
+        slack_export::export_key(
+            &channel,
+            payload.thread_ts.as_deref(),
+            slack_schedule::unix_now(),
+        )
+    });
+    // 履歴をたどる前に書き込み先の権限を確認する
+    caller.require(RequiredScope::S3Write {
+        bucket: bucket.clone(),
+        key: Some(key.clone()),
+    })?;
+
+    let target = ExportTarget {
+        bucket,
+        key,
+        channel,
+        thread_ts: payload.thread_ts.clone(),
+    };
+    let job_id = app_state.export_jobs.start(
+        &caller.key_name,
+        target.clone(),
+        tokio::time::Instant::now(),
+    );
+    info!(
+        job_id = %job_id,
+        bucket = %target.bucket,
+        key = %target.key,
+        channel = %target.channel,
+        thread_ts = ?target.thread_ts,
+        "Started Slack export job"
+    );
+
+    let app_state = app_state.clone();
+    let spawned_job_id = job_id.clone();
+    let spawned_target = target.clone();
+    tokio::spawn(async move {
+        let status = match run_export(&app_state, &spawned_target, &payload).await {
+            Ok(summary) => ExportStatus::Succeeded(summary),
+            Err(e) => {
+                error!(
+                    error = %e,
+                    job_id = %spawned_job_id,
+                    channel = %spawned_target.channel,
+                    "Slack export job failed"
+                );
+                ExportStatus::Failed(e.to_string())
+            }
+        };
+        app_state
+            .export_jobs
+            .finish(&spawned_job_id, status, tokio::time::Instant::now());
+    });
+
+    Ok(export_job_response(
+        202,
+        &job_id,
+        &target,
+        &ExportStatus::Running,
+    ))
 }
 
-#[instrument(skip(app_state, body))]
-pub async fn post_message(app_state: &AppState, body: &[u8]) -> Result<Response, ApiError> {
+/// `export_conversation` で始めたジョブの状態を返す。ジョブを作った呼び出し元にだけ見せる。
+#[instrument(skip(app_state, caller, body))]
+pub async fn export_status(
+    app_state: &AppState,
+    caller: &CallerIdentity,
+    body: &[u8],
+) -> Result<Response, ApiError> {
     let body = String::from_utf8(body.to_vec())
         .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
-    let payload = parse_message_request(&body)?;
+    let job_id = parse_export_status_request(&body)?;
+    let ExportJob { target, status, .. } = app_state
+        .export_jobs
+        .get(&job_id, &caller.key_name, tokio::time::Instant::now())
+        .ok_or_else(|| ApiError::NotFound(format!("Export job '{job_id}' not found")))?;
+    Ok(export_job_response(200, &job_id, &target, &status))
+}
 
-    debug!(
-        channel = %payload.channel,
-        text_length = payload.text.len(),
-        "Processing Slack message request"
-    );
+fn export_job_response(
+    status_code: u16,
+    job_id: &str,
+    target: &ExportTarget,
+    status: &ExportStatus,
+) -> Response {
+    let body = nojson::json(|f| {
+        f.object(|f| {
+            f.member("job_id", job_id)?;
+            f.member("status", status.as_str())?;
+            f.member("bucket", &target.bucket)?;
+            f.member("key", &target.key)?;
+            f.member("channel", &target.channel)?;
+            f.member("thread_ts", &target.thread_ts)?;
+            match status {
+                ExportStatus::Running => {}
+                ExportStatus::Succeeded(summary) => {
+                    f.member("message_count", summary.message_count)?;
+                    f.member("bytes", summary.uploaded.bytes)?;
+                    f.member("multipart", summary.uploaded.parts > 0)?;
+                    f.member("truncated", summary.truncated)?;
+                }
+                ExportStatus::Failed(error) => f.member("error", error)?,
+            }
+            Ok(())
+        })
+    })
+    .to_string();
+    let reason = if status_code == 202 { "Accepted" } else { "OK" };
+    Response::new(status_code, reason)
+        .header("Content-Type", "application/json")
+        .body(body.into_bytes())
+}
 
+/// メッセージを集めて名前を引き、JSONL として書き出す。メモリに持つのは `MAX_EXPORT_MESSAGES` 件まで。
+async fn run_export(
+    app_state: &AppState,
+    target: &ExportTarget,
+    payload: &SlackExportRequest,
+) -> Result<ExportSummary, ApiError> {
     let start = Instant::now();
+    let (messages, truncated) = fetch_export_messages(app_state, &target.channel, payload)
+        .await
+        .map_err(|e| {
+            error!(
+                error = %e,
+                channel = %target.channel,
+                thread_ts = ?target.thread_ts,
+                "Failed to read Slack conversation"
+            );
+            map_slack_error_to_api_error(e)
+        })?;
+    let names = resolve_user_names(app_state, &messages).await;
 
-    let response_text = slack_service::post_message(
+    let mut upload = JsonlUpload::new(
         &app_state.client,
-        &app_state.settings.slack_bot_token,
-        &app_state.settings.slack_api_base_url,
-        &payload.channel,
-        &payload.text,
-    )
-    .await
-    .map_err(|e| {
-        error!(
-            error = %e,
-            channel = %payload.channel,
-            "Failed to post message to Slack"
-        );
-        ApiError::InternalServerError(e.to_string())
-    })?;
+        &app_state.settings,
+        &target.bucket,
+        &target.key,
+    );
+    for message in &messages {
+        if let Err(e) = upload.write_line(&message.to_json_line(&names)).await {
+            error!(error = %e, bucket = %target.bucket, key = %target.key, "Failed to write Slack export");
+            if let Err(abort_error) = upload.abort().await {
+                warn!(error = %abort_error, key = %target.key, "Failed to abort multipart upload");
+            }
+            return Err(e);
+        }
+    }
+    let uploaded = upload.finish().await?;
 
-    let duration = start.elapsed();
     info!(
-        channel = %payload.channel,
-        duration_ms = duration.as_millis() as u64,
-        "Successfully posted message to Slack"
+        bucket = %target.bucket,
+        key = %target.key,
+        channel = %target.channel,
+        thread_ts = ?target.thread_ts,
+        message_count = messages.len(),
+        bytes = uploaded.bytes,
+        parts = uploaded.parts,
+        truncated,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Exported Slack conversation to S3"
     );
 
-    Ok(json_string_response(response_text))
+    Ok(ExportSummary {
+        message_count: messages.len(),
+        uploaded,
+        truncated,
+    })
 }
 
-#[instrument(skip(app_state, headers, body))]
-pub async fn upload_image_raw(
+#[instrument(skip(app_state, caller, body))]
+pub async fn upload_from_s3(
     app_state: &AppState,
-    raw_query: Option<&str>,
-    headers: &[(String, String)],
+    caller: &CallerIdentity,
     body: &[u8],
 ) -> Result<Response, ApiError> {
-    let payload = parse_upload_query(raw_query)?;
-    let content_type = content_type(&headers)?;
-    validate_file_not_empty(body)?;
-    let extension = ensure_image_content(&content_type, body)?;
+    let body = String::from_utf8(body.to_vec())
+        .map_err(|_| ApiError::BadRequest("Request body must be valid UTF-8".to_string()))?;
+    let payload = parse_upload_from_s3_request(&body)?;
+    caller.require(RequiredScope::S3Read {
+        bucket: payload.bucket.clone(),
+        key: Some(payload.key.clone()),
+    })?;
+    // 存在しない・権限の無いチャンネルでアップロード済みのファイルが残らないよう、先に投稿先を解決する
+    resolve_authorized_channel(app_state, caller, &payload.channel, slack_post).await?;
+
+    let start = Instant::now();
+
+    let mut object = s3_service::open_object(
+        &app_state.client,
+        &app_state.settings,
+        GetObjectInput {
+            bucket: payload.bucket.clone(),
+            key: payload.key.clone(),
+        },
+        app_state.settings.s3_transfer_max_bytes,
+    )
+    .await?;
+    // Slack のアップロード URL は長さを先に指定するため、本文を読まずに長さを知る必要がある
+    let file_size = object.content_length.ok_or_else(|| {
+        ApiError::InternalServerError(format!(
+            "S3 GetObject for {}/{} returned no Content-Length",
+            payload.bucket, payload.key
+        ))
+    })?;
+    let head = object.read_prefix(file_type::SNIFF_LEN).await?;
+    validate_file_not_empty(&head)?;
+    let declared = object
+        .content_type
+        .as_deref()
+        .map(normalize_media_type)
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    let sniffed = if head.len() < file_size {
+        file_type::trim_partial_utf8(&head)
+    } else {
+        &head
+    };
+    let (content_type, extension) = check_upload_content(
+        &declared,
+        sniffed,
+        &app_state.settings.slack_upload_content_types,
+    )?;
     let file_name = payload
         .file_name
-        .unwrap_or_else(|| build_default_name("image-upload", extension));
+        .unwrap_or_else(|| file_name_from_key(&payload.key, extension));
+    let title = payload.title.unwrap_or_else(|| file_name.clone());
 
     debug!(
-        file_name = %file_name,
+        bucket = %payload.bucket,
+        key = %payload.key,
         channel = %payload.channel,
         content_type = %content_type,
-        file_size = body.len(),
-        "Processing raw image upload request"
+        file_size,
+        "Sharing S3 object to Slack"
     );
 
-    let start = Instant::now();
-
-    let response_text = slack_service::send_single_file_to_slack(
+    let file_id = slack_service::upload_file_stream(
         &app_state.client,
+        &app_state.slack_rate_limiter,
         &app_state.settings.slack_bot_token,
         &app_state.settings.slack_api_base_url,
-        body,
         &file_name,
+        StreamedBody {
+            head,
+            rest: &mut object.body,
+            length: file_size,
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            key = %payload.key,
+            channel = %payload.channel,
+            "Failed to upload S3 object to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    let uploaded = &[UploadedFile { id: file_id, title }];
+    let placement = &UploadPlacement::default();
+    let response_text = with_channel(
+        app_state,
+        caller,
         &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::complete_uploads(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                uploaded,
+                placement,
+            )
+            .await
+        },
     )
     .await
     .map_err(|e| {
         error!(
             error = %e,
-            file_name = %file_name,
+            key = %payload.key,
             channel = %payload.channel,
-            "Failed to upload image to Slack"
+            "Failed to share S3 object to Slack"
         );
-        ApiError::InternalServerError(e.to_string())
+        map_slack_error_to_api_error(e)
     })?;
 
-    let duration = start.elapsed();
     info!(
+        bucket = %payload.bucket,
+        key = %payload.key,
         file_name = %file_name,
         channel = %payload.channel,
-        file_size = body.len(),
-        duration_ms = duration.as_millis() as u64,
-        "Successfully uploaded image to Slack"
+        file_size,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully shared S3 object to Slack"
     );
 
     Ok(json_string_response(response_text))
 }
 
-#[instrument(skip(app_state, headers, body))]
-pub async fn upload_pdf_raw(
+#[instrument(skip(app_state, caller, headers, body), fields(body_size = body.len()))]
+pub async fn upload_files_multipart(
     app_state: &AppState,
-    raw_query: Option<&str>,
+    caller: &CallerIdentity,
     headers: &[(String, String)],
     body: &[u8],
 ) -> Result<Response, ApiError> {
-    let payload = parse_upload_query(raw_query)?;
-    let content_type = content_type(&headers)?;
-    validate_file_not_empty(body)?;
-
-    if content_type != "application/pdf" {
-        return Err(ApiError::BadRequest(
-            "Unsupported Content-Type. Use application/pdf".to_string(),
-        ));
-    }
-
-    if !looks_like_pdf(body) {
-        warn!(channel = %payload.channel, "PDF signature check failed");
-        return Err(ApiError::BadRequest(
-            "Body is not a valid PDF document".to_string(),
-        ));
-    }
-
-    let file_name = payload
-        .file_name
-        .unwrap_or_else(|| build_default_name("document", "pdf"));
+    let content_type = header_value(headers, "content-type")
+        .ok_or_else(|| ApiError::BadRequest("Missing Content-Type header".to_string()))?;
+    let payload = parse_multipart_upload(
+        content_type,
+        body,
+        &app_state.settings.slack_upload_content_types,
+        app_state.settings.multipart_limits,
+    )?;
+    // 存在しない・権限の無いチャンネルでアップロード済みのファイルが残らないよう、先に投稿先を解決する
+    resolve_authorized_channel(app_state, caller, &payload.channel, slack_post).await?;
 
+    let file_count = payload.files.len();
     debug!(
-        file_name = %file_name,
         channel = %payload.channel,
-        file_size = body.len(),
-        "Processing raw PDF upload request"
+        file_count,
+        thread_ts = ?payload.placement.thread_ts,
+        "Processing multipart file upload request"
     );
 
     let start = Instant::now();
 
-    let response_text = slack_service::send_single_file_to_slack(
+    let uploaded = slack_service::upload_files(
         &app_state.client,
+        &app_state.slack_rate_limiter,
         &app_state.settings.slack_bot_token,
         &app_state.settings.slack_api_base_url,
-        body,
-        &file_name,
+        payload.files,
+    )
+    .await
+    .map_err(|e| {
+        error!(
+            error = %e,
+            channel = %payload.channel,
+            file_count,
+            "Failed to upload files to Slack"
+        );
+        map_slack_error_to_api_error(e)
+    })?;
+
+    let (uploaded, placement) = (&uploaded, &payload.placement);
+    let response_text = with_channel(
+        app_state,
+        caller,
         &payload.channel,
+        slack_post,
+        |channel| async move {
+            slack_service::complete_uploads(
+                &app_state.client,
+                &app_state.slack_rate_limiter,
+                &app_state.settings.slack_bot_token,
+                &app_state.settings.slack_api_base_url,
+                &channel,
+                uploaded,
+                placement,
+            )
+            .await
+        },
     )
     .await
     .map_err(|e| {
         error!(
             error = %e,
-            file_name = %file_name,
             channel = %payload.channel,
-            "Failed to upload PDF to Slack"
+            file_count,
+            "Failed to share files to Slack"
         );
-        ApiError::InternalServerError(e.to_string())
+        map_slack_error_to_api_error(e)
     })?;
 
-    let duration = start.elapsed();
     info!(
-        file_name = %file_name,
         channel = %payload.channel,
-        file_size = body.len(),
-        duration_ms = duration.as_millis() as u64,
-        "Successfully uploaded PDF to Slack"
+        file_count,
+        duration_ms = start.elapsed().as_millis() as u64,
+        "Successfully uploaded files to Slack"
     );
 
     Ok(json_string_response(response_text))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        ArchiveTarget, DmRecipient, archive_files, check_upload_content, export_conversation,
+        export_status, file_name_from_key, map_slack_error_to_api_error, parse_archive_request,
+        parse_bookmark_request, parse_dm_request, parse_ephemeral_request, parse_export_request,
+        parse_message_request, parse_message_target, parse_multipart_upload, parse_pin_request,
+        parse_reaction_request, parse_schedule_request, parse_scheduled_list_request,
+        parse_template_request, parse_update_request, parse_upload_from_s3_request,
+        post_template_message, read_single_upload, render_template_message, schedule_message,
+    };
+    use crate::{
+        auth::{CallerIdentity, scope::Scope},
+        config::state::AppState,
+        errors::api_error::ApiError,
+        multipart::MultipartLimits,
+        service::slack_markdown::MessageFormat,
+    };
+
+    #[test]
+    fn parse_message_request_accepts_blocks_without_text() {
+        let body = r#"{
+            "channel": "C123",
+            "blocks": [{"type": "divider"}],
+            "unfurl_links": false,
+            "username": null
+        }"#;
+
+        let parsed = parse_message_request(body).expect("request should parse");
+
+        assert_eq!(parsed.channel, "C123");
+        assert_eq!(parsed.message.text, None);
+        assert_eq!(parsed.message.unfurl_links, Some(false));
+        assert_eq!(parsed.message.username, None);
+        assert!(parsed.message.blocks.is_some());
+    }
+
+    #[test]
+    fn parse_message_request_reads_markdown_format() {
+        let parsed = parse_message_request(
+            r#"{"channel": "C123", "text": "**done**", "format": "markdown"}"#,
+        )
+        .expect("request should parse");
+        assert_eq!(parsed.format, MessageFormat::Markdown);
+
+        assert!(
+            parse_message_request(r#"{"channel": "C123", "text": "x", "format": "html"}"#).is_err()
+        );
+        assert!(
+            parse_message_request(
+                r#"{"channel": "C123", "text": "x", "format": "markdown", "blocks": [{"type": "divider"}]}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_message_request_rejects_invalid_options() {
+        assert!(parse_message_request(r#"{"channel": "C123"}"#).is_err());
+        assert!(
+            parse_message_request(r#"{"channel": "C123", "text": "hi", "mrkdwn": "yes"}"#).is_err()
+        );
+        assert!(
+            parse_message_request(r#"{"channel": "C123", "blocks": {"type": "divider"}}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn template_request_is_rendered_into_a_message() {
+        let template = nojson::RawJsonOwned::parse(
+            r#"{"text": "{{service}} deploy started", "blocks": [{"type": "section", "text": {"type": "mrkdwn", "text": "*{{service}}* `{{version}}`"}}]}"#,
+        )
+        .expect("template");
+        let request = parse_template_request(
+            r##"{"channel": "#deploys", "template": "deploy_started", "context": {"service": "api", "version": "v1.2.3"}, "thread_ts": "1.2"}"##,
+        )
+        .expect("request should parse");
+
+        let message = render_template_message(&template, &request).expect("rendered");
+        assert_eq!(message.text.as_deref(), Some("api deploy started"));
+        assert_eq!(message.thread_ts.as_deref(), Some("1.2"));
+        assert_eq!(
+            message.blocks.expect("blocks").text(),
+            r#"[{"type":"section","text":{"type":"mrkdwn","text":"*api* `v1.2.3`"}}]"#
+        );
+
+        let missing = parse_template_request(
+            r#"{"channel": "C1", "template": "deploy_started", "context": {"service": "api"}}"#,
+        )
+        .expect("request should parse");
+        let err = render_template_message(&template, &missing).expect_err("missing value");
+        assert_eq!(
+            err.to_string(),
+            "Bad Request: Template 'deploy_started' failed to render: blocks[0].text.text: {{version}}: 'version' is not defined"
+        );
+
+        assert!(parse_template_request(r#"{"channel": "C1", "template": "../x"}"#).is_err());
+        assert!(
+            parse_template_request(r#"{"channel": "C1", "template": "x", "context": []}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_update_request_requires_ts_and_body() {
+        let parsed = parse_update_request(
+            r#"{"channel": "C123", "ts": "1712345678.000100", "text": "deploy: done"}"#,
+        )
+        .expect("request should parse");
+        assert_eq!(parsed.target.ts, "1712345678.000100");
+        assert_eq!(parsed.message.text.as_deref(), Some("deploy: done"));
+
+        assert!(parse_update_request(r#"{"channel": "C123", "text": "x"}"#).is_err());
+        assert!(parse_update_request(r#"{"channel": "C123", "ts": "1.2"}"#).is_err());
+    }
+
+    #[test]
+    fn parse_message_target_requires_channel_and_ts() {
+        assert!(parse_message_target(r#"{"channel": "C123", "ts": "1.2"}"#).is_ok());
+        assert!(parse_message_target(r#"{"ts": "1.2"}"#).is_err());
+    }
+
+    const NOW: i64 = 1_792_195_200;
+
+    #[test]
+    fn parse_schedule_request_accepts_unix_and_rfc3339() {
+        let unix = parse_schedule_request(
+            r#"{"channel": "C123", "post_at": 1792198800, "text": "release at 10:00"}"#,
+            NOW,
+        )
+        .expect("unix post_at should parse");
+        assert_eq!(unix.post_at, 1_792_198_800);
+
+        let rfc3339 = parse_schedule_request(
+            r#"{"channel": "C123", "post_at": "2026-10-17T10:00:00+09:00", "text": "x"}"#,
+            NOW,
+        )
+        .expect("rfc3339 post_at should parse");
+        assert_eq!(rfc3339.post_at, NOW + 3600);
+    }
+
+    #[test]
+    fn parse_schedule_request_rejects_past_far_future_and_garbage() {
+        for post_at in [
+            r#""2026-10-16T00:00:00Z""#,
+            "1792195200",
+            r#""2027-03-01T00:00:00Z""#,
+            r#""tomorrow""#,
+            "1792198800.5",
+        ] {
+            let body = format!(r#"{{"channel": "C123", "post_at": {post_at}, "text": "x"}}"#);
+            assert!(parse_schedule_request(&body, NOW).is_err(), "{post_at}");
+        }
+        assert!(parse_schedule_request(r#"{"channel": "C123", "text": "x"}"#, NOW).is_err());
+    }
+
+    #[test]
+    fn parse_scheduled_list_request_reads_paging() {
+        let parsed =
+            parse_scheduled_list_request(r#"{"channel": "C123", "cursor": "abc", "limit": 20}"#)
+                .expect("request should parse");
+        assert_eq!(parsed.cursor.as_deref(), Some("abc"));
+        assert_eq!(parsed.limit, Some(20));
+        assert!(parse_scheduled_list_request(r#"{"channel": "C123", "limit": -1}"#).is_err());
+    }
+
+    #[test]
+    fn parse_reaction_request_normalizes_name_and_checks_ts() {
+        let parsed = parse_reaction_request(
+            r##"{"channel": "#deploys", "ts": "1712345678.000100", "name": ":white_check_mark:"}"##,
+        )
+        .expect("request should parse");
+        assert_eq!(parsed.target.channel, "#deploys");
+        assert_eq!(parsed.name, "white_check_mark");
+
+        assert!(
+            parse_reaction_request(
+                r#"{"channel": "C1", "ts": "1712345678.000100", "name": "a b"}"#
+            )
+            .is_err()
+        );
+        assert!(
+            parse_reaction_request(r#"{"channel": "C1", "ts": "yesterday", "name": "eyes"}"#)
+                .is_err()
+        );
+        assert!(parse_pin_request(r#"{"channel": "C1", "ts": "1712345678.000100"}"#).is_ok());
+        assert!(parse_pin_request(r#"{"channel": "C1"}"#).is_err());
+    }
+
+    #[test]
+    fn parse_bookmark_request_validates_link_and_emoji() {
+        let parsed = parse_bookmark_request(
+            r##"{"channel": "#oncall", "title": "Handover", "link": "https://wiki.example.com/oncall", "emoji": "pager"}"##,
+        )
+        .expect("request should parse");
+        assert_eq!(parsed.bookmark.title, "Handover");
+        assert_eq!(parsed.bookmark.emoji.as_deref(), Some(":pager:"));
+
+        assert!(
+            parse_bookmark_request(
+                r#"{"channel": "C1", "title": "x", "link": "ftp://files.example.com"}"#
+            )
+            .is_err()
+        );
+        assert!(
+            parse_bookmark_request(
+                r#"{"channel": "C1", "title": " ", "link": "https://a.example"}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_export_request_accepts_channel_range_or_thread() {
+        let history = parse_export_request(
+            r#"{"channel": "C9", "oldest": "2026-10-16T00:00:00Z", "latest": 1792195200, "include_replies": true}"#,
+        )
+        .expect("history export");
+        assert_eq!(history.range.oldest, Some(1_792_108_800));
+        assert_eq!(history.range.latest, Some(1_792_195_200));
+        assert!(history.include_replies);
+        assert_eq!(history.thread_ts, None);
+
+        let thread = parse_export_request(
+            r#"{"channel": "C9", "thread_ts": "1712345678.000100", "bucket": "retro", "key": "inc-42.jsonl"}"#,
+        )
+        .expect("thread export");
+        assert_eq!(thread.thread_ts.as_deref(), Some("1712345678.000100"));
+        assert_eq!(thread.key.as_deref(), Some("inc-42.jsonl"));
+
+        assert!(parse_export_request(r#"{"channel": "C9", "thread_ts": "yesterday"}"#).is_err());
+        assert!(
+            parse_export_request(
+                r#"{"channel": "C9", "thread_ts": "1712345678.000100", "oldest": 1}"#
+            )
+            .is_err()
+        );
+        assert!(parse_export_request(r#"{"channel": "C9", "oldest": 2, "latest": 1}"#).is_err());
+    }
+
+    #[test]
+    fn parse_ephemeral_request_requires_user() {
+        let parsed =
+            parse_ephemeral_request(r#"{"channel": "C123", "user": "U123", "text": "only you"}"#)
+                .expect("request should parse");
+        assert_eq!(parsed.user, "U123");
+        assert!(parse_ephemeral_request(r#"{"channel": "C123", "text": "x"}"#).is_err());
+    }
+
+    #[test]
+    fn parse_dm_request_accepts_user_or_email() {
+        let by_id = parse_dm_request(r#"{"user": "U123", "text": "hi"}"#).expect("user");
+        assert_eq!(by_id.recipient, DmRecipient::User("U123".to_string()));
+
+        let by_email =
+            parse_dm_request(r#"{"email": "oncall@example.com", "text": "hi"}"#).expect("email");
+        assert_eq!(
+            by_email.recipient,
+            DmRecipient::Email("oncall@example.com".to_string())
+        );
+
+        assert!(parse_dm_request(r#"{"text": "hi"}"#).is_err());
+        assert!(
+            parse_dm_request(r#"{"user": "U1", "email": "a@example.com", "text": "hi"}"#).is_err()
+        );
+        assert!(parse_dm_request(r#"{"email": "oncall", "text": "hi"}"#).is_err());
+    }
+
+    #[test]
+    fn not_found_slack_errors_map_to_404() {
+        use crate::{errors::api_error::ApiError, service::slack_service::SlackApiError};
+
+        let not_found =
+            map_slack_error_to_api_error(Box::new(SlackApiError::new("channel_not_found")));
+        assert!(matches!(not_found, ApiError::NotFound(_)));
+
+        let other = map_slack_error_to_api_error(Box::new(SlackApiError::new("not_in_channel")));
+        assert!(matches!(other, ApiError::InternalServerError(_)));
+    }
+
+    #[test]
+    fn check_upload_content_applies_policy_to_sniffed_type() {
+        use crate::{errors::api_error::ApiError, service::file_type::ContentTypePolicy};
+
+        let policy = ContentTypePolicy {
+            allowed: Vec::new(),
+            denied: ContentTypePolicy::parse_list("application/zip"),
+        };
+        assert!(check_upload_content("text/csv", b"a,b\n", &policy).is_ok());
+        assert!(matches!(
+            check_upload_content("application/octet-stream", b"PK\x03\x04", &policy),
+            Err(ApiError::UnsupportedMediaType(_))
+        ));
+        assert!(matches!(
+            check_upload_content("application/x-anything", b"PK\x03\x04", &policy),
+            Err(ApiError::UnsupportedMediaType(_))
+        ));
+        assert!(matches!(
+            check_upload_content("application/zip", b"not a zip", &policy),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn parse_multipart_upload_assigns_titles_by_position() {
+        use crate::service::file_type::ContentTypePolicy;
+
+        let body = b"--b\r\n\
+Content-Disposition: form-data; name=\"channel\"\r\n\r\n#deploys\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"thread_ts\"\r\n\r\n1700000000.000100\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"title\"\r\n\r\nFirst\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"alt_text\"\r\n\r\nA chart\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"files\"; filename=\"chart.png\"\r\n\
+Content-Type: image/png\r\n\r\n\x89PNG\r\n\x1a\n\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"files\"; filename=\"report.csv\"\r\n\
+Content-Type: text/csv\r\n\r\na,b\r\n\
+--b--\r\n";
+
+        let parsed = parse_multipart_upload(
+            "multipart/form-data; boundary=b",
+            body,
+            &ContentTypePolicy::default(),
+            MultipartLimits::default(),
+        )
+        .expect("form should parse");
+
+        assert_eq!(parsed.channel, "#deploys");
+        assert_eq!(
+            parsed.placement.thread_ts.as_deref(),
+            Some("1700000000.000100")
+        );
+        assert_eq!(parsed.placement.initial_comment, None);
+        assert_eq!(parsed.files.len(), 2);
+        assert_eq!(parsed.files[0].title, "First");
+        assert_eq!(parsed.files[0].alt_text.as_deref(), Some("A chart"));
+        assert_eq!(parsed.files[1].title, "report.csv");
+        assert_eq!(parsed.files[1].alt_text, None);
+        assert_eq!(parsed.files[1].data, b"a,b");
+    }
+
+    #[test]
+    fn parse_multipart_upload_rejects_missing_parts() {
+        use crate::{errors::api_error::ApiError, service::file_type::ContentTypePolicy};
+
+        let policy = ContentTypePolicy::default();
+        let no_files =
+            b"--b\r\nContent-Disposition: form-data; name=\"channel\"\r\n\r\nC1\r\n--b--\r\n";
+        assert!(matches!(
+            parse_multipart_upload(
+                "multipart/form-data; boundary=b",
+                no_files,
+                &policy,
+                MultipartLimits::default()
+            ),
+            Err(ApiError::BadRequest(_))
+        ));
+        assert!(matches!(
+            parse_multipart_upload(
+                "application/json",
+                b"{}",
+                &policy,
+                MultipartLimits::default()
+            ),
+            Err(ApiError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn read_single_upload_accepts_form_or_raw_body() {
+        let headers = vec![(
+            "Content-Type".to_string(),
+            "multipart/form-data; boundary=b".to_string(),
+        )];
+        let body = b"--b\r\n\
+Content-Disposition: form-data; name=\"channel\"\r\n\r\nC1\r\n\
+--b\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"doc.pdf\"\r\n\
+Content-Type: application/pdf\r\n\r\n%PDF-1.7\r\n\
+--b--\r\n";
+
+        let upload = read_single_upload(
+            Some("file_name=report.pdf"),
+            &headers,
+            body,
+            MultipartLimits::default(),
+        )
+        .expect("form should parse");
+        assert_eq!(upload.query.channel, "C1");
+        assert_eq!(upload.query.file_name.as_deref(), Some("report.pdf"));
+        assert_eq!(upload.content_type.as_deref(), Some("application/pdf"));
+        assert_eq!(&*upload.data, b"%PDF-1.7");
+
+        let raw_headers = vec![("content-type".to_string(), "Image/PNG".to_string())];
+        let upload = read_single_upload(
+            Some("channel=C2"),
+            &raw_headers,
+            b"raw",
+            MultipartLimits::default(),
+        )
+        .expect("raw body should parse");
+        assert_eq!(upload.query.channel, "C2");
+        assert_eq!(upload.content_type.as_deref(), Some("image/png"));
+        assert_eq!(&*upload.data, b"raw");
+    }
+
+    #[test]
+    fn parse_upload_from_s3_request_requires_location_and_channel() {
+        let parsed = parse_upload_from_s3_request(
+            r##"{"bucket": "reports", "key": "daily/2026-10-17", "channel": "#ops", "title": null}"##,
+        )
+        .expect("request should parse");
+        assert_eq!(parsed.key, "daily/2026-10-17");
+        assert_eq!(parsed.title, None);
+        assert!(parse_upload_from_s3_request(r#"{"bucket": "reports", "key": "a.pdf"}"#).is_err());
+    }
+
+    #[test]
+    fn file_name_from_key_adds_missing_extension() {
+        assert_eq!(file_name_from_key("daily/report.pdf", "pdf"), "report.pdf");
+        assert_eq!(
+            file_name_from_key("daily/2026-10-17", "pdf"),
+            "2026-10-17.pdf"
+        );
+        assert_eq!(file_name_from_key("daily/blob", "bin"), "blob");
+        assert_eq!(file_name_from_key("daily/", "csv"), "file-upload.csv");
+    }
+
+    #[test]
+    fn parse_archive_request_accepts_file_id_or_channel_filter() {
+        let by_id = parse_archive_request(r#"{"file_id": "F123"}"#).expect("file_id");
+        assert_eq!(
+            by_id.target,
+            ArchiveTarget::File {
+                file_id: "F123".to_string(),
+                channel: None
+            }
+        );
+        assert_eq!(by_id.bucket, None);
+
+        let by_filter = parse_archive_request(
+            r#"{"channel": "C1", "ts_from": "2026-10-17T00:00:00Z", "ts_to": 1792281600, "bucket": "archive"}"#,
+        )
+        .expect("filter");
+        let ArchiveTarget::List(filter) = by_filter.target else {
+            panic!("expected a files.list filter");
+        };
+        assert_eq!(filter.channel.as_deref(), Some("C1"));
+        assert_eq!(filter.ts_from, Some(1_792_195_200));
+        assert_eq!(filter.ts_to, Some(1_792_281_600));
+        assert_eq!(by_filter.bucket.as_deref(), Some("archive"));
+
+        assert!(parse_archive_request(r#"{"ts_from": 1}"#).is_err());
+        assert!(parse_archive_request(r#"{"file_id": "F1", "types": "pdfs"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn multibyte_timestamps_are_rejected_as_bad_request() {
+        let app_state = AppState::for_test();
+        let caller = CallerIdentity::anonymous();
+
+        let archived = archive_files(
+            &app_state,
+            &caller,
+            r#"{"channel": "C1", "ts_from": "2026-10-17T09:00:0\u00e90Z"}"#.as_bytes(),
+        )
+        .await;
+        assert!(matches!(archived, Err(ApiError::BadRequest(_))));
+
+        let scheduled = schedule_message(
+            &app_state,
+            &caller,
+            r#"{"channel": "C1", "text": "hi", "post_at": "2026-10-17T09:00:\u3042Z"}"#.as_bytes(),
+        )
+        .await;
+        assert!(matches!(scheduled, Err(ApiError::BadRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn export_runs_as_a_job_visible_to_its_owner() {
+        let app_state = AppState::for_test();
+        let caller = CallerIdentity::anonymous();
+
+        let started = export_conversation(
+            &app_state,
+            &caller,
+            br#"{"channel": "C9", "key": "inc-42.jsonl"}"#,
+        )
+        .await
+        .expect("job should start");
+        assert_eq!(started.status_code, 202);
+        let body = String::from_utf8(started.body).expect("UTF-8");
+        let json = nojson::RawJson::parse(&body).expect("JSON");
+        let job_id = String::try_from(
+            json.value()
+                .to_member("job_id")
+                .and_then(|member| member.required())
+                .expect("job_id"),
+        )
+        .expect("string");
+        assert!(body.contains(r#""status":"running""#), "{body}");
+        assert!(
+            body.contains(r#""bucket":"archive","key":"inc-42.jsonl""#),
+            "{body}"
+        );
+
+        let request = format!(r#"{{"job_id": "{job_id}"}}"#);
+        let status = export_status(&app_state, &caller, request.as_bytes())
+            .await
+            .expect("status");
+        assert_eq!(status.status_code, 200);
+        let other = CallerIdentity {
+            key_name: "other".to_string(),
+            scopes: vec![Scope::All],
+        };
+        assert!(matches!(
+            export_status(&app_state, &other, request.as_bytes()).await,
+            Err(ApiError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn template_is_not_loaded_before_the_channel_is_authorized() {
+        let app_state = AppState::for_test();
+        let caller = CallerIdentity {
+            key_name: "ops".to_string(),
+            scopes: vec![Scope::SlackPost("C1".to_string())],
+        };
+
+        let posted = post_template_message(
+            &app_state,
+            &caller,
+            br#"{"channel": "C9", "template": "missing"}"#,
+        )
+        .await;
+        assert!(matches!(posted, Err(ApiError::Forbidden(_))));
+    }
+}
@@ -0,0 +1,1252 @@
+mod pool;
+mod retry;
+mod timeout;
+
+pub use pool::PoolConfig;
+pub use retry::{RetryPolicy, retry_after};
+pub use timeout::{TimeoutConfig, TimeoutPhase};
+
+use pool::{ConnectionPool, ConnectionSlot, PoolKey};
+use rustls::{ClientConfig, RootCertStore, pki_types::ServerName};
+use shiguredo_http11::{
+    BodyKind, BodyProgress, DecoderLimits, HttpHead, Request, ResponseDecoder,
+    encode_request_headers, uri::Uri,
+};
+use std::{
+    cmp::min,
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::Poll,
+    time::{Duration, Instant},
+};
+use timeout::within;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+};
+use tokio_rustls::TlsConnector;
+use tracing::{debug, warn};
+use webpki_roots::TLS_SERVER_ROOTS;
+
+type Header = (String, String);
+
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<Header>,
+    pub body: Vec<u8>,
+}
+
+pub struct HttpResponse {
+    pub status_code: u16,
+    pub headers: Vec<Header>,
+    pub body: Vec<u8>,
+}
+
+/// ボディを逐次読み出すレスポンス。
+///
+/// ボディを最後まで読み切った keep-alive 接続はプールへ戻される。
+/// 途中で drop した場合は接続を閉じる。
+/// 受信が `TimeoutConfig::read_idle` 以上途切れるか、送信開始から `TimeoutConfig::total` を超えると
+/// `HttpClientError::Timeout` を返す。
+pub struct HttpResponseStream {
+    pub status_code: u16,
+    pub headers: Vec<Header>,
+    stream: Option<Box<dyn AsyncReadWrite>>,
+    decoder: ResponseDecoder,
+    finished: bool,
+    reusable: bool,
+    read_idle: Duration,
+    deadline: Instant,
+    pool: Arc<ConnectionPool>,
+    slot: Option<ConnectionSlot>,
+}
+
+impl HttpResponseStream {
+    pub async fn read_chunk(&mut self, output: &mut [u8]) -> Result<usize, HttpClientError> {
+        if output.is_empty() {
+            return Ok(0);
+        }
+        if self.finished {
+            return Ok(0);
+        }
+
+        let mut read_buffer = vec![0_u8; 8192];
+
+        loop {
+            if let Some(data) = self.decoder.peek_body()
+                && !data.is_empty()
+            {
+                let count = min(data.len(), output.len());
+                output[..count].copy_from_slice(&data[..count]);
+                let progress = self
+                    .decoder
+                    .consume_body(count)
+                    .map_err(|e| HttpClientError::Decode(e.to_string()))?;
+                if matches!(progress, BodyProgress::Complete { .. }) {
+                    self.finish();
+                }
+                return Ok(count);
+            }
+
+            match self
+                .decoder
+                .progress()
+                .map_err(|e| HttpClientError::Decode(e.to_string()))?
+            {
+                BodyProgress::Complete { .. } => {
+                    self.finish();
+                    return Ok(0);
+                }
+                BodyProgress::Continue => {}
+            }
+
+            let stream = self
+                .stream
+                .as_mut()
+                .ok_or_else(|| HttpClientError::Io("Connection already released".to_string()))?;
+            let remaining = self.deadline.saturating_duration_since(Instant::now());
+            let (limit, phase) = if remaining < self.read_idle {
+                (remaining, TimeoutPhase::Total)
+            } else {
+                (self.read_idle, TimeoutPhase::ReadIdle)
+            };
+            let n = within(limit, phase, stream.read(&mut read_buffer))
+                .await?
+                .map_err(|e| HttpClientError::Io(e.to_string()))?;
+
+            if n == 0 {
+                self.reusable = false;
+                self.decoder.mark_eof();
+                if matches!(
+                    self.decoder
+                        .progress()
+                        .map_err(|e| HttpClientError::Decode(e.to_string()))?,
+                    BodyProgress::Complete { .. }
+                ) {
+                    self.finish();
+                    return Ok(0);
+                }
+                return Err(HttpClientError::Decode(
+                    "Connection closed before the response body was fully received".to_string(),
+                ));
+            }
+
+            self.decoder
+                .feed(&read_buffer[..n])
+                .map_err(|e| HttpClientError::Decode(e.to_string()))?;
+        }
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+        let reusable = self.reusable && self.decoder.remaining().is_empty();
+        if let (true, Some(slot), Some(stream)) = (reusable, self.slot.take(), self.stream.take()) {
+            self.pool.checkin(slot, stream);
+        }
+    }
+}
+
+/// `head` に続けて `rest` の残りを読み切るまで送る、全体で `length` バイトの本文。
+pub struct StreamedBody<'a> {
+    pub head: Vec<u8>,
+    pub rest: &'a mut HttpResponseStream,
+    pub length: usize,
+}
+
+#[derive(Debug)]
+pub enum HttpClientError {
+    InvalidUrl(String),
+    UnsupportedScheme(String),
+    MissingHost,
+    Io(String),
+    Tls(String),
+    Decode(String),
+    Timeout(TimeoutPhase),
+}
+
+impl fmt::Display for HttpClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUrl(e) => write!(f, "Invalid URL: {e}"),
+            Self::UnsupportedScheme(s) => write!(f, "Unsupported URL scheme: {s}"),
+            Self::MissingHost => write!(f, "Missing URL host"),
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Tls(e) => write!(f, "TLS error: {e}"),
+            Self::Decode(e) => write!(f, "HTTP decode error: {e}"),
+            Self::Timeout(phase) => write!(f, "Timed out waiting for {phase}"),
+        }
+    }
+}
+
+impl std::error::Error for HttpClientError {}
+
+trait AsyncReadWrite: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T> AsyncReadWrite for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// エンコード済みのリクエストと接続先。
+struct PreparedRequest {
+    key: PoolKey,
+    bytes: Vec<u8>,
+    expect_no_body: bool,
+    /// 送信後に接続が切れても送り直してよいか
+    idempotent: bool,
+}
+
+/// 送信済みの接続と、最初に受信したレスポンスのバイト列。
+struct Exchange {
+    slot: ConnectionSlot,
+    stream: Box<dyn AsyncReadWrite>,
+    first_bytes: Vec<u8>,
+}
+
+#[derive(Clone)]
+pub struct HttpClient {
+    tls_connector: TlsConnector,
+    pool: Arc<ConnectionPool>,
+    timeouts: TimeoutConfig,
+    retry: RetryPolicy,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpClient {
+    pub fn new() -> Self {
+        let root_store = RootCertStore::from_iter(TLS_SERVER_ROOTS.iter().cloned());
+        let tls_config = ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+
+        Self {
+            tls_connector: TlsConnector::from(Arc::new(tls_config)),
+            pool: Arc::new(ConnectionPool::new(PoolConfig::default())),
+            timeouts: TimeoutConfig::default(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_pool_config(mut self, config: PoolConfig) -> Self {
+        self.pool = Arc::new(ConnectionPool::new(config));
+        self
+    }
+
+    pub fn with_timeouts(mut self, timeouts: TimeoutConfig) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// リクエストを送信する。冪等なメソッドは `RetryPolicy` に従って再試行する。
+    pub async fn send(&self, request: HttpRequest) -> Result<HttpResponse, HttpClientError> {
+        let idempotent = retry::is_idempotent_method(&request.method);
+        self.send_with_retry(request, idempotent, true).await
+    }
+
+    /// `send` と同じだが 429 は再試行せずそのまま返す。
+    /// レート制限を呼び出し元のスケジューラーで扱う場合に使う。
+    pub async fn send_without_rate_limit_retry(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let idempotent = retry::is_idempotent_method(&request.method);
+        self.send_with_retry(request, idempotent, false).await
+    }
+
+    /// 再送しても結果が変わらないことを呼び出し元が保証する POST などを、冪等なリクエストとして送信する。
+    pub async fn send_idempotent(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, HttpClientError> {
+        self.send_with_retry(request, true, true).await
+    }
+
+    async fn send_with_retry(
+        &self,
+        request: HttpRequest,
+        idempotent: bool,
+        retry_rate_limited: bool,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let started = Instant::now();
+        let deadline = started + self.retry.deadline;
+        let max_attempts = self.retry.max_attempts.max(1);
+        let mut attempt = 1;
+
+        loop {
+            debug!(
+                attempt,
+                max_attempts,
+                method = %request.method,
+                url = %redact_query(&request.url),
+                "Sending HTTP request"
+            );
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let result = within(
+                remaining,
+                TimeoutPhase::Total,
+                self.send_once(request.clone(), idempotent),
+            )
+            .await
+            .and_then(|result| result);
+
+            let (delay, reason) = match &result {
+                Ok(response) if response.status_code == 429 && !retry_rate_limited => {
+                    return result;
+                }
+                Ok(response) if retry::is_retryable_status(response.status_code, idempotent) => (
+                    retry::retry_after(&response.headers)
+                        .unwrap_or_else(|| self.retry.backoff(attempt)),
+                    format!("status {}", response.status_code),
+                ),
+                Err(e) if idempotent && retry::is_retryable_error(e) => {
+                    (self.retry.backoff(attempt), e.to_string())
+                }
+                _ => return result,
+            };
+
+            if attempt >= max_attempts || started.elapsed() + delay > self.retry.deadline {
+                warn!(
+                    attempt,
+                    max_attempts,
+                    method = %request.method,
+                    url = %redact_query(&request.url),
+                    reason = %reason,
+                    "Giving up HTTP request retries"
+                );
+                return result;
+            }
+
+            warn!(
+                attempt,
+                max_attempts,
+                method = %request.method,
+                url = %redact_query(&request.url),
+                reason = %reason,
+                delay_ms = delay.as_millis() as u64,
+                "Retrying HTTP request"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn send_once(
+        &self,
+        request: HttpRequest,
+        idempotent: bool,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let mut prepared = prepare_request(request)?;
+        prepared.idempotent = idempotent;
+        within(
+            self.timeouts.total,
+            TimeoutPhase::Total,
+            self.send_prepared(prepared),
+        )
+        .await?
+    }
+
+    pub async fn send_streaming(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponseStream, HttpClientError> {
+        let prepared = prepare_request(request)?;
+        let deadline = Instant::now() + self.timeouts.total;
+        within(
+            self.timeouts.total,
+            TimeoutPhase::Total,
+            self.send_streaming_prepared(prepared, deadline),
+        )
+        .await?
+    }
+
+    /// `body` を読み進めながら本文として送信する。`request.body` は使わない。
+    /// 送り直せないため再試行しない。
+    pub async fn send_streamed_body(
+        &self,
+        request: HttpRequest,
+        body: StreamedBody<'_>,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let prepared = prepare_streamed_request(request, body.length)?;
+        within(
+            self.timeouts.total,
+            TimeoutPhase::Total,
+            self.send_streamed_prepared(prepared, body),
+        )
+        .await?
+    }
+
+    async fn send_prepared(
+        &self,
+        prepared: PreparedRequest,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let Exchange {
+            slot,
+            stream,
+            first_bytes,
+        } = self.exchange(&prepared).await?;
+        self.read_response(slot, stream, first_bytes, prepared.expect_no_body)
+            .await
+    }
+
+    async fn send_streamed_prepared(
+        &self,
+        prepared: PreparedRequest,
+        body: StreamedBody<'_>,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let (slot, idle) = self.pool.checkout(prepared.key.clone()).await;
+        let mut stream = match self.reuse_idle(&prepared.key, idle).await {
+            Some(stream) => stream,
+            None => self.connect(&prepared.key).await?,
+        };
+
+        let mut sent = body.head.len();
+        stream
+            .write_all(&prepared.bytes)
+            .await
+            .map_err(|e| HttpClientError::Io(e.to_string()))?;
+        stream
+            .write_all(&body.head)
+            .await
+            .map_err(|e| HttpClientError::Io(e.to_string()))?;
+        let mut chunk = vec![0_u8; 64 * 1024];
+        loop {
+            let n = body.rest.read_chunk(&mut chunk).await?;
+            if n == 0 {
+                break;
+            }
+            sent += n;
+            if sent > body.length {
+                break;
+            }
+            stream
+                .write_all(&chunk[..n])
+                .await
+                .map_err(|e| HttpClientError::Io(e.to_string()))?;
+        }
+        if sent != body.length {
+            return Err(HttpClientError::Io(format!(
+                "Request body was {sent} bytes but {} bytes were declared",
+                body.length
+            )));
+        }
+        stream
+            .flush()
+            .await
+            .map_err(|e| HttpClientError::Io(e.to_string()))?;
+
+        let first_bytes = self.read_first(&mut stream).await?;
+        self.read_response(slot, stream, first_bytes, prepared.expect_no_body)
+            .await
+    }
+
+    /// 最初に受信したバイト列に続けてレスポンスを読み切る。keep-alive の接続はプールへ戻す。
+    async fn read_response(
+        &self,
+        slot: ConnectionSlot,
+        mut stream: Box<dyn AsyncReadWrite>,
+        first_bytes: Vec<u8>,
+        expect_no_body: bool,
+    ) -> Result<HttpResponse, HttpClientError> {
+        let mut decoder = ResponseDecoder::new();
+        decoder.set_expect_no_body(expect_no_body);
+        decoder
+            .feed(&first_bytes)
+            .map_err(|e| HttpClientError::Decode(e.to_string()))?;
+
+        let mut buf = vec![0_u8; 8192];
+        let mut eof = first_bytes.is_empty();
+
+        loop {
+            if eof {
+                decoder.mark_eof();
+            }
+            if let Some(response) = decoder
+                .decode()
+                .map_err(|e| HttpClientError::Decode(e.to_string()))?
+            {
+                if !eof && response.is_keep_alive() && decoder.remaining().is_empty() {
+                    self.pool.checkin(slot, stream);
+                }
+                return Ok(HttpResponse {
+                    status_code: response.status_code,
+                    headers: response.headers,
+                    body: response.body,
+                });
+            }
+            if eof {
+                return Err(HttpClientError::Decode(
+                    "Connection closed before a complete response was received".to_string(),
+                ));
+            }
+
+            let n = stream
+                .read(&mut buf)
+                .await
+                .map_err(|e| HttpClientError::Io(e.to_string()))?;
+            if n == 0 {
+                eof = true;
+                continue;
+            }
+
+            decoder
+                .feed(&buf[..n])
+                .map_err(|e| HttpClientError::Decode(e.to_string()))?;
+        }
+    }
+
+    async fn send_streaming_prepared(
+        &self,
+        prepared: PreparedRequest,
+        deadline: Instant,
+    ) -> Result<HttpResponseStream, HttpClientError> {
+        let mut decoder = ResponseDecoder::with_limits(DecoderLimits::unlimited());
+        decoder.set_expect_no_body(prepared.expect_no_body);
+
+        let Exchange {
+            slot,
+            mut stream,
+            first_bytes,
+        } = self.exchange(&prepared).await?;
+        decoder
+            .feed(&first_bytes)
+            .map_err(|e| HttpClientError::Decode(e.to_string()))?;
+
+        let mut buf = vec![0_u8; 8192];
+
+        loop {
+            if let Some((head, body_kind)) = decoder
+                .decode_headers()
+                .map_err(|e| HttpClientError::Decode(e.to_string()))?
+            {
+                let reusable =
+                    head.is_keep_alive() && !matches!(body_kind, BodyKind::CloseDelimited);
+                return Ok(HttpResponseStream {
+                    status_code: head.status_code,
+                    headers: head.headers,
+                    stream: Some(stream),
+                    decoder,
+                    finished: false,
+                    reusable,
+                    read_idle: self.timeouts.read_idle,
+                    deadline,
+                    pool: self.pool.clone(),
+                    slot: Some(slot),
+                });
+            }
+
+            let n = stream
+                .read(&mut buf)
+                .await
+                .map_err(|e| HttpClientError::Io(e.to_string()))?;
+            if n == 0 {
+                return Err(HttpClientError::Decode(
+                    "Connection closed before response headers were fully received".to_string(),
+                ));
+            }
+
+            decoder
+                .feed(&buf[..n])
+                .map_err(|e| HttpClientError::Decode(e.to_string()))?;
+        }
+    }
+
+    /// リクエストを送信し、最初のレスポンスバイトを受信するまで進める。
+    ///
+    /// プールから取り出した接続は、サーバー側で既に閉じられていれば送信前に捨てて新しく接続する。
+    /// 確認の直後に切れた場合も新しい接続で一度だけ送り直すが、書き込みまで終えた後に切れた場合は
+    /// サーバーが処理した可能性があるため、冪等なリクエストに限る。
+    async fn exchange(&self, prepared: &PreparedRequest) -> Result<Exchange, HttpClientError> {
+        let (slot, idle) = self.pool.checkout(prepared.key.clone()).await;
+        if let Some(mut stream) = self.reuse_idle(&prepared.key, idle).await {
+            match write_request(&mut stream, &prepared.bytes).await {
+                Ok(()) => match self.read_first(&mut stream).await {
+                    Ok(first_bytes) if !first_bytes.is_empty() => {
+                        return Ok(Exchange {
+                            slot,
+                            stream,
+                            first_bytes,
+                        });
+                    }
+                    Ok(_) if prepared.idempotent => {
+                        debug!(host = %prepared.key.host, "pooled connection was closed by peer")
+                    }
+                    Err(HttpClientError::Io(e)) if prepared.idempotent => {
+                        debug!(host = %prepared.key.host, error = %e, "pooled connection failed")
+                    }
+                    Ok(_) => {
+                        return Err(HttpClientError::Io(
+                            "Connection closed after the request was sent".to_string(),
+                        ));
+                    }
+                    Err(e) => return Err(e),
+                },
+                Err(HttpClientError::Io(e)) => {
+                    debug!(host = %prepared.key.host, error = %e, "pooled connection failed before the request was sent")
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut stream = self.connect(&prepared.key).await?;
+        write_request(&mut stream, &prepared.bytes).await?;
+        let first_bytes = self.read_first(&mut stream).await?;
+        Ok(Exchange {
+            slot,
+            stream,
+            first_bytes,
+        })
+    }
+
+    /// プールから取り出した接続が、サーバー側で閉じられていなければ返す。
+    async fn reuse_idle(
+        &self,
+        key: &PoolKey,
+        idle: Option<Box<dyn AsyncReadWrite>>,
+    ) -> Option<Box<dyn AsyncReadWrite>> {
+        let mut stream = idle?;
+        let open = is_open(&mut stream).await;
+        if !open {
+            debug!(host = %key.host, "pooled connection was closed while idle");
+        }
+        open.then_some(stream)
+    }
+
+    async fn connect(&self, key: &PoolKey) -> Result<Box<dyn AsyncReadWrite>, HttpClientError> {
+        let tcp = within(
+            self.timeouts.connect,
+            TimeoutPhase::Connect,
+            TcpStream::connect((key.host.as_str(), key.port)),
+        )
+        .await?
+        .map_err(|e| HttpClientError::Io(e.to_string()))?;
+
+        match key.scheme.as_str() {
+            "http" => Ok(Box::new(tcp)),
+            "https" => {
+                let server_name = ServerName::try_from(key.host.clone())
+                    .map_err(|e| HttpClientError::Tls(e.to_string()))?;
+                let tls = within(
+                    self.timeouts.tls_handshake,
+                    TimeoutPhase::TlsHandshake,
+                    self.tls_connector.connect(server_name, tcp),
+                )
+                .await?
+                .map_err(|e| HttpClientError::Tls(e.to_string()))?;
+                Ok(Box::new(tls))
+            }
+            other => Err(HttpClientError::UnsupportedScheme(other.to_string())),
+        }
+    }
+
+    async fn read_first(
+        &self,
+        stream: &mut Box<dyn AsyncReadWrite>,
+    ) -> Result<Vec<u8>, HttpClientError> {
+        let mut buf = vec![0_u8; 8192];
+        let n = within(
+            self.timeouts.first_byte,
+            TimeoutPhase::FirstByte,
+            stream.read(&mut buf),
+        )
+        .await?
+        .map_err(|e| HttpClientError::Io(e.to_string()))?;
+        buf.truncate(n);
+        Ok(buf)
+    }
+}
+
+/// アイドル中の接続が使えるかを待たずに確かめる。EOF やエラー、要求していないデータが届いていれば使わない。
+async fn is_open(stream: &mut Box<dyn AsyncReadWrite>) -> bool {
+    let mut byte = [0_u8; 1];
+    std::future::poll_fn(|cx| {
+        let mut buf = ReadBuf::new(&mut byte);
+        Poll::Ready(Pin::new(&mut *stream).poll_read(cx, &mut buf).is_pending())
+    })
+    .await
+}
+
+async fn write_request(
+    stream: &mut Box<dyn AsyncReadWrite>,
+    bytes: &[u8],
+) -> Result<(), HttpClientError> {
+    stream
+        .write_all(bytes)
+        .await
+        .map_err(|e| HttpClientError::Io(e.to_string()))?;
+    stream
+        .flush()
+        .await
+        .map_err(|e| HttpClientError::Io(e.to_string()))
+}
+
+/// ログに署名やトークンを残さないよう、クエリ文字列を取り除く。
+fn redact_query(url: &str) -> &str {
+    url.split_once('?').map_or(url, |(base, _)| base)
+}
+
+fn prepare_request(request: HttpRequest) -> Result<PreparedRequest, HttpClientError> {
+    let expect_no_body = request.method.eq_ignore_ascii_case("HEAD");
+    let idempotent = retry::is_idempotent_method(&request.method);
+    let (key, req) = build_request(request)?;
+    Ok(PreparedRequest {
+        key,
+        bytes: req.encode(),
+        expect_no_body,
+        idempotent,
+    })
+}
+
+/// 本文を別に送るリクエストのヘッダーを、`Content-Length: length` を付けてエンコードする。
+fn prepare_streamed_request(
+    request: HttpRequest,
+    length: usize,
+) -> Result<PreparedRequest, HttpClientError> {
+    let (key, req) = build_request(HttpRequest {
+        body: Vec::new(),
+        ..request
+    })?;
+    let bytes = encode_request_headers(&req.header("Content-Length", &length.to_string()))
+        .map_err(|e| HttpClientError::InvalidUrl(e.to_string()))?;
+    Ok(PreparedRequest {
+        key,
+        bytes,
+        expect_no_body: false,
+        idempotent: false,
+    })
+}
+
+fn build_request(request: HttpRequest) -> Result<(PoolKey, Request), HttpClientError> {
+    let uri = Uri::parse(&request.url).map_err(|e| HttpClientError::InvalidUrl(e.to_string()))?;
+
+    let scheme = uri
+        .scheme()
+        .ok_or_else(|| HttpClientError::InvalidUrl(request.url.clone()))?
+        .to_ascii_lowercase();
+    if scheme != "http" && scheme != "https" {
+        return Err(HttpClientError::UnsupportedScheme(scheme));
+    }
+    let host = uri.host().ok_or(HttpClientError::MissingHost)?.to_string();
+    let port = uri
+        .port()
+        .unwrap_or_else(|| if scheme == "https" { 443 } else { 80 });
+
+    let mut target = uri.path().to_string();
+    if target.is_empty() {
+        target = "/".to_string();
+    }
+    if let Some(query) = uri.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+
+    let has_host_header = request
+        .headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("host"));
+
+    let mut req = Request::new(&request.method, &target);
+    if !has_host_header {
+        req = req.header("Host", &host);
+    }
+    for (name, value) in &request.headers {
+        req = req.header(name, value);
+    }
+    if !request.body.is_empty() {
+        req = req.body(request.body);
+    }
+
+    Ok((PoolKey { scheme, host, port }, req))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        HttpClient, HttpClientError, HttpRequest, RetryPolicy, StreamedBody, TimeoutConfig,
+        TimeoutPhase,
+    };
+    use std::time::Duration;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// 1本の接続で `responses` を順に返し、受け付けた接続数を返すサーバー。
+    async fn serve(listener: TcpListener, responses: Vec<&'static str>) -> usize {
+        let mut accepted = 0;
+        let mut pending = responses.into_iter();
+        while let Some(first) = pending.next() {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            accepted += 1;
+            let mut next = Some(first);
+            while let Some(response) = next {
+                let mut buf = vec![0_u8; 4096];
+                let n = socket.read(&mut buf).await.expect("read");
+                if n == 0 {
+                    break;
+                }
+                socket.write_all(response.as_bytes()).await.expect("write");
+                if response.contains("Connection: close") {
+                    break;
+                }
+                next = pending.next();
+            }
+        }
+        accepted
+    }
+
+    #[tokio::test]
+    async fn keep_alive_connection_is_reused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+                "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nyes",
+            ],
+        ));
+
+        let client = HttpClient::new();
+        let url = format!("http://{addr}/");
+        let first = client.send(get(&url)).await.expect("first");
+        let second = client.send(get(&url)).await.expect("second");
+
+        assert_eq!(first.body, b"ok");
+        assert_eq!(second.body, b"yes");
+        assert_eq!(server.await.expect("server"), 1);
+    }
+
+    #[tokio::test]
+    async fn closed_connection_is_not_reused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok",
+                "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nyes",
+            ],
+        ));
+
+        let client = HttpClient::new();
+        let url = format!("http://{addr}/");
+        client.send(get(&url)).await.expect("first");
+        let second = client.send(get(&url)).await.expect("second");
+
+        assert_eq!(second.body, b"yes");
+        assert_eq!(server.await.expect("server"), 2);
+    }
+
+    #[tokio::test]
+    async fn streamed_response_returns_connection_after_body_is_read() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            ],
+        ));
+
+        let client = HttpClient::new();
+        let url = format!("http://{addr}/");
+        let mut stream = client.send_streaming(get(&url)).await.expect("stream");
+        let mut body = Vec::new();
+        let mut chunk = [0_u8; 2];
+        loop {
+            let n = stream.read_chunk(&mut chunk).await.expect("chunk");
+            if n == 0 {
+                break;
+            }
+            body.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(body, b"hello");
+
+        let second = client.send(get(&url)).await.expect("second");
+        assert_eq!(second.body, b"ok");
+        assert_eq!(server.await.expect("server"), 1);
+    }
+
+    /// リクエストを1件受け付け、受信した本文をそのまま返すサーバー。
+    async fn echo_body(listener: TcpListener) -> String {
+        let (mut socket, _) = listener.accept().await.expect("accept");
+        let mut received = Vec::new();
+        let mut buf = vec![0_u8; 4096];
+        let (head, body) = loop {
+            let n = socket.read(&mut buf).await.expect("read");
+            received.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&received).to_string();
+            if let Some((head, body)) = text.split_once("\r\n\r\n")
+                && let Some(length) = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                && body.len() >= length.parse::<usize>().expect("length")
+            {
+                break (head.to_string(), body.to_string());
+            }
+            assert!(n > 0, "request ended early");
+        };
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        socket.write_all(response.as_bytes()).await.expect("write");
+        head
+    }
+
+    #[tokio::test]
+    async fn streamed_body_is_sent_after_its_head() {
+        let source = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let source_addr = source.local_addr().expect("addr");
+        tokio::spawn(serve(
+            source,
+            vec!["HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\n world"],
+        ));
+        let sink = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let sink_addr = sink.local_addr().expect("addr");
+        let server = tokio::spawn(echo_body(sink));
+
+        let client = HttpClient::new();
+        let mut rest = client
+            .send_streaming(get(&format!("http://{source_addr}/")))
+            .await
+            .expect("source");
+        let response = client
+            .send_streamed_body(
+                HttpRequest {
+                    method: "POST".to_string(),
+                    url: format!("http://{sink_addr}/upload"),
+                    headers: Vec::new(),
+                    body: Vec::new(),
+                },
+                StreamedBody {
+                    head: b"hello".to_vec(),
+                    rest: &mut rest,
+                    length: 11,
+                },
+            )
+            .await
+            .expect("upload");
+
+        assert_eq!(response.body, b"hello world");
+        assert!(server.await.expect("server").contains("Content-Length: 11"));
+    }
+
+    #[tokio::test]
+    async fn streamed_body_shorter_than_declared_is_an_error() {
+        let source = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let source_addr = source.local_addr().expect("addr");
+        tokio::spawn(serve(
+            source,
+            vec!["HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok"],
+        ));
+        let sink = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let sink_addr = sink.local_addr().expect("addr");
+
+        let client = HttpClient::new();
+        let mut rest = client
+            .send_streaming(get(&format!("http://{source_addr}/")))
+            .await
+            .expect("source");
+        let result = client
+            .send_streamed_body(
+                HttpRequest {
+                    method: "POST".to_string(),
+                    url: format!("http://{sink_addr}/upload"),
+                    headers: Vec::new(),
+                    body: Vec::new(),
+                },
+                StreamedBody {
+                    head: Vec::new(),
+                    rest: &mut rest,
+                    length: 5,
+                },
+            )
+            .await;
+
+        assert!(matches!(result, Err(HttpClientError::Io(_))));
+    }
+
+    /// 最初の接続は keep-alive の応答を返した直後に閉じ、次の接続では `second` を返すサーバー。
+    async fn serve_then_close(listener: TcpListener, second: &'static str) -> usize {
+        let mut accepted = 0;
+        for response in ["HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok", second] {
+            let Ok(Ok((mut socket, _))) =
+                tokio::time::timeout(Duration::from_millis(200), listener.accept()).await
+            else {
+                break;
+            };
+            accepted += 1;
+            let mut buf = vec![0_u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            socket.write_all(response.as_bytes()).await.expect("write");
+        }
+        accepted
+    }
+
+    #[tokio::test]
+    async fn get_is_resent_when_pooled_connection_was_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve_then_close(
+            listener,
+            "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nyes",
+        ));
+
+        let client = HttpClient::new().with_retry_policy(RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        });
+        let url = format!("http://{addr}/");
+        client.send(get(&url)).await.expect("first");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let second = client.send(get(&url)).await.expect("second");
+
+        assert_eq!(second.body, b"yes");
+        assert_eq!(server.await.expect("server"), 2);
+    }
+
+    #[tokio::test]
+    async fn post_uses_fresh_connection_when_pooled_connection_was_closed() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve_then_close(
+            listener,
+            "HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nyes",
+        ));
+
+        let client = HttpClient::new();
+        let url = format!("http://{addr}/");
+        client.send(get(&url)).await.expect("first");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let mut request = get(&url);
+        request.method = "POST".to_string();
+        let second = client.send(request).await.expect("second");
+
+        assert_eq!(second.body, b"yes");
+        assert_eq!(server.await.expect("server"), 2);
+    }
+
+    fn short_timeouts() -> TimeoutConfig {
+        TimeoutConfig {
+            first_byte: Duration::from_millis(50),
+            read_idle: Duration::from_millis(50),
+            ..TimeoutConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn silent_server_times_out_waiting_for_first_byte() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.expect("accept");
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            drop(socket);
+        });
+
+        let client = HttpClient::new()
+            .with_timeouts(short_timeouts())
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            });
+        let result = client.send(get(&format!("http://{addr}/"))).await;
+
+        assert!(matches!(
+            result,
+            Err(HttpClientError::Timeout(TimeoutPhase::FirstByte))
+        ));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn stalled_body_times_out_while_streaming() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            let mut buf = vec![0_u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nabc")
+                .await
+                .expect("write");
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        });
+
+        let client = HttpClient::new().with_timeouts(short_timeouts());
+        let mut stream = client
+            .send_streaming(get(&format!("http://{addr}/")))
+            .await
+            .expect("stream");
+        let mut chunk = [0_u8; 16];
+        assert_eq!(stream.read_chunk(&mut chunk).await.expect("chunk"), 3);
+
+        let result = stream.read_chunk(&mut chunk).await;
+        assert!(matches!(
+            result,
+            Err(HttpClientError::Timeout(TimeoutPhase::ReadIdle))
+        ));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn slow_body_times_out_at_total_deadline_while_streaming() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.expect("accept");
+            let mut buf = vec![0_u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100\r\n\r\n")
+                .await
+                .expect("write");
+            for _ in 0..100 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                if socket.write_all(b"a").await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let client = HttpClient::new().with_timeouts(TimeoutConfig {
+            total: Duration::from_millis(200),
+            ..short_timeouts()
+        });
+        let mut stream = client
+            .send_streaming(get(&format!("http://{addr}/")))
+            .await
+            .expect("stream");
+        let mut chunk = [0_u8; 16];
+        let result = loop {
+            match stream.read_chunk(&mut chunk).await {
+                Ok(0) => break Ok(0),
+                Ok(_) => continue,
+                Err(e) => break Err(e),
+            }
+        };
+        assert!(matches!(
+            result,
+            Err(HttpClientError::Timeout(TimeoutPhase::Total))
+        ));
+        server.abort();
+    }
+
+    fn fast_retry() -> RetryPolicy {
+        RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn attempt_is_cut_off_at_retry_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.expect("accept");
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(socket);
+        });
+
+        let client = HttpClient::new().with_retry_policy(RetryPolicy {
+            deadline: Duration::from_millis(100),
+            ..fast_retry()
+        });
+        let started = std::time::Instant::now();
+        let result = client.send(get(&format!("http://{addr}/"))).await;
+
+        assert!(matches!(
+            result,
+            Err(HttpClientError::Timeout(TimeoutPhase::Total))
+        ));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn idempotent_request_is_retried_after_server_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            ],
+        ));
+
+        let client = HttpClient::new().with_retry_policy(fast_retry());
+        let response = client
+            .send(get(&format!("http://{addr}/")))
+            .await
+            .expect("response");
+
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, b"ok");
+        server.await.expect("server");
+    }
+
+    #[tokio::test]
+    async fn post_is_not_retried_after_server_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                "HTTP/1.1 503 Service Unavailable\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            ],
+        ));
+
+        let client = HttpClient::new().with_retry_policy(fast_retry());
+        let mut request = get(&format!("http://{addr}/"));
+        request.method = "POST".to_string();
+        let response = client.send(request).await.expect("response");
+
+        assert_eq!(response.status_code, 503);
+        assert_eq!(server.await.expect("server"), 1);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_post_is_retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(serve(
+            listener,
+            vec![
+                "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok",
+            ],
+        ));
+
+        let client = HttpClient::new().with_retry_policy(fast_retry());
+        let mut request = get(&format!("http://{addr}/"));
+        request.method = "POST".to_string();
+        let response = client.send(request).await.expect("response");
+
+        assert_eq!(response.status_code, 200);
+        server.await.expect("server");
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::AsyncReadWrite;
+
+/// アイドル接続プールの設定
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// 同一ホストに保持するアイドル接続数の上限 (0 でプール無効)
+    pub max_idle_per_host: usize,
+    /// アイドル接続を再利用できる最大時間
+    pub idle_timeout: Duration,
+    /// 同一ホストへ同時に使用する接続数の上限
+    pub max_per_host: usize,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 8,
+            // 多くのサーバーの keep-alive (60 秒前後) より短くする
+            idle_timeout: Duration::from_secs(30),
+            max_per_host: 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) struct PoolKey {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+}
+
+struct IdleConnection {
+    stream: Box<dyn AsyncReadWrite>,
+    idle_since: Instant,
+}
+
+struct HostPool {
+    idle: Vec<IdleConnection>,
+    limiter: Arc<Semaphore>,
+}
+
+/// 使用中の接続枠。drop すると同一ホストの同時接続枠が解放される。
+pub(super) struct ConnectionSlot {
+    pub key: PoolKey,
+    _permit: OwnedSemaphorePermit,
+}
+
+pub(super) struct ConnectionPool {
+    config: PoolConfig,
+    hosts: Mutex<HashMap<PoolKey, HostPool>>,
+}
+
+impl ConnectionPool {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 同時接続枠を確保し、再利用できるアイドル接続があれば返す。
+    pub async fn checkout(
+        &self,
+        key: PoolKey,
+    ) -> (ConnectionSlot, Option<Box<dyn AsyncReadWrite>>) {
+        let limiter = {
+            let mut hosts = self.lock_hosts();
+            hosts
+                .entry(key.clone())
+                .or_insert_with(|| HostPool {
+                    idle: Vec::new(),
+                    limiter: Arc::new(Semaphore::new(self.config.max_per_host.max(1))),
+                })
+                .limiter
+                .clone()
+        };
+
+        let permit = limiter
+            .acquire_owned()
+            .await
+            .expect("connection limiter is never closed");
+
+        let idle = {
+            let mut hosts = self.lock_hosts();
+            hosts.get_mut(&key).and_then(|host| {
+                let now = Instant::now();
+                host.idle
+                    .retain(|conn| now.duration_since(conn.idle_since) < self.config.idle_timeout);
+                host.idle.pop().map(|conn| conn.stream)
+            })
+        };
+
+        (
+            ConnectionSlot {
+                key,
+                _permit: permit,
+            },
+            idle,
+        )
+    }
+
+    /// レスポンスを最後まで読み終えた keep-alive 接続をプールへ戻す。
+    pub fn checkin(&self, slot: ConnectionSlot, stream: Box<dyn AsyncReadWrite>) {
+        if self.config.max_idle_per_host == 0 {
+            return;
+        }
+
+        let mut hosts = self.lock_hosts();
+        let Some(host) = hosts.get_mut(&slot.key) else {
+            return;
+        };
+        let now = Instant::now();
+        host.idle
+            .retain(|conn| now.duration_since(conn.idle_since) < self.config.idle_timeout);
+        if host.idle.len() < self.config.max_idle_per_host {
+            host.idle.push(IdleConnection {
+                stream,
+                idle_since: now,
+            });
+        }
+    }
+
+    #[cfg(test)]
+    fn idle_count(&self, key: &PoolKey) -> usize {
+        self.lock_hosts()
+            .get(key)
+            .map(|host| host.idle.len())
+            .unwrap_or(0)
+    }
+
+    fn lock_hosts(&self) -> std::sync::MutexGuard<'_, HashMap<PoolKey, HostPool>> {
+        self.hosts.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConnectionPool, PoolConfig, PoolKey};
+    use std::time::Duration;
+
+    fn key() -> PoolKey {
+        PoolKey {
+            scheme: "http".to_string(),
+            host: "rustfs.local".to_string(),
+            port: 9000,
+        }
+    }
+
+    fn stream() -> Box<dyn super::AsyncReadWrite> {
+        let (client, _server) = tokio::io::duplex(64);
+        Box::new(client)
+    }
+
+    #[tokio::test]
+    async fn returned_connection_is_reused() {
+        let pool = ConnectionPool::new(PoolConfig::default());
+
+        let (slot, idle) = pool.checkout(key()).await;
+        assert!(idle.is_none());
+        pool.checkin(slot, stream());
+        assert_eq!(pool.idle_count(&key()), 1);
+
+        let (_slot, idle) = pool.checkout(key()).await;
+        assert!(idle.is_some());
+        assert_eq!(pool.idle_count(&key()), 0);
+    }
+
+    #[tokio::test]
+    async fn idle_connections_are_capped_per_host() {
+        let pool = ConnectionPool::new(PoolConfig {
+            max_idle_per_host: 1,
+            ..PoolConfig::default()
+        });
+
+        let (first, _) = pool.checkout(key()).await;
+        let (second, _) = pool.checkout(key()).await;
+        pool.checkin(first, stream());
+        pool.checkin(second, stream());
+
+        assert_eq!(pool.idle_count(&key()), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_connections_are_discarded() {
+        let pool = ConnectionPool::new(PoolConfig {
+            idle_timeout: Duration::ZERO,
+            ..PoolConfig::default()
+        });
+
+        let (slot, _) = pool.checkout(key()).await;
+        pool.checkin(slot, stream());
+
+        let (_slot, idle) = pool.checkout(key()).await;
+        assert!(idle.is_none());
+    }
+
+    #[tokio::test]
+    async fn checkout_waits_for_free_slot() {
+        let pool = ConnectionPool::new(PoolConfig {
+            max_per_host: 1,
+            ..PoolConfig::default()
+        });
+
+        let (slot, _) = pool.checkout(key()).await;
+        let blocked = tokio::time::timeout(Duration::from_millis(20), pool.checkout(key())).await;
+        assert!(blocked.is_err());
+
+        drop(slot);
+        let acquired = tokio::time::timeout(Duration::from_millis(20), pool.checkout(key())).await;
+        assert!(acquired.is_ok());
+    }
+}
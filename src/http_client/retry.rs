@@ -0,0 +1,125 @@
+use std::{collections::hash_map::RandomState, hash::BuildHasher, time::Duration};
+
+use super::HttpClientError;
+
+/// `HttpClient::send` の再試行ポリシー
+///
+/// 冪等なメソッド (GET / HEAD / PUT / DELETE / OPTIONS) は 5xx・接続エラー・タイムアウトで再試行する。
+/// POST は `HttpClient::send_idempotent` で明示した場合のみ同じ扱いになる。
+/// 429 はリクエストが処理されていないため、メソッドによらず再試行する。
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// 初回を含む最大試行回数 (1 で再試行しない)
+    pub max_attempts: u32,
+    /// 指数バックオフの初期待ち時間
+    pub base_delay: Duration,
+    /// 1回あたりの待ち時間の上限
+    pub max_delay: Duration,
+    /// 初回送信からの全体の期限。試行中でも期限で打ち切り、次の待ち時間がこれを超える場合は再試行しない
+    pub deadline: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            deadline: Duration::from_secs(120),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// `attempt` 回目の失敗後の待ち時間。上限付きの指数バックオフに、後半半分のジッターを加える。
+    pub(super) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay = self
+            .base_delay
+            .saturating_mul(1_u32 << exponent)
+            .min(self.max_delay);
+        let half = delay / 2;
+        let jitter_range = (delay - half).as_millis() as u64;
+        if jitter_range == 0 {
+            return delay;
+        }
+        let jitter = RandomState::new().hash_one(attempt) % (jitter_range + 1);
+        half + Duration::from_millis(jitter)
+    }
+}
+
+pub(super) fn is_idempotent_method(method: &str) -> bool {
+    matches!(
+        method.to_ascii_uppercase().as_str(),
+        "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS"
+    )
+}
+
+pub(super) fn is_retryable_status(status_code: u16, idempotent: bool) -> bool {
+    match status_code {
+        429 => true,
+        500 | 502 | 503 | 504 => idempotent,
+        _ => false,
+    }
+}
+
+pub(super) fn is_retryable_error(error: &HttpClientError) -> bool {
+    matches!(error, HttpClientError::Io(_) | HttpClientError::Timeout(_))
+}
+
+/// `Retry-After` の delta-seconds 形式を読む。HTTP-date 形式は無視してバックオフに任せる。
+pub fn retry_after(headers: &[(String, String)]) -> Option<Duration> {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))
+        .and_then(|(_, value)| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RetryPolicy, is_idempotent_method, is_retryable_status, retry_after};
+    use proptest::{prelude::ProptestConfig, prop_assert, proptest};
+    use std::time::Duration;
+
+    #[test]
+    fn post_is_not_idempotent() {
+        assert!(is_idempotent_method("get"));
+        assert!(is_idempotent_method("DELETE"));
+        assert!(!is_idempotent_method("POST"));
+    }
+
+    #[test]
+    fn rate_limit_is_retried_for_any_method() {
+        assert!(is_retryable_status(429, false));
+        assert!(!is_retryable_status(503, false));
+        assert!(is_retryable_status(503, true));
+        assert!(!is_retryable_status(404, true));
+    }
+
+    #[test]
+    fn retry_after_reads_delta_seconds() {
+        let headers = vec![("Retry-After".to_string(), "7".to_string())];
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        let date = vec![(
+            "retry-after".to_string(),
+            "Wed, 21 Oct 2026 07:28:00 GMT".to_string(),
+        )];
+        assert_eq!(retry_after(&date), None);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn backoff_stays_within_bounds(attempt in 1_u32..40) {
+            let policy = RetryPolicy::default();
+            let delay = policy.backoff(attempt);
+            prop_assert!(delay <= policy.max_delay);
+            let exponent = (attempt - 1).min(16);
+            let full = policy.base_delay.saturating_mul(1 << exponent).min(policy.max_delay);
+            prop_assert!(delay >= full / 2);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::{fmt, future::Future, time::Duration};
+
+use super::HttpClientError;
+
+/// 外部 API 呼び出しのフェーズごとのタイムアウト
+#[derive(Debug, Clone)]
+pub struct TimeoutConfig {
+    /// TCP 接続の確立
+    pub connect: Duration,
+    /// TLS ハンドシェイク
+    pub tls_handshake: Duration,
+    /// リクエスト送信後、レスポンスの最初の1バイトを受信するまで
+    pub first_byte: Duration,
+    /// 送信開始からレスポンスのボディを受信し終えるまでの合計時間
+    pub total: Duration,
+    /// `HttpResponseStream` でボディの受信が途切れてよい最大時間
+    pub read_idle: Duration,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            tls_handshake: Duration::from_secs(10),
+            first_byte: Duration::from_secs(30),
+            total: Duration::from_secs(120),
+            read_idle: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutPhase {
+    Connect,
+    TlsHandshake,
+    FirstByte,
+    Total,
+    ReadIdle,
+}
+
+impl fmt::Display for TimeoutPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connect => write!(f, "connect"),
+            Self::TlsHandshake => write!(f, "TLS handshake"),
+            Self::FirstByte => write!(f, "first byte"),
+            Self::Total => write!(f, "total"),
+            Self::ReadIdle => write!(f, "read idle"),
+        }
+    }
+}
+
+/// `future` が `duration` 以内に完了しなければ `phase` のタイムアウトエラーにする。
+pub(super) async fn within<F: Future>(
+    duration: Duration,
+    phase: TimeoutPhase,
+    future: F,
+) -> Result<F::Output, HttpClientError> {
+    tokio::time::timeout(duration, future)
+        .await
+        .map_err(|_| HttpClientError::Timeout(phase))
+}
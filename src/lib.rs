@@ -1,8 +1,11 @@
+pub mod auth;
 pub mod config;
 pub mod errors;
 pub mod handlers;
 pub mod http_client;
 pub mod logging;
+pub mod multipart;
 pub mod request_id;
+pub mod router;
 pub mod server;
 pub mod service;
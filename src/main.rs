@@ -36,9 +36,26 @@ async fn main() {
         }
     };
 
-    let client = api_hub::http_client::HttpClient::new();
-
-    let app_state = config::state::AppState { settings, client };
+    let client = api_hub::http_client::HttpClient::new()
+        .with_pool_config(settings.http_pool.clone())
+        .with_timeouts(settings.http_timeouts.clone())
+        .with_retry_policy(settings.http_retry.clone());
+
+    let app_state = config::state::AppState {
+        client,
+        slack_rate_limiter: api_hub::service::slack_rate_limiter::SlackRateLimiter::new(),
+        slack_channels: api_hub::service::slack_channels::ChannelDirectory::new(
+            settings.slack_channel_cache_ttl,
+        ),
+        slack_handlers: api_hub::service::slack_commands::builtin_registry(),
+        hook_threads: api_hub::service::hook_threads::HookThreads::new(
+            api_hub::service::hook_threads::THREAD_TTL,
+        ),
+        export_jobs: api_hub::service::slack_export::ExportJobs::new(
+            api_hub::service::slack_export::EXPORT_JOB_TTL,
+        ),
+        settings,
+    };
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
 
@@ -0,0 +1,487 @@
+use std::fmt;
+
+/// `multipart/form-data` の1パート
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    pub name: String,
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Part {
+    pub fn is_file(&self) -> bool {
+        self.file_name.is_some()
+    }
+
+    /// テキストフィールドの値
+    pub fn text(&self) -> Result<&str, MultipartError> {
+        std::str::from_utf8(&self.data)
+            .map_err(|_| MultipartError::Malformed(format!("field '{}' is not UTF-8", self.name)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultipartError {
+    /// `Content-Type` が `multipart/form-data` でない、または `boundary` が無い
+    NotMultipart,
+    Malformed(String),
+    /// 1パートの本文が `MultipartLimits::max_part_size` を超えた
+    PartTooLarge {
+        name: String,
+        limit: usize,
+    },
+    /// 本文全体が `MultipartLimits::max_total_size` を超えた
+    TooLarge {
+        limit: usize,
+    },
+}
+
+impl MultipartError {
+    /// サイズ制限による失敗 (413 として返す) かどうか
+    pub fn is_too_large(&self) -> bool {
+        matches!(self, Self::PartTooLarge { .. } | Self::TooLarge { .. })
+    }
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotMultipart => write!(
+                f,
+                "Content-Type must be multipart/form-data with a boundary"
+            ),
+            Self::Malformed(reason) => write!(f, "Malformed multipart body: {reason}"),
+            Self::PartTooLarge { name, limit } => {
+                write!(f, "Part '{name}' exceeds the limit of {limit} bytes")
+            }
+            Self::TooLarge { limit } => {
+                write!(f, "Multipart body exceeds the limit of {limit} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MultipartError {}
+
+/// パートごと・本文全体のサイズ上限 (バイト)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultipartLimits {
+    pub max_part_size: usize,
+    pub max_total_size: usize,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        Self {
+            max_part_size: 20 * 1024 * 1024,
+            max_total_size: 100 * 1024 * 1024,
+        }
+    }
+}
+
+/// パートのヘッダー部 (区切り行の空白を含む) の上限
+const MAX_HEADER_SIZE: usize = 8 * 1024;
+
+/// `multipart/form-data; boundary=...` から boundary を取り出す。
+pub fn boundary(content_type: &str) -> Result<String, MultipartError> {
+    let mut params = split_params(content_type);
+    let media_type = params.next().unwrap_or_default();
+    if !media_type.eq_ignore_ascii_case("multipart/form-data") {
+        return Err(MultipartError::NotMultipart);
+    }
+    let boundary = params
+        .filter_map(parse_param)
+        .find(|(name, _)| name.eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value)
+        .ok_or(MultipartError::NotMultipart)?;
+    // RFC 2046: 1〜70 文字
+    if boundary.is_empty() || boundary.len() > 70 {
+        return Err(MultipartError::Malformed(
+            "boundary must be 1 to 70 characters".to_string(),
+        ));
+    }
+    Ok(boundary)
+}
+
+/// 本文全体をパートに分割する。プリアンブルとエピローグは読み飛ばす。
+pub fn parse(
+    body: &[u8],
+    boundary: &str,
+    limits: MultipartLimits,
+) -> Result<Vec<Part>, MultipartError> {
+    let mut parser = Parser::new(boundary, limits);
+    parser.feed(body)?;
+    parser.finish()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Preamble,
+    /// 区切り行の直後。`--` なら終端、それ以外は行末まで読む
+    Delimiter,
+    Headers,
+    Body,
+    Epilogue,
+}
+
+/// 本文を届いた順に `feed` で渡していくパーサー。
+///
+/// 区切りの一部かもしれない末尾だけを手元に残し、それ以外は読んだ時点でパートの本文に移すので、
+/// 上限を超えたことは本文を最後まで受け取る前に分かる。
+pub struct Parser {
+    delimiter: Vec<u8>,
+    limits: MultipartLimits,
+    state: State,
+    buffer: Vec<u8>,
+    total: usize,
+    current: Option<Part>,
+    parts: Vec<Part>,
+}
+
+impl Parser {
+    pub fn new(boundary: &str, limits: MultipartLimits) -> Self {
+        Self {
+            delimiter: format!("\r\n--{boundary}").into_bytes(),
+            limits,
+            state: State::Preamble,
+            // 先頭の区切りにも CRLF が前置されているものとして扱う
+            buffer: b"\r\n".to_vec(),
+            total: 0,
+            current: None,
+            parts: Vec::new(),
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), MultipartError> {
+        self.total += chunk.len();
+        if self.total > self.limits.max_total_size {
+            return Err(MultipartError::TooLarge {
+                limit: self.limits.max_total_size,
+            });
+        }
+        if self.state == State::Epilogue {
+            return Ok(());
+        }
+        self.buffer.extend_from_slice(chunk);
+        while self.step()? {}
+        Ok(())
+    }
+
+    /// 終端の区切りまで読めていればパートの一覧を返す。
+    pub fn finish(self) -> Result<Vec<Part>, MultipartError> {
+        match self.state {
+            State::Epilogue => Ok(self.parts),
+            State::Preamble => Err(malformed("opening boundary not found")),
+            State::Delimiter => Err(malformed("boundary line is not terminated")),
+            State::Headers => Err(malformed("part headers are not terminated")),
+            State::Body => Err(malformed("closing boundary not found")),
+        }
+    }
+
+    /// 手元のバッファで1段階進める。追加の入力が必要なら false
+    fn step(&mut self) -> Result<bool, MultipartError> {
+        match self.state {
+            State::Preamble => match find(&self.buffer, &self.delimiter) {
+                Some(i) => {
+                    self.buffer.drain(..i + self.delimiter.len());
+                    self.state = State::Delimiter;
+                    Ok(true)
+                }
+                None => {
+                    self.keep_tail();
+                    Ok(false)
+                }
+            },
+            State::Delimiter => {
+                if self.buffer.starts_with(b"--") {
+                    self.buffer.clear();
+                    self.state = State::Epilogue;
+                    return Ok(false);
+                }
+                let Some(line_end) = find(&self.buffer, b"\r\n") else {
+                    self.check_header_size()?;
+                    return Ok(false);
+                };
+                // 区切り行の末尾の空白 (transport padding) は許す
+                if self.buffer[..line_end]
+                    .iter()
+                    .any(|b| !matches!(b, b' ' | b'\t'))
+                {
+                    return Err(malformed("unexpected characters after boundary"));
+                }
+                self.buffer.drain(..line_end + 2);
+                self.state = State::Headers;
+                Ok(true)
+            }
+            State::Headers => {
+                let header_end = if self.buffer.starts_with(b"\r\n") {
+                    0
+                } else {
+                    match find(&self.buffer, b"\r\n\r\n") {
+                        Some(i) => i + 2,
+                        None => {
+                            self.check_header_size()?;
+                            return Ok(false);
+                        }
+                    }
+                };
+                let headers = std::str::from_utf8(&self.buffer[..header_end])
+                    .map_err(|_| malformed("part headers are not UTF-8"))?;
+                self.current = Some(parse_part(headers, Vec::new())?);
+                self.buffer.drain(..header_end + 2);
+                self.state = State::Body;
+                Ok(true)
+            }
+            State::Body => match find(&self.buffer, &self.delimiter) {
+                Some(i) => {
+                    self.take_data(i)?;
+                    self.buffer.drain(..self.delimiter.len());
+                    self.parts.extend(self.current.take());
+                    self.state = State::Delimiter;
+                    Ok(true)
+                }
+                None => {
+                    let keep = self.delimiter.len() - 1;
+                    self.take_data(self.buffer.len().saturating_sub(keep))?;
+                    Ok(false)
+                }
+            },
+            State::Epilogue => Ok(false),
+        }
+    }
+
+    /// バッファの先頭 `len` バイトを現在のパートの本文に移す。
+    fn take_data(&mut self, len: usize) -> Result<(), MultipartError> {
+        let Some(part) = self.current.as_mut() else {
+            return Ok(());
+        };
+        if part.data.len() + len > self.limits.max_part_size {
+            return Err(MultipartError::PartTooLarge {
+                name: part.name.clone(),
+                limit: self.limits.max_part_size,
+            });
+        }
+        part.data.extend(self.buffer.drain(..len));
+        Ok(())
+    }
+
+    /// 区切りの途中かもしれない末尾だけを残す。
+    fn keep_tail(&mut self) {
+        let keep = self.delimiter.len() - 1;
+        if self.buffer.len() > keep {
+            self.buffer.drain(..self.buffer.len() - keep);
+        }
+    }
+
+    fn check_header_size(&self) -> Result<(), MultipartError> {
+        if self.buffer.len() > MAX_HEADER_SIZE {
+            return Err(malformed("part headers are too large"));
+        }
+        Ok(())
+    }
+}
+
+fn parse_part(headers: &str, data: Vec<u8>) -> Result<Part, MultipartError> {
+    let mut name = None;
+    let mut file_name = None;
+    let mut content_type = None;
+
+    for line in headers.split("\r\n").filter(|line| !line.is_empty()) {
+        let (header, value) = line
+            .split_once(':')
+            .ok_or_else(|| malformed("invalid part header"))?;
+        let value = value.trim();
+        if header.trim().eq_ignore_ascii_case("content-disposition") {
+            let mut params = split_params(value);
+            if !params
+                .next()
+                .is_some_and(|kind| kind.eq_ignore_ascii_case("form-data"))
+            {
+                return Err(malformed("Content-Disposition must be form-data"));
+            }
+            for (param, value) in params.filter_map(parse_param) {
+                match param.to_ascii_lowercase().as_str() {
+                    "name" => name = Some(value),
+                    "filename" => file_name = Some(value),
+                    _ => {}
+                }
+            }
+        } else if header.trim().eq_ignore_ascii_case("content-type") {
+            content_type = Some(value.to_string());
+        }
+    }
+
+    Ok(Part {
+        name: name.ok_or_else(|| malformed("part has no Content-Disposition name"))?,
+        file_name,
+        content_type,
+        data,
+    })
+}
+
+/// `;` で区切る。引用符の中の `;` は区切りとして扱わない。
+fn split_params(value: &str) -> impl Iterator<Item = &str> {
+    let mut params = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                params.push(value[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    params.push(value[start..].trim());
+    params.into_iter().filter(|param| !param.is_empty())
+}
+
+/// `name=value` / `name="quoted \"value\""` を読む。
+fn parse_param(param: &str) -> Option<(String, String)> {
+    let (name, value) = param.split_once('=')?;
+    let value = value.trim();
+    let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(quoted) => {
+            let mut unescaped = String::with_capacity(quoted.len());
+            let mut chars = quoted.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unescaped.extend(chars.next()),
+                    c => unescaped.push(c),
+                }
+            }
+            unescaped
+        }
+        None => value.to_string(),
+    };
+    Some((name.trim().to_string(), value))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn malformed(reason: &str) -> MultipartError {
+    MultipartError::Malformed(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MultipartError, MultipartLimits, Parser, boundary, parse};
+    use proptest::{prelude::ProptestConfig, prop_assert_eq, proptest};
+
+    const LIMITS: MultipartLimits = MultipartLimits {
+        max_part_size: 1024,
+        max_total_size: 4096,
+    };
+
+    const BODY: &[u8] = b"preamble\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"channel\"\r\n\
+\r\n\
+C123\r\n\
+--XyZ\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"a; b.csv\"\r\n\
+Content-Type: text/csv\r\n\
+\r\n\
+a,b\r\n1,2\r\n\
+--XyZ--\r\n";
+
+    #[test]
+    fn boundary_is_read_from_content_type() {
+        assert_eq!(
+            boundary("multipart/form-data; boundary=XyZ"),
+            Ok("XyZ".to_string())
+        );
+        assert_eq!(
+            boundary("Multipart/Form-Data; charset=utf-8; boundary=\"a b\""),
+            Ok("a b".to_string())
+        );
+        assert_eq!(
+            boundary("application/json"),
+            Err(MultipartError::NotMultipart)
+        );
+        assert_eq!(
+            boundary("multipart/form-data"),
+            Err(MultipartError::NotMultipart)
+        );
+    }
+
+    #[test]
+    fn fields_and_files_are_split() {
+        let parts = parse(BODY, "XyZ", LIMITS).expect("body should parse");
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "channel");
+        assert_eq!(parts[0].text(), Ok("C123"));
+        assert!(!parts[0].is_file());
+
+        assert_eq!(parts[1].file_name.as_deref(), Some("a; b.csv"));
+        assert_eq!(parts[1].content_type.as_deref(), Some("text/csv"));
+        assert_eq!(parts[1].data, b"a,b\r\n1,2");
+    }
+
+    #[test]
+    fn truncated_body_is_rejected() {
+        let truncated = &BODY[..BODY.len() - 9];
+        assert!(matches!(
+            parse(truncated, "XyZ", LIMITS),
+            Err(MultipartError::Malformed(_))
+        ));
+        assert!(matches!(
+            parse(BODY, "other", LIMITS),
+            Err(MultipartError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn limits_are_enforced_per_part_and_in_total() {
+        let per_part = MultipartLimits {
+            max_part_size: 7,
+            max_total_size: 4096,
+        };
+        assert_eq!(
+            parse(BODY, "XyZ", per_part),
+            Err(MultipartError::PartTooLarge {
+                name: "file".to_string(),
+                limit: 7
+            })
+        );
+
+        let total = MultipartLimits {
+            max_part_size: 1024,
+            max_total_size: BODY.len() - 1,
+        };
+        assert_eq!(
+            parse(BODY, "XyZ", total),
+            Err(MultipartError::TooLarge {
+                limit: BODY.len() - 1
+            })
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn chunked_input_matches_whole_body(split_points in proptest::collection::vec(0..BODY.len(), 0..8)) {
+            let mut split_points = split_points;
+            split_points.sort_unstable();
+
+            let mut parser = Parser::new("XyZ", LIMITS);
+            let mut start = 0;
+            for end in split_points.into_iter().chain([BODY.len()]) {
+                parser.feed(&BODY[start..end]).expect("chunk should parse");
+                start = end;
+            }
+            prop_assert_eq!(parser.finish(), parse(BODY, "XyZ", LIMITS));
+        }
+    }
+}
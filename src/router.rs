@@ -0,0 +1,265 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    CatchAll(String),
+}
+
+struct Route<H> {
+    method: String,
+    segments: Vec<Segment>,
+    handler: H,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PathParams {
+    values: Vec<(String, String)>,
+}
+
+impl PathParams {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(param_name, _)| param_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub enum RouteMatch<'r, H> {
+    Found { handler: &'r H, params: PathParams },
+    MethodNotAllowed { allowed: Vec<&'r str> },
+    NotFound,
+}
+
+/// (method, path pattern, handler) のテーブルでルーティングする。
+///
+/// パターンは `/` 区切りで、`{name}` は空でない1セグメント、`{*name}` は残り全体
+/// (空を含む) にマッチする。`{*name}` はパターンの末尾にのみ置ける。
+/// 複数のルートがマッチする場合は登録順で先のものが優先される。
+pub struct Router<H> {
+    routes: Vec<Route<H>>,
+}
+
+impl<H> Default for Router<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H> Router<H> {
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    pub fn route(mut self, method: &str, pattern: &str, handler: H) -> Self {
+        self.routes.push(Route {
+            method: method.to_ascii_uppercase(),
+            segments: parse_pattern(pattern),
+            handler,
+        });
+        self
+    }
+
+    pub fn resolve(&self, method: &str, path: &str) -> RouteMatch<'_, H> {
+        let mut allowed: Vec<&str> = Vec::new();
+
+        for route in &self.routes {
+            let Some(params) = match_segments(&route.segments, path) else {
+                continue;
+            };
+            if route.method.eq_ignore_ascii_case(method) {
+                return RouteMatch::Found {
+                    handler: &route.handler,
+                    params,
+                };
+            }
+            if !allowed.contains(&route.method.as_str()) {
+                allowed.push(&route.method);
+            }
+        }
+
+        if allowed.is_empty() {
+            RouteMatch::NotFound
+        } else {
+            RouteMatch::MethodNotAllowed { allowed }
+        }
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Vec<Segment> {
+    assert!(
+        pattern.starts_with('/'),
+        "route pattern must start with '/': {pattern}"
+    );
+
+    let raw_segments = pattern[1..].split('/').collect::<Vec<_>>();
+    let mut segments = Vec::with_capacity(raw_segments.len());
+
+    for (index, raw) in raw_segments.iter().enumerate() {
+        let segment = match raw.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(name) => {
+                if let Some(rest_name) = name.strip_prefix('*') {
+                    assert!(
+                        index == raw_segments.len() - 1,
+                        "catch-all parameter must be the last segment: {pattern}"
+                    );
+                    Segment::CatchAll(rest_name.to_string())
+                } else {
+                    Segment::Param(name.to_string())
+                }
+            }
+            None => Segment::Literal((*raw).to_string()),
+        };
+        segments.push(segment);
+    }
+
+    segments
+}
+
+fn match_segments(segments: &[Segment], path: &str) -> Option<PathParams> {
+    let mut rest = path.strip_prefix('/')?;
+    let mut params = PathParams::default();
+
+    for (index, segment) in segments.iter().enumerate() {
+        if let Segment::CatchAll(name) = segment {
+            params.values.push((name.clone(), rest.to_string()));
+            return Some(params);
+        }
+
+        let (current, remaining) = match rest.split_once('/') {
+            Some((current, remaining)) => (current, Some(remaining)),
+            None => (rest, None),
+        };
+
+        match segment {
+            Segment::Literal(literal) => {
+                if current != literal {
+                    return None;
+                }
+            }
+            Segment::Param(name) => {
+                if current.is_empty() {
+                    return None;
+                }
+                params.values.push((name.clone(), current.to_string()));
+            }
+            Segment::CatchAll(_) => unreachable!(),
+        }
+
+        let is_last = index == segments.len() - 1;
+        match remaining {
+            Some(remaining) => {
+                if is_last {
+                    return None;
+                }
+                rest = remaining;
+            }
+            None => {
+                if !is_last {
+                    // 残りが catch-all だけなら空文字列でマッチさせる
+                    return match &segments[index + 1..] {
+                        [Segment::CatchAll(name)] => {
+                            params.values.push((name.clone(), String::new()));
+                            Some(params)
+                        }
+                        _ => None,
+                    };
+                }
+            }
+        }
+    }
+
+    Some(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RouteMatch, Router};
+    use proptest::{prelude::ProptestConfig, prop_assert_eq, proptest};
+
+    fn router() -> Router<&'static str> {
+        Router::new()
+            .route("GET", "/health", "health")
+            .route("POST", "/slack/message", "post_message")
+            .route("POST", "/s3/list_objects_v2", "list_objects_v2")
+            .route("OPTIONS", "/s3/list_objects_v2", "preflight")
+            .route("GET", "/s3/preview/{bucket}/{*key}", "preview")
+            .route("GET", "/items/{id}", "item")
+    }
+
+    #[test]
+    fn resolves_literal_route() {
+        match router().resolve("POST", "/slack/message") {
+            RouteMatch::Found { handler, .. } => assert_eq!(*handler, "post_message"),
+            _ => panic!("route should match"),
+        }
+    }
+
+    #[test]
+    fn unknown_path_is_not_found() {
+        assert!(matches!(
+            router().resolve("GET", "/unknown"),
+            RouteMatch::NotFound
+        ));
+        assert!(matches!(
+            router().resolve("GET", "/health/extra"),
+            RouteMatch::NotFound
+        ));
+    }
+
+    #[test]
+    fn wrong_method_lists_allowed_methods() {
+        match router().resolve("GET", "/s3/list_objects_v2") {
+            RouteMatch::MethodNotAllowed { allowed } => {
+                assert_eq!(allowed, vec!["POST", "OPTIONS"]);
+            }
+            _ => panic!("route should be method-not-allowed"),
+        }
+    }
+
+    #[test]
+    fn captures_param_and_catch_all() {
+        match router().resolve("GET", "/s3/preview/pdfs/2026/04/a.pdf") {
+            RouteMatch::Found { handler, params } => {
+                assert_eq!(*handler, "preview");
+                assert_eq!(params.get("bucket"), Some("pdfs"));
+                assert_eq!(params.get("key"), Some("2026/04/a.pdf"));
+            }
+            _ => panic!("route should match"),
+        }
+    }
+
+    #[test]
+    fn catch_all_may_be_empty() {
+        match router().resolve("GET", "/s3/preview/pdfs/") {
+            RouteMatch::Found { params, .. } => assert_eq!(params.get("key"), Some("")),
+            _ => panic!("route should match"),
+        }
+        match router().resolve("GET", "/s3/preview/pdfs") {
+            RouteMatch::Found { params, .. } => assert_eq!(params.get("key"), Some("")),
+            _ => panic!("route should match"),
+        }
+    }
+
+    #[test]
+    fn param_requires_non_empty_segment() {
+        assert!(matches!(
+            router().resolve("GET", "/items/"),
+            RouteMatch::NotFound
+        ));
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn param_captures_any_single_segment(id in "[A-Za-z0-9_.%-]{1,32}") {
+            let router = router();
+            let path = format!("/items/{id}");
+            match router.resolve("GET", &path) {
+                RouteMatch::Found { params, .. } => prop_assert_eq!(params.get("id"), Some(id.as_str())),
+                _ => panic!("route should match"),
+            }
+        }
+    }
+}
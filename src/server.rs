@@ -1,54 +1,798 @@
+use crate::auth::{self, CallerIdentity};
 use crate::config::state::AppState;
 use crate::errors::api_error::{ApiError, reason_phrase};
-use crate::handlers::{health_handler, openapi_handler, s3_handler, slack_handler};
+use crate::handlers::{
+    health_handler, hooks_handler, openapi_handler, s3_handler, slack_events_handler, slack_handler,
+};
 use crate::request_id;
+use crate::router::{PathParams, RouteMatch, Router};
 use shiguredo_http11::uri::percent_decode;
-use shiguredo_http11::{Request, RequestDecoder, Response};
+use shiguredo_http11::{BodyKind, BodyProgress, DecoderLimits, Request, RequestDecoder, Response};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::LazyLock;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tracing::{Instrument, debug, error, info, info_span, warn};
 
 const S3_CORS_ALLOWED_ORIGIN: &str = "https://hitomi-upload-viewer.internal.qroksera.com";
 
-pub async fn handle_connection(mut stream: TcpStream, app_state: AppState) {
-    let mut decoder = RequestDecoder::new();
-    let mut buffer = vec![0_u8; 8192];
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
-    loop {
-        let request = loop {
-            match decoder.decode() {
-                Ok(Some(request)) => break request,
-                Ok(None) => {
-                    let n = match stream.read(&mut buffer).await {
-                        Ok(n) => n,
-                        Err(e) => {
-                            error!(error = %e, "Failed to read from socket");
-                            return;
-                        }
-                    };
-                    if n == 0 {
-                        return;
+type Handler = for<'a> fn(RouteContext<'a>) -> BoxFuture<'a, Result<Response, ApiError>>;
+
+struct RouteContext<'a> {
+    request: &'a Request,
+    app_state: &'a AppState,
+    query: Option<&'a str>,
+    caller: CallerIdentity,
+}
+
+enum RouteHandler {
+    Buffered(Handler),
+    /// レスポンスボディをソケットへ直接ストリーミングする S3 プレビュー
+    PreviewStream,
+}
+
+static ROUTER: LazyLock<Router<RouteHandler>> = LazyLock::new(build_router);
+
+fn build_router() -> Router<RouteHandler> {
+    use RouteHandler::{Buffered, PreviewStream};
+
+    Router::new()
+        .route(
+            "GET",
+            "/health",
+            Buffered(|_| Box::pin(async { Ok(health_handler::health()) })),
+        )
+        .route(
+            "GET",
+            "/openapi.json",
+            Buffered(|_| Box::pin(async { Ok(openapi_handler::openapi_json()) })),
+        )
+        .route(
+            "POST",
+            "/slack/events",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_events_handler::receive_event(
+                        ctx.app_state,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/commands",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_events_handler::receive_command(
+                        ctx.app_state,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/interactivity",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_events_handler::receive_interaction(
+                        ctx.app_state,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/hooks/alertmanager",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    hooks_handler::alertmanager(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/hooks/github",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    hooks_handler::github(
+                        ctx.app_state,
+                        &ctx.caller,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/hooks/grafana",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    hooks_handler::grafana(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/message",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::post_message(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/message/template",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::post_template_message(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "PATCH",
+            "/slack/message",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::update_message(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/ephemeral",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::post_ephemeral(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/dm",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::post_direct_message(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/message/scheduled",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::schedule_message(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/message/scheduled/list",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::list_scheduled_messages(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "DELETE",
+            "/slack/message/scheduled",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::delete_scheduled_message(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "DELETE",
+            "/slack/message",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::delete_message(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/reactions",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::add_reaction(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "DELETE",
+            "/slack/reactions",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::remove_reaction(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/reactions/get",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::get_reactions(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/pins",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::add_pin(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "DELETE",
+            "/slack/pins",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::remove_pin(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/pins/list",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::list_pins(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/bookmarks",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::add_bookmark(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "DELETE",
+            "/slack/bookmarks",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::remove_bookmark(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/bookmarks/list",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::list_bookmarks(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/upload/image",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::upload_image_raw(
+                        ctx.app_state,
+                        &ctx.caller,
+                        ctx.query,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/upload/pdf",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::upload_pdf_raw(
+                        ctx.app_state,
+                        &ctx.caller,
+                        ctx.query,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/upload/file",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::upload_file_raw(
+                        ctx.app_state,
+                        &ctx.caller,
+                        ctx.query,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/archive/files",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::archive_files(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/export",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::export_conversation(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/export/status",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::export_status(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/upload/from_s3",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::upload_from_s3(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/slack/upload/files",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    slack_handler::upload_files_multipart(
+                        ctx.app_state,
+                        &ctx.caller,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/put_object_base64",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::put_object_base64(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/put_object_form",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::put_object_form(
+                        ctx.app_state,
+                        &ctx.caller,
+                        ctx.request.headers.as_slice(),
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/get_object_base64",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::get_object_base64(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/head_object",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::head_object(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/delete_object",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::delete_object(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/delete_objects",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::delete_objects(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/list_objects_v2",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::list_objects_v2(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/create_multipart_upload",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::create_multipart_upload(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/upload_part_base64",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::upload_part_base64(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/complete_multipart_upload",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::complete_multipart_upload(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/abort_multipart_upload",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::abort_multipart_upload(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/list_parts",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::list_parts(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/list_multipart_uploads",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::list_multipart_uploads(
+                        ctx.app_state,
+                        &ctx.caller,
+                        &ctx.request.body,
+                    )
+                    .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/presigned_get_object",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::presigned_get_object(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/presigned_put_object",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::presigned_put_object(ctx.app_state, &ctx.caller, &ctx.request.body)
+                        .await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/list_buckets",
+            Buffered(|ctx| {
+                Box::pin(async move { s3_handler::list_buckets(ctx.app_state, &ctx.caller).await })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/create_bucket",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::create_bucket(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/head_bucket",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::head_bucket(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "POST",
+            "/s3/delete_bucket",
+            Buffered(|ctx| {
+                Box::pin(async move {
+                    s3_handler::delete_bucket(ctx.app_state, &ctx.caller, &ctx.request.body).await
+                })
+            }),
+        )
+        .route(
+            "OPTIONS",
+            "/s3/list_objects_v2",
+            Buffered(|_| Box::pin(async { Ok(s3_handler::s3_preflight()) })),
+        )
+        .route(
+            "OPTIONS",
+            "/s3/presigned_get_object",
+            Buffered(|_| Box::pin(async { Ok(s3_handler::s3_preflight()) })),
+        )
+        .route("GET", "/s3/preview/{bucket}/{*key}", PreviewStream)
+}
+
+/// `multipart/form-data` を受け付けるルート以外の本文の上限
+const DEFAULT_MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+/// 本文の上限を `MultipartLimits` の本文全体の上限まで広げるルート
+const MULTIPART_ROUTES: [&str; 2] = ["/slack/upload/files", "/s3/put_object_form"];
+
+/// デコーダー自体の上限は最も大きいルートに合わせ、ルートごとの上限は `read_request` で確かめる。
+fn request_decoder_limits(app_state: &AppState) -> DecoderLimits {
+    DecoderLimits {
+        max_body_size: app_state
+            .settings
+            .multipart_limits
+            .max_total_size
+            .max(DEFAULT_MAX_BODY_SIZE),
+        ..DecoderLimits::default()
+    }
+}
+
+/// ルートごとの本文の上限。認証前に読み込むため、マルチパートのルート以外は小さく抑える。
+fn body_limit(app_state: &AppState, method: &str, uri: &str) -> usize {
+    let (path, _) = split_uri(uri);
+    if method == "POST" && MULTIPART_ROUTES.contains(&path) {
+        app_state.settings.multipart_limits.max_total_size
+    } else {
+        DEFAULT_MAX_BODY_SIZE
+    }
+}
+
+fn decode_error_response(e: shiguredo_http11::Error) -> Response {
+    match e {
+        shiguredo_http11::Error::BodyTooLarge { .. } => {
+            ApiError::PayloadTooLarge(format!("Request body is too large: {e}"))
+        }
+        e => ApiError::BadRequest(format!("Invalid HTTP request: {e}")),
+    }
+    .into_response()
+}
+
+fn body_too_large_response(limit: usize) -> Response {
+    ApiError::PayloadTooLarge(format!("Request body is too large: limit is {limit} bytes"))
+        .into_response()
+}
+
+enum ReadOutcome {
+    Request(Request),
+    /// クライアントが接続を閉じた、または読み込みに失敗した
+    Closed,
+    /// 応答を返して接続を閉じる
+    Rejected(Response),
+}
+
+/// ソケットから読んだ分をデコーダーに渡す。
+async fn fill(
+    stream: &mut TcpStream,
+    decoder: &mut RequestDecoder,
+    buffer: &mut [u8],
+) -> Result<(), ReadOutcome> {
+    let n = match stream.read(buffer).await {
+        Ok(n) => n,
+        Err(e) => {
+            error!(error = %e, "Failed to read from socket");
+            return Err(ReadOutcome::Closed);
+        }
+    };
+    if n == 0 {
+        return Err(ReadOutcome::Closed);
+    }
+    decoder
+        .feed(&buffer[..n])
+        .map_err(|e| ReadOutcome::Rejected(decode_error_response(e)))
+}
+
+/// ヘッダーを読んでルートを決めてから、そのルートの上限まで本文を読む。
+async fn read_request(
+    stream: &mut TcpStream,
+    decoder: &mut RequestDecoder,
+    buffer: &mut [u8],
+    app_state: &AppState,
+) -> ReadOutcome {
+    let (head, body_kind) = loop {
+        match decoder.decode_headers() {
+            Ok(Some(decoded)) => break decoded,
+            Ok(None) => {
+                if let Err(outcome) = fill(stream, decoder, buffer).await {
+                    return outcome;
+                }
+            }
+            Err(e) => return ReadOutcome::Rejected(decode_error_response(e)),
+        }
+    };
+
+    let limit = body_limit(app_state, &head.method, &head.uri);
+    let mut body = Vec::new();
+    match body_kind {
+        BodyKind::ContentLength(len) if len > limit => {
+            return ReadOutcome::Rejected(body_too_large_response(limit));
+        }
+        BodyKind::ContentLength(_) | BodyKind::Chunked => loop {
+            if let Some(data) = decoder.peek_body().filter(|data| !data.is_empty()) {
+                if body.len() + data.len() > limit {
+                    return ReadOutcome::Rejected(body_too_large_response(limit));
+                }
+                body.extend_from_slice(data);
+                let consumed = data.len();
+                match decoder.consume_body(consumed) {
+                    Ok(BodyProgress::Complete { .. }) => break,
+                    Ok(BodyProgress::Continue) => continue,
+                    Err(e) => return ReadOutcome::Rejected(decode_error_response(e)),
+                }
+            }
+            match decoder.progress() {
+                Ok(BodyProgress::Complete { .. }) => break,
+                Ok(BodyProgress::Continue) => {
+                    if decoder.peek_body().is_some_and(|data| !data.is_empty()) {
+                        continue;
                     }
-                    if let Err(e) = decoder.feed(&buffer[..n]) {
-                        let response = ApiError::BadRequest(format!("Invalid HTTP request: {e}"))
-                            .into_response();
-                        let _ = write_response(&mut stream, response).await;
-                        return;
+                    if let Err(outcome) = fill(stream, decoder, buffer).await {
+                        return outcome;
                     }
                 }
-                Err(e) => {
-                    let response =
-                        ApiError::BadRequest(format!("Invalid HTTP request: {e}")).into_response();
-                    let _ = write_response(&mut stream, response).await;
-                    return;
-                }
+                Err(e) => return ReadOutcome::Rejected(decode_error_response(e)),
+            }
+        },
+        _ => {}
+    }
+
+    ReadOutcome::Request(Request {
+        method: head.method,
+        uri: head.uri,
+        version: head.version,
+        headers: head.headers,
+        body,
+    })
+}
+
+pub async fn handle_connection(mut stream: TcpStream, app_state: AppState) {
+    let mut decoder = RequestDecoder::with_limits(request_decoder_limits(&app_state));
+    let mut buffer = vec![0_u8; 8192];
+
+    loop {
+        let request = match read_request(&mut stream, &mut decoder, &mut buffer, &app_state).await {
+            ReadOutcome::Request(request) => request,
+            ReadOutcome::Closed => return,
+            ReadOutcome::Rejected(response) => {
+                let _ = write_response(&mut stream, response).await;
+                return;
             }
         };
 
         let keep_alive = request.is_keep_alive();
+        let (path, _) = split_uri(&request.uri);
+        let route = ROUTER.resolve(&request.method, path);
 
-        if is_preview_get(&request) {
-            if write_preview_stream_response(&mut stream, request, &app_state, keep_alive)
+        if let RouteMatch::Found {
+            handler: RouteHandler::PreviewStream,
+            params,
+        } = route
+        {
+            if write_preview_stream_response(&mut stream, request, params, &app_state, keep_alive)
                 .await
                 .is_err()
             {
@@ -60,7 +804,7 @@ pub async fn handle_connection(mut stream: TcpStream, app_state: AppState) {
             continue;
         }
 
-        let mut response = process_request(request, &app_state).await;
+        let mut response = process_request(request, route, &app_state).await;
 
         if !keep_alive {
             response.add_header("Connection", "close");
@@ -82,16 +826,9 @@ async fn write_response(stream: &mut TcpStream, response: Response) -> std::io::
     stream.flush().await
 }
 
-fn is_preview_get(request: &Request) -> bool {
-    let (path, _) = split_uri(&request.uri);
-    request.method == "GET" && path.starts_with("/s3/preview/")
-}
-
-fn parse_preview_bucket_and_key(path: &str) -> Result<(String, String), ApiError> {
-    let remaining = &path["/s3/preview/".len()..];
-    let (bucket, key) = remaining
-        .split_once('/')
-        .ok_or_else(|| ApiError::BadRequest("Invalid preview path".to_string()))?;
+fn parse_preview_bucket_and_key(params: &PathParams) -> Result<(String, String), ApiError> {
+    let bucket = params.get("bucket").unwrap_or_default();
+    let key = params.get("key").unwrap_or_default();
     if bucket.is_empty() || key.is_empty() {
         return Err(ApiError::BadRequest("Invalid preview path".to_string()));
     }
@@ -106,6 +843,7 @@ fn parse_preview_bucket_and_key(path: &str) -> Result<(String, String), ApiError
 async fn write_preview_stream_response(
     stream: &mut TcpStream,
     request: Request,
+    params: PathParams,
     app_state: &AppState,
     keep_alive: bool,
 ) -> std::io::Result<()> {
@@ -122,179 +860,213 @@ async fn write_preview_stream_response(
         .get_header("x-forwarded-for")
         .map(ToString::to_string)
         .or_else(|| request.get_header("x-real-ip").map(ToString::to_string));
-
-    info!(
-        target: "http::request",
+    let span = info_span!(
+        "http_request",
         request_id = %request_id,
         method = %method,
         path = %path,
         query = ?query,
+        version = %request.version,
         user_agent = ?user_agent,
         client_ip = ?client_ip,
-        "Incoming request"
+        api_key = tracing::field::Empty,
+        status = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
     );
 
-    let start = std::time::Instant::now();
+    async move {
+        info!(
+            target: "http::request",
+            request_id = %request_id,
+            method = %method,
+            path = %path,
+            query = ?query,
+            user_agent = ?user_agent,
+            client_ip = ?client_ip,
+            "Incoming request"
+        );
 
-    let result = async {
-        let (bucket, key) = parse_preview_bucket_and_key(&path)?;
-        s3_handler::preview_object_stream(app_state, bucket, key, request.headers.as_slice()).await
-    }
-    .await;
-
-    match result {
-        Ok(mut stream_response) => {
-            let mut head =
-                Response::new(stream_response.status_code, stream_response.reason_phrase)
-                    .omit_body(true);
-            for (name, value) in &stream_response.headers {
-                head.add_header(name, value);
-            }
-            apply_s3_cors(&path, &request, &mut head);
-            if !head.has_header("x-request-id") {
-                head.add_header("x-request-id", &request_id);
-            }
-            if !keep_alive {
-                head.add_header("Connection", "close");
-            }
+        let start = std::time::Instant::now();
 
-            let encoded_head = head.encode();
-            stream.write_all(&encoded_head).await?;
+        let result = async {
+            let caller = authenticate_request(&request, &path, app_state)?;
+            tracing::Span::current().record("api_key", caller.key_name.as_str());
+            let (bucket, key) = parse_preview_bucket_and_key(&params)?;
+            s3_handler::preview_object_stream(
+                app_state,
+                &caller,
+                bucket,
+                key,
+                request.headers.as_slice(),
+            )
+            .await
+        }
+        .await;
 
-            let mut chunk = vec![0_u8; 16 * 1024];
-            loop {
-                let n = stream_response
-                    .body_stream
-                    .read_chunk(&mut chunk)
-                    .await
-                    .map_err(|e| std::io::Error::other(e.to_string()))?;
-                if n == 0 {
-                    break;
+        match result {
+            Ok(mut stream_response) => {
+                let mut head =
+                    Response::new(stream_response.status_code, stream_response.reason_phrase)
+                        .omit_body(true);
+                for (name, value) in &stream_response.headers {
+                    head.add_header(name, value);
+                }
+                apply_s3_cors(&path, &request, &mut head);
+                if !head.has_header("x-request-id") {
+                    head.add_header("x-request-id", &request_id);
+                }
+                if !keep_alive {
+                    head.add_header("Connection", "close");
                 }
-                stream.write_all(&chunk[..n]).await?;
-            }
-            stream.flush().await?;
 
-            let latency_ms = start.elapsed().as_millis() as u64;
-            match stream_response.status_code {
-                200..=299 => info!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status = stream_response.status_code,
-                    latency_ms,
-                    "Request completed"
-                ),
-                300..=399 => info!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status = stream_response.status_code,
-                    latency_ms,
-                    "Request redirected"
-                ),
-                400..=499 => warn!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status = stream_response.status_code,
-                    latency_ms,
-                    "Client error"
-                ),
-                500..=599 => error!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status = stream_response.status_code,
-                    latency_ms,
-                    "Server error"
-                ),
-                _ => debug!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status = stream_response.status_code,
-                    latency_ms,
-                    "Unexpected status"
-                ),
-            }
+                let encoded_head = head.encode();
+                stream.write_all(&encoded_head).await?;
 
-            Ok(())
-        }
-        Err(error) => {
-            let mut response = error.into_response();
-            apply_problem_details(&mut response);
-            apply_s3_cors(&path, &request, &mut response);
-            if !response.has_header("x-request-id") {
-                response.add_header("x-request-id", &request_id);
-            }
-            if !keep_alive {
-                response.add_header("Connection", "close");
-            }
+                let mut chunk = vec![0_u8; 16 * 1024];
+                loop {
+                    let n = stream_response
+                        .body_stream
+                        .read_chunk(&mut chunk)
+                        .await
+                        .map_err(|e| std::io::Error::other(e.to_string()))?;
+                    if n == 0 {
+                        break;
+                    }
+                    stream.write_all(&chunk[..n]).await?;
+                }
+                stream.flush().await?;
 
-            let status = response.status_code;
-            write_response(stream, response).await?;
+                let latency_ms = start.elapsed().as_millis() as u64;
+                tracing::Span::current().record("status", stream_response.status_code);
+                tracing::Span::current().record("latency_ms", latency_ms);
+                match stream_response.status_code {
+                    200..=299 => info!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status = stream_response.status_code,
+                        latency_ms,
+                        "Request completed"
+                    ),
+                    300..=399 => info!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status = stream_response.status_code,
+                        latency_ms,
+                        "Request redirected"
+                    ),
+                    400..=499 => warn!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status = stream_response.status_code,
+                        latency_ms,
+                        "Client error"
+                    ),
+                    500..=599 => error!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status = stream_response.status_code,
+                        latency_ms,
+                        "Server error"
+                    ),
+                    _ => debug!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status = stream_response.status_code,
+                        latency_ms,
+                        "Unexpected status"
+                    ),
+                }
 
-            let latency_ms = start.elapsed().as_millis() as u64;
-            match status {
-                200..=299 => info!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status,
-                    latency_ms,
-                    "Request completed"
-                ),
-                300..=399 => info!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status,
-                    latency_ms,
-                    "Request redirected"
-                ),
-                400..=499 => warn!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status,
-                    latency_ms,
-                    "Client error"
-                ),
-                500..=599 => error!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status,
-                    latency_ms,
-                    "Server error"
-                ),
-                _ => debug!(
-                    target: "http::response",
-                    request_id = %request_id,
-                    method = %method,
-                    path = %path,
-                    status,
-                    latency_ms,
-                    "Unexpected status"
-                ),
+                Ok(())
             }
+            Err(error) => {
+                let mut response = error.into_response();
+                apply_problem_details(&mut response);
+                apply_s3_cors(&path, &request, &mut response);
+                if !response.has_header("x-request-id") {
+                    response.add_header("x-request-id", &request_id);
+                }
+                if !keep_alive {
+                    response.add_header("Connection", "close");
+                }
 
-            Ok(())
+                let status = response.status_code;
+                write_response(stream, response).await?;
+
+                let latency_ms = start.elapsed().as_millis() as u64;
+                tracing::Span::current().record("status", status);
+                tracing::Span::current().record("latency_ms", latency_ms);
+                match status {
+                    200..=299 => info!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status,
+                        latency_ms,
+                        "Request completed"
+                    ),
+                    300..=399 => info!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status,
+                        latency_ms,
+                        "Request redirected"
+                    ),
+                    400..=499 => warn!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status,
+                        latency_ms,
+                        "Client error"
+                    ),
+                    500..=599 => error!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status,
+                        latency_ms,
+                        "Server error"
+                    ),
+                    _ => debug!(
+                        target: "http::response",
+                        request_id = %request_id,
+                        method = %method,
+                        path = %path,
+                        status,
+                        latency_ms,
+                        "Unexpected status"
+                    ),
+                }
+
+                Ok(())
+            }
         }
     }
+    .instrument(span)
+    .await
 }
 
-async fn process_request(request: Request, app_state: &AppState) -> Response {
+async fn process_request(
+    request: Request,
+    route: RouteMatch<'static, RouteHandler>,
+    app_state: &AppState,
+) -> Response {
     let (path, query) = split_uri(&request.uri);
     let path = path.to_string();
     let query = query.map(ToString::to_string);
@@ -321,6 +1093,7 @@ async fn process_request(request: Request, app_state: &AppState) -> Response {
         user_agent = ?user_agent,
         client_ip = ?client_ip,
         content_length = ?content_length,
+        api_key = tracing::field::Empty,
         status = tracing::field::Empty,
         latency_ms = tracing::field::Empty,
         error = tracing::field::Empty,
@@ -339,7 +1112,14 @@ async fn process_request(request: Request, app_state: &AppState) -> Response {
         );
 
         let start = std::time::Instant::now();
-        let mut response = match route_request(&request, app_state, &path, query.as_deref()).await {
+        let result = match authenticate_request(&request, &path, app_state) {
+            Ok(caller) => {
+                tracing::Span::current().record("api_key", caller.key_name.as_str());
+                route_request(&request, route, app_state, caller, &path, query.as_deref()).await
+            }
+            Err(error) => Err(error),
+        };
+        let mut response = match result {
             Ok(response) => response,
             Err(error) => error.into_response(),
         };
@@ -415,102 +1195,72 @@ async fn process_request(request: Request, app_state: &AppState) -> Response {
 
 async fn route_request(
     request: &Request,
+    route: RouteMatch<'static, RouteHandler>,
     app_state: &AppState,
+    caller: CallerIdentity,
     path: &str,
     query: Option<&str>,
 ) -> Result<Response, ApiError> {
-    match (request.method.as_str(), path) {
-        ("GET", "/health") => return Ok(health_handler::health()),
-        ("GET", "/openapi.json") => return Ok(openapi_handler::openapi_json()),
-        ("POST", "/slack/message") => {
-            return slack_handler::post_message(app_state, &request.body).await;
-        }
-        ("POST", "/slack/upload/image") => {
-            return slack_handler::upload_image_raw(
-                app_state,
-                query,
-                request.headers.as_slice(),
-                &request.body,
-            )
-            .await;
-        }
-        ("POST", "/slack/upload/pdf") => {
-            return slack_handler::upload_pdf_raw(
+    match route {
+        RouteMatch::Found {
+            handler: RouteHandler::Buffered(handler),
+            ..
+        } => {
+            handler(RouteContext {
+                request,
                 app_state,
                 query,
-                request.headers.as_slice(),
-                &request.body,
-            )
-            .await;
-        }
-        ("POST", "/s3/put_object_base64") => {
-            return s3_handler::put_object_base64(app_state, &request.body).await;
-        }
-        ("POST", "/s3/get_object_base64") => {
-            return s3_handler::get_object_base64(app_state, &request.body).await;
-        }
-        ("POST", "/s3/head_object") => {
-            return s3_handler::head_object(app_state, &request.body).await;
-        }
-        ("POST", "/s3/delete_object") => {
-            return s3_handler::delete_object(app_state, &request.body).await;
-        }
-        ("POST", "/s3/delete_objects") => {
-            return s3_handler::delete_objects(app_state, &request.body).await;
-        }
-        ("POST", "/s3/list_objects_v2") => {
-            return s3_handler::list_objects_v2(app_state, &request.body).await;
-        }
-        ("POST", "/s3/create_multipart_upload") => {
-            return s3_handler::create_multipart_upload(app_state, &request.body).await;
+                caller,
+            })
+            .await
         }
-        ("POST", "/s3/upload_part_base64") => {
-            return s3_handler::upload_part_base64(app_state, &request.body).await;
+        RouteMatch::Found {
+            handler: RouteHandler::PreviewStream,
+            ..
+        } => Err(ApiError::InternalServerError(
+            "Streaming route reached buffered dispatch".to_string(),
+        )),
+        RouteMatch::MethodNotAllowed { allowed } => {
+            let mut response = ApiError::MethodNotAllowed(format!(
+                "Method {} is not allowed for {}",
+                request.method, path
+            ))
+            .into_response();
+            response.add_header("Allow", &allowed.join(", "));
+            Ok(response)
         }
-        ("POST", "/s3/complete_multipart_upload") => {
-            return s3_handler::complete_multipart_upload(app_state, &request.body).await;
-        }
-        ("POST", "/s3/abort_multipart_upload") => {
-            return s3_handler::abort_multipart_upload(app_state, &request.body).await;
-        }
-        ("POST", "/s3/list_parts") => {
-            return s3_handler::list_parts(app_state, &request.body).await;
-        }
-        ("POST", "/s3/list_multipart_uploads") => {
-            return s3_handler::list_multipart_uploads(app_state, &request.body).await;
-        }
-        ("POST", "/s3/presigned_get_object") => {
-            return s3_handler::presigned_get_object(app_state, &request.body).await;
-        }
-        ("POST", "/s3/presigned_put_object") => {
-            return s3_handler::presigned_put_object(app_state, &request.body).await;
-        }
-        ("POST", "/s3/list_buckets") => {
-            return s3_handler::list_buckets(app_state).await;
-        }
-        ("POST", "/s3/create_bucket") => {
-            return s3_handler::create_bucket(app_state, &request.body).await;
-        }
-        ("POST", "/s3/head_bucket") => {
-            return s3_handler::head_bucket(app_state, &request.body).await;
-        }
-        ("POST", "/s3/delete_bucket") => {
-            return s3_handler::delete_bucket(app_state, &request.body).await;
-        }
-        ("OPTIONS", "/s3/list_objects_v2") | ("OPTIONS", "/s3/presigned_get_object") => {
-            return Ok(s3_handler::s3_preflight());
-        }
-        _ => {}
+        RouteMatch::NotFound => Err(ApiError::NotFound(format!("Route not found: {}", path))),
     }
+}
 
-    if is_known_path(path) {
-        return Err(ApiError::MethodNotAllowed(format!(
-            "Method {} is not allowed for {}",
-            request.method, path
-        )));
+/// `/health` と CORS プリフライト以外はすべて API キーを要求する。
+/// Slack から呼ばれるルートと、`GITHUB_WEBHOOK_SECRET` を設定した `/hooks/github` は
+/// API キーの代わりにハンドラーで署名を検証する。
+fn authenticate_request(
+    request: &Request,
+    path: &str,
+    app_state: &AppState,
+) -> Result<CallerIdentity, ApiError> {
+    if is_public_route(&request.method, path) {
+        return Ok(CallerIdentity::anonymous());
+    }
+    if request.method == "POST"
+        && path == "/hooks/github"
+        && app_state.settings.github_webhook_secret.is_some()
+    {
+        return Ok(CallerIdentity::anonymous());
     }
+    auth::authenticate(&app_state.settings, request.headers.as_slice())
+}
 
-    Err(ApiError::NotFound(format!("Route not found: {}", path)))
+fn is_public_route(method: &str, path: &str) -> bool {
+    path == "/health"
+        || method == "OPTIONS"
+        || (method == "POST"
+            && matches!(
+                path,
+                "/slack/events" | "/slack/commands" | "/slack/interactivity"
+            ))
 }
 
 fn split_uri(uri: &str) -> (&str, Option<&str>) {
@@ -521,35 +1271,6 @@ fn split_uri(uri: &str) -> (&str, Option<&str>) {
     }
 }
 
-fn is_known_path(path: &str) -> bool {
-    matches!(
-        path,
-        "/health"
-            | "/openapi.json"
-            | "/slack/message"
-            | "/slack/upload/image"
-            | "/slack/upload/pdf"
-            | "/s3/put_object_base64"
-            | "/s3/get_object_base64"
-            | "/s3/head_object"
-            | "/s3/delete_object"
-            | "/s3/delete_objects"
-            | "/s3/list_objects_v2"
-            | "/s3/create_multipart_upload"
-            | "/s3/upload_part_base64"
-            | "/s3/complete_multipart_upload"
-            | "/s3/abort_multipart_upload"
-            | "/s3/list_parts"
-            | "/s3/list_multipart_uploads"
-            | "/s3/presigned_get_object"
-            | "/s3/presigned_put_object"
-            | "/s3/list_buckets"
-            | "/s3/create_bucket"
-            | "/s3/head_bucket"
-            | "/s3/delete_bucket"
-    ) || path.starts_with("/s3/preview/")
-}
-
 fn apply_problem_details(response: &mut Response) {
     if !(400..=599).contains(&response.status_code) {
         return;
@@ -581,7 +1302,123 @@ fn apply_s3_cors(path: &str, request: &Request, response: &mut Response) {
 
     response.add_header("Access-Control-Allow-Origin", allow_origin);
     response.add_header("Access-Control-Allow-Methods", "GET, POST, OPTIONS");
-    response.add_header("Access-Control-Allow-Headers", "content-type, x-request-id");
+    response.add_header(
+        "Access-Control-Allow-Headers",
+        "authorization, content-type, x-api-key, x-request-id",
+    );
     response.add_header("Access-Control-Max-Age", "600");
     response.add_header("Vary", "Origin");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::handle_connection;
+    use crate::config::state::AppState;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    #[tokio::test]
+    async fn body_over_multipart_total_limit_is_rejected_with_413() {
+        let mut app_state = AppState::for_test();
+        app_state.settings.multipart_limits.max_total_size = 1024;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            handle_connection(stream, app_state).await;
+        });
+
+        let mut client = TcpStream::connect(addr).await.expect("connect");
+        client
+            .write_all(
+                b"POST /slack/upload/files HTTP/1.1\r\nHost: localhost\r\n\
+                  Content-Type: multipart/form-data; boundary=b\r\n\
+                  Content-Length: 2048\r\n\r\n",
+            )
+            .await
+            .expect("write");
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.expect("read");
+        let response = String::from_utf8_lossy(&response);
+
+        assert!(
+            response.starts_with("HTTP/1.1 413 "),
+            "unexpected response: {response}"
+        );
+        server.await.expect("server");
+    }
+
+    #[tokio::test]
+    async fn unauthenticated_routes_keep_the_default_body_limit() {
+        let app_state = AppState::for_test();
+        assert!(app_state.settings.multipart_limits.max_total_size > 20 * 1024 * 1024);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            handle_connection(stream, app_state).await;
+        });
+
+        let mut client = TcpStream::connect(addr).await.expect("connect");
+        client
+            .write_all(
+                format!(
+                    "POST /slack/events HTTP/1.1\r\nHost: localhost\r\n\
+                     Content-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                    20 * 1024 * 1024
+                )
+                .as_bytes(),
+            )
+            .await
+            .expect("write");
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.expect("read");
+        let response = String::from_utf8_lossy(&response);
+
+        assert!(
+            response.starts_with("HTTP/1.1 413 "),
+            "unexpected response: {response}"
+        );
+        server.await.expect("server");
+    }
+
+    #[tokio::test]
+    async fn part_over_multipart_part_limit_is_rejected_with_413() {
+        let mut app_state = AppState::for_test();
+        app_state.settings.multipart_limits.max_part_size = 16;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("addr");
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.expect("accept");
+            handle_connection(stream, app_state).await;
+        });
+
+        let body = format!(
+            "--b\r\nContent-Disposition: form-data; name=\"channel\"\r\n\r\nC1\r\n\
+             --b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\n{}\r\n--b--\r\n",
+            "x".repeat(100)
+        );
+        let request = format!(
+            "POST /slack/upload/files HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+             Content-Type: multipart/form-data; boundary=b\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let mut client = TcpStream::connect(addr).await.expect("connect");
+        client.write_all(request.as_bytes()).await.expect("write");
+        let mut response = Vec::new();
+        client.read_to_end(&mut response).await.expect("read");
+        let response = String::from_utf8_lossy(&response);
+
+        assert!(
+            response.starts_with("HTTP/1.1 413 "),
+            "unexpected response: {response}"
+        );
+        server.await.expect("server");
+    }
+}
@@ -0,0 +1,223 @@
+use crate::auth::scope::glob_match;
+
+/// 先頭のバイト列で判別できる形式
+#[derive(Debug, Clone, Copy)]
+pub struct FileType {
+    pub content_type: &'static str,
+    pub extension: &'static str,
+    matches: fn(&[u8]) -> bool,
+}
+
+impl FileType {
+    pub fn matches(&self, data: &[u8]) -> bool {
+        (self.matches)(data)
+    }
+}
+
+pub const PNG: FileType = FileType {
+    content_type: "image/png",
+    extension: "png",
+    matches: |data| data.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']),
+};
+pub const JPEG: FileType = FileType {
+    content_type: "image/jpeg",
+    extension: "jpg",
+    matches: |data| data.starts_with(&[0xFF, 0xD8, 0xFF]),
+};
+pub const GIF: FileType = FileType {
+    content_type: "image/gif",
+    extension: "gif",
+    matches: |data| data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a"),
+};
+pub const WEBP: FileType = FileType {
+    content_type: "image/webp",
+    extension: "webp",
+    matches: |data| data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WEBP",
+};
+pub const PDF: FileType = FileType {
+    content_type: "application/pdf",
+    extension: "pdf",
+    matches: |data| data.starts_with(b"%PDF-"),
+};
+pub const ZIP: FileType = FileType {
+    content_type: "application/zip",
+    extension: "zip",
+    matches: |data| data.starts_with(b"PK\x03\x04") || data.starts_with(b"PK\x05\x06"),
+};
+pub const GZIP: FileType = FileType {
+    content_type: "application/gzip",
+    extension: "gz",
+    matches: |data| data.starts_with(&[0x1F, 0x8B]),
+};
+pub const TAR: FileType = FileType {
+    content_type: "application/x-tar",
+    extension: "tar",
+    matches: |data| data.len() >= 262 && &data[257..262] == b"ustar",
+};
+
+/// 判別できる形式の一覧。`sniff` はこの順に照合する。
+const SNIFFABLE: [FileType; 8] = [PNG, JPEG, GIF, WEBP, PDF, ZIP, GZIP, TAR];
+
+/// 本文が UTF-8 であることだけを確認するテキスト形式と、その既定の拡張子
+const TEXT_TYPES: [(&str, &str); 6] = [
+    ("text/plain", "txt"),
+    ("text/csv", "csv"),
+    ("text/markdown", "md"),
+    ("text/html", "html"),
+    ("application/json", "json"),
+    ("application/x-ndjson", "jsonl"),
+];
+
+/// 本文を読み切らずに判別する場合に読む先頭のバイト数。どの形式もこの範囲で判別できる。
+pub const SNIFF_LEN: usize = 512;
+
+/// 本文の先頭 `SNIFF_LEN` バイトから、末尾で途中まで切れた UTF-8 の文字を除く。
+pub fn trim_partial_utf8(prefix: &[u8]) -> &[u8] {
+    match std::str::from_utf8(prefix) {
+        Err(e) if e.error_len().is_none() => &prefix[..e.valid_up_to()],
+        _ => prefix,
+    }
+}
+
+/// 先頭のバイト列から形式を推定する。
+pub fn sniff(data: &[u8]) -> Option<FileType> {
+    SNIFFABLE
+        .into_iter()
+        .find(|file_type| file_type.matches(data))
+}
+
+/// `Content-Type` (パラメータを除いた小文字) と本文を照合し、実際の形式と既定の拡張子を返す。
+///
+/// - 判別できる形式を名乗る場合は、先頭のバイト列が一致しなければエラー
+/// - テキスト形式 (`text/*` / JSON) は UTF-8 でなければエラー
+/// - `application/octet-stream` は推定できた形式に置き換える
+/// - それ以外はそのまま受け付ける
+pub fn check_content(content_type: &str, data: &[u8]) -> Result<(String, &'static str), String> {
+    if let Some(file_type) = SNIFFABLE
+        .into_iter()
+        .find(|file_type| file_type.content_type == content_type)
+    {
+        if !file_type.matches(data) {
+            return Err(format!("Body does not look like {content_type}"));
+        }
+        return Ok((content_type.to_string(), file_type.extension));
+    }
+
+    let text_extension = TEXT_TYPES
+        .iter()
+        .find(|(text_type, _)| *text_type == content_type)
+        .map(|(_, extension)| *extension);
+    if text_extension.is_some() || content_type.starts_with("text/") {
+        if std::str::from_utf8(data).is_err() {
+            return Err(format!("Body of {content_type} must be valid UTF-8 text"));
+        }
+        return Ok((content_type.to_string(), text_extension.unwrap_or("txt")));
+    }
+
+    if content_type == "application/octet-stream"
+        && let Some(file_type) = sniff(data)
+    {
+        return Ok((file_type.content_type.to_string(), file_type.extension));
+    }
+
+    Ok((content_type.to_string(), "bin"))
+}
+
+/// アップロードを受け付ける `Content-Type` の許可・拒否リスト。`*` を glob として扱う (`image/*` など)。
+///
+/// 拒否リストが優先され、許可リストが空の場合は拒否されていないものをすべて受け付ける。
+#[derive(Debug, Clone, Default)]
+pub struct ContentTypePolicy {
+    pub allowed: Vec<String>,
+    pub denied: Vec<String>,
+}
+
+impl ContentTypePolicy {
+    /// カンマ区切りのパターン一覧を読む。
+    pub fn parse_list(raw: &str) -> Vec<String> {
+        raw.split(',')
+            .map(|pattern| pattern.trim().to_ascii_lowercase())
+            .filter(|pattern| !pattern.is_empty())
+            .collect()
+    }
+
+    pub fn permits(&self, content_type: &str) -> bool {
+        if self
+            .denied
+            .iter()
+            .any(|pattern| glob_match(pattern, content_type))
+        {
+            return false;
+        }
+        self.allowed.is_empty()
+            || self
+                .allowed
+                .iter()
+                .any(|pattern| glob_match(pattern, content_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContentTypePolicy, check_content, sniff, trim_partial_utf8};
+
+    #[test]
+    fn sniff_detects_archives() {
+        assert_eq!(sniff(b"PK\x03\x04rest").map(|t| t.extension), Some("zip"));
+        assert_eq!(sniff(&[0x1F, 0x8B, 0x08]).map(|t| t.extension), Some("gz"));
+        assert!(sniff(b"plain text").is_none());
+    }
+
+    #[test]
+    fn declared_type_must_match_magic_bytes() {
+        assert!(check_content("application/zip", b"PK\x03\x04").is_ok());
+        assert!(check_content("application/zip", b"not a zip").is_err());
+        assert!(check_content("image/png", b"%PDF-1.7").is_err());
+    }
+
+    #[test]
+    fn text_types_must_be_utf8() {
+        assert_eq!(
+            check_content("text/csv", b"a,b\n1,2\n"),
+            Ok(("text/csv".to_string(), "csv"))
+        );
+        assert_eq!(
+            check_content("text/x-log", b"INFO started"),
+            Ok(("text/x-log".to_string(), "txt"))
+        );
+        assert!(check_content("text/plain", &[0xFF, 0xFE, 0x00]).is_err());
+    }
+
+    #[test]
+    fn partial_utf8_at_the_end_of_a_prefix_is_trimmed() {
+        let text = "日本語".as_bytes();
+        assert_eq!(trim_partial_utf8(&text[..4]), &text[..3]);
+        assert_eq!(trim_partial_utf8(text), text);
+        assert_eq!(trim_partial_utf8(b"\xFFabc"), b"\xFFabc");
+    }
+
+    #[test]
+    fn octet_stream_is_refined_by_sniffing() {
+        assert_eq!(
+            check_content("application/octet-stream", b"%PDF-1.7"),
+            Ok(("application/pdf".to_string(), "pdf"))
+        );
+        assert_eq!(
+            check_content("application/octet-stream", &[0x00, 0x01]),
+            Ok(("application/octet-stream".to_string(), "bin"))
+        );
+    }
+
+    #[test]
+    fn policy_denies_before_allowing() {
+        let policy = ContentTypePolicy {
+            allowed: ContentTypePolicy::parse_list("text/*, application/zip"),
+            denied: ContentTypePolicy::parse_list("text/html"),
+        };
+        assert!(policy.permits("text/csv"));
+        assert!(policy.permits("application/zip"));
+        assert!(!policy.permits("text/html"));
+        assert!(!policy.permits("application/x-msdownload"));
+        assert!(ContentTypePolicy::default().permits("application/x-msdownload"));
+    }
+}
@@ -0,0 +1,715 @@
+use nojson::{RawJsonOwned, RawJsonValue};
+
+use crate::{
+    errors::api_error::ApiError,
+    service::{
+        hook_routes::HookSource,
+        slack_message::{SlackMessage, escape_mrkdwn},
+    },
+};
+
+const COLOR_DANGER: &str = "#E01E5A";
+const COLOR_WARNING: &str = "#ECB22E";
+const COLOR_INFO: &str = "#36C5F0";
+const COLOR_GOOD: &str = "#2EB67D";
+const COLOR_NEUTRAL: &str = "#6E7781";
+const COLOR_MERGED: &str = "#8250DF";
+
+/// `push` で1件ずつ表示するコミット数の上限
+const MAX_PUSH_COMMITS: usize = 5;
+
+/// Webhook 1件から作る Slack への通知
+#[derive(Debug, Clone)]
+pub struct HookNotification {
+    /// スレッドにまとめる単位 (アラートの fingerprint や PR 番号)。`None` なら常に新しいメッセージにする
+    pub group_key: Option<String>,
+    /// ルーティングに使う項目 (アラートのラベル、GitHub のリポジトリなど)
+    pub fields: Vec<(String, String)>,
+    pub message: SlackMessage,
+    /// 解決・マージなど状態が変わった通知。スレッドへの返信をチャンネルにも表示する
+    pub broadcast: bool,
+}
+
+/// 色付きの attachment 1つで表す通知の本文
+struct Attachment {
+    color: &'static str,
+    title: String,
+    title_link: Option<String>,
+    text: String,
+    fields: Vec<(String, String)>,
+    footer: &'static str,
+}
+
+impl Attachment {
+    fn into_message(self, fallback: String) -> Result<SlackMessage, ApiError> {
+        let attachments = nojson::json(|f| {
+            f.value(nojson::array(|f| {
+                f.element(nojson::object(|f| {
+                    f.member("color", self.color)?;
+                    f.member("fallback", &fallback)?;
+                    f.member("title", &self.title)?;
+                    if let Some(link) = &self.title_link {
+                        f.member("title_link", link)?;
+                    }
+                    if !self.text.is_empty() {
+                        f.member("text", &self.text)?;
+                    }
+                    if !self.fields.is_empty() {
+                        f.member(
+                            "fields",
+                            nojson::array(|f| {
+                                for (title, value) in &self.fields {
+                                    f.element(nojson::object(|f| {
+                                        f.member("title", title)?;
+                                        f.member("value", value)?;
+                                        f.member("short", true)
+                                    }))?;
+                                }
+                                Ok(())
+                            }),
+                        )?;
+                    }
+                    f.member("footer", self.footer)
+                }))
+            }))
+        })
+        .to_string();
+        let attachments = RawJsonOwned::parse(attachments)
+            .map_err(|e| ApiError::InternalServerError(e.to_string()))?;
+        Ok(SlackMessage {
+            text: Some(fallback),
+            attachments: Some(attachments),
+            ..SlackMessage::default()
+        })
+    }
+}
+
+/// Alertmanager の Webhook (`version: 4`) をアラートごとの通知にする。
+pub fn from_alertmanager(body: &str) -> Result<Vec<HookNotification>, ApiError> {
+    from_alerts(HookSource::Alertmanager, body)
+}
+
+/// Grafana Alerting の Webhook をアラートごとの通知にする。形式は Alertmanager にダッシュボードなどの URL を加えたもの。
+pub fn from_grafana(body: &str) -> Result<Vec<HookNotification>, ApiError> {
+    from_alerts(HookSource::Grafana, body)
+}
+
+fn from_alerts(source: HookSource, body: &str) -> Result<Vec<HookNotification>, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let alerts = member(root, &["alerts"])
+        .and_then(|alerts| alerts.to_array().ok())
+        .ok_or_else(|| ApiError::BadRequest("'alerts' must be an array".to_string()))?;
+    let receiver = member_str(root, &["receiver"]);
+    let group_status = member_str(root, &["status"]).unwrap_or_else(|| "firing".to_string());
+
+    let mut notifications = Vec::new();
+    for alert in alerts {
+        let status = member_str(alert, &["status"]).unwrap_or_else(|| group_status.clone());
+        let labels = string_map(alert, "labels");
+        let annotations = string_map(alert, "annotations");
+        let label = |name: &str| {
+            labels
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let annotation = |name: &str| {
+            annotations
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let alertname = label("alertname").unwrap_or("alert");
+        let severity = label("severity");
+        let resolved = status == "resolved";
+
+        let mut lines = Vec::new();
+        if let Some(summary) = annotation("summary") {
+            lines.push(escape_mrkdwn(summary));
+        }
+        if let Some(description) = annotation("description") {
+            lines.push(escape_mrkdwn(description));
+        }
+        if let Some(value) = member_str(alert, &["valueString"]).filter(|v| !v.is_empty()) {
+            lines.push(format!("Values: `{}`", escape_mrkdwn(&value)));
+        }
+        let links = [
+            ("Dashboard", member_str(alert, &["dashboardURL"])),
+            ("Panel", member_str(alert, &["panelURL"])),
+            ("Silence", member_str(alert, &["silenceURL"])),
+        ]
+        .into_iter()
+        .filter_map(|(label, url)| Some(link(&url.filter(|u| !u.is_empty())?, label)))
+        .collect::<Vec<_>>();
+        if !links.is_empty() {
+            lines.push(links.join(" · "));
+        }
+
+        let title_link = member_str(alert, &["panelURL"])
+            .or_else(|| member_str(alert, &["generatorURL"]))
+            .filter(|url| !url.is_empty());
+        let state = if resolved { "RESOLVED" } else { "FIRING" };
+        let fallback = match severity {
+            Some(severity) => format!(
+                "[{state}] {} ({})",
+                escape_mrkdwn(alertname),
+                escape_mrkdwn(severity)
+            ),
+            None => format!("[{state}] {}", escape_mrkdwn(alertname)),
+        };
+        let message = Attachment {
+            color: if resolved {
+                COLOR_GOOD
+            } else {
+                severity_color(severity)
+            },
+            title: format!("[{state}] {}", escape_mrkdwn(alertname)),
+            title_link,
+            text: lines.join("\n"),
+            fields: labels
+                .iter()
+                .filter(|(key, _)| key != "alertname")
+                .map(|(key, value)| (key.clone(), escape_mrkdwn(value)))
+                .collect(),
+            footer: match source {
+                HookSource::Grafana => "Grafana",
+                _ => "Alertmanager",
+            },
+        }
+        .into_message(fallback)?;
+
+        let mut fields = labels.clone();
+        fields.push(("status".to_string(), status.clone()));
+        if let Some(receiver) = &receiver {
+            fields.push(("receiver".to_string(), receiver.clone()));
+        }
+        notifications.push(HookNotification {
+            group_key: member_str(alert, &["fingerprint"])
+                .filter(|fingerprint| !fingerprint.is_empty())
+                .map(|fingerprint| format!("{source}:{fingerprint}")),
+            fields,
+            message,
+            broadcast: resolved,
+        });
+    }
+    Ok(notifications)
+}
+
+fn severity_color(severity: Option<&str>) -> &'static str {
+    match severity.map(str::to_ascii_lowercase).as_deref() {
+        Some("critical" | "error" | "page" | "high") => COLOR_DANGER,
+        Some("info" | "informational" | "low" | "none") => COLOR_INFO,
+        _ => COLOR_WARNING,
+    }
+}
+
+/// GitHub の Webhook を通知にする。`event` は `X-GitHub-Event`。通知しないイベントやアクションは空を返す。
+pub fn from_github(event: &str, body: &str) -> Result<Vec<HookNotification>, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+    let repository = member_str(root, &["repository", "full_name"]).unwrap_or_default();
+    let sender = member_str(root, &["sender", "login"]).unwrap_or_default();
+    let action = member_str(root, &["action"]);
+
+    let notification = match event {
+        "pull_request" => github_pull_request(root, &repository, &sender)?,
+        "pull_request_review" => github_review(root, &repository, &sender)?,
+        "issues" => github_issue(root, &repository, &sender)?,
+        "push" => github_push(root, &repository, &sender)?,
+        "workflow_run" => github_workflow_run(root, &repository)?,
+        _ => None,
+    };
+    let Some((mut notification, branch)) = notification else {
+        return Ok(Vec::new());
+    };
+
+    notification.fields = [
+        Some(("event", event.to_string())),
+        action.map(|action| ("action", action)),
+        Some(("repository", repository)),
+        Some(("sender", sender)),
+        branch.map(|branch| ("branch", branch)),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect();
+    Ok(vec![notification])
+}
+
+/// 通知とルーティングに使うブランチ名
+type GithubNotification = Option<(HookNotification, Option<String>)>;
+
+fn github_pull_request(
+    root: RawJsonValue<'_, '_>,
+    repository: &str,
+    sender: &str,
+) -> Result<GithubNotification, ApiError> {
+    let action = member_str(root, &["action"]).unwrap_or_default();
+    let pr = member(root, &["pull_request"])
+        .ok_or_else(|| ApiError::BadRequest("'pull_request' is required".to_string()))?;
+    let number = github_number(root, pr)?;
+    let merged = member(pr, &["merged"]).is_some_and(|v| bool::try_from(v).unwrap_or(false));
+    let (state, color) = match action.as_str() {
+        "opened" | "reopened" | "ready_for_review" => (action.replace('_', " "), COLOR_INFO),
+        "closed" if merged => ("merged".to_string(), COLOR_MERGED),
+        "closed" => ("closed".to_string(), COLOR_NEUTRAL),
+        "converted_to_draft" => ("converted to draft".to_string(), COLOR_NEUTRAL),
+        _ => return Ok(None),
+    };
+
+    let title = member_str(pr, &["title"]).unwrap_or_default();
+    let head = member_str(pr, &["head", "ref"]).unwrap_or_default();
+    let base = member_str(pr, &["base", "ref"]);
+    let message = Attachment {
+        color,
+        title: format!("#{number} {}", escape_mrkdwn(&title)),
+        title_link: member_str(pr, &["html_url"]),
+        text: format!("Pull request {state} by {}", escape_mrkdwn(sender)),
+        fields: vec![
+            ("Repository".to_string(), escape_mrkdwn(repository)),
+            (
+                "Branch".to_string(),
+                escape_mrkdwn(&format!("{head} → {}", base.as_deref().unwrap_or_default())),
+            ),
+        ],
+        footer: "GitHub",
+    }
+    .into_message(format!(
+        "[{}] Pull request #{number} {state}: {}",
+        escape_mrkdwn(repository),
+        escape_mrkdwn(&title)
+    ))?;
+
+    Ok(Some((
+        HookNotification {
+            group_key: Some(format!("github:{repository}#{number}")),
+            fields: Vec::new(),
+            message,
+            broadcast: action != "opened",
+        },
+        base,
+    )))
+}
+
+fn github_review(
+    root: RawJsonValue<'_, '_>,
+    repository: &str,
+    sender: &str,
+) -> Result<GithubNotification, ApiError> {
+    if member_str(root, &["action"]).as_deref() != Some("submitted") {
+        return Ok(None);
+    }
+    let pr = member(root, &["pull_request"])
+        .ok_or_else(|| ApiError::BadRequest("'pull_request' is required".to_string()))?;
+    let number = github_number(root, pr)?;
+    let review_state = member_str(root, &["review", "state"])
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let (state, color) = match review_state.as_str() {
+        "approved" => ("approved", COLOR_GOOD),
+        "changes_requested" => ("requested changes on", COLOR_DANGER),
+        _ => ("commented on", COLOR_NEUTRAL),
+    };
+
+    let title = member_str(pr, &["title"]).unwrap_or_default();
+    let message = Attachment {
+        color,
+        title: format!("#{number} {}", escape_mrkdwn(&title)),
+        title_link: member_str(root, &["review", "html_url"])
+            .or_else(|| member_str(pr, &["html_url"])),
+        text: format!("{} {state} the pull request", escape_mrkdwn(sender)),
+        fields: Vec::new(),
+        footer: "GitHub",
+    }
+    .into_message(format!(
+        "[{}] {} {state} #{number}: {}",
+        escape_mrkdwn(repository),
+        escape_mrkdwn(sender),
+        escape_mrkdwn(&title)
+    ))?;
+
+    Ok(Some((
+        HookNotification {
+            group_key: Some(format!("github:{repository}#{number}")),
+            fields: Vec::new(),
+            message,
+            broadcast: review_state == "approved",
+        },
+        member_str(pr, &["base", "ref"]),
+    )))
+}
+
+fn github_issue(
+    root: RawJsonValue<'_, '_>,
+    repository: &str,
+    sender: &str,
+) -> Result<GithubNotification, ApiError> {
+    let action = member_str(root, &["action"]).unwrap_or_default();
+    let color = match action.as_str() {
+        "opened" | "reopened" => COLOR_INFO,
+        "closed" => COLOR_NEUTRAL,
+        _ => return Ok(None),
+    };
+    let issue = member(root, &["issue"])
+        .ok_or_else(|| ApiError::BadRequest("'issue' is required".to_string()))?;
+    let number = github_number(root, issue)?;
+
+    let title = member_str(issue, &["title"]).unwrap_or_default();
+    let message = Attachment {
+        color,
+        title: format!("#{number} {}", escape_mrkdwn(&title)),
+        title_link: member_str(issue, &["html_url"]),
+        text: format!("Issue {action} by {}", escape_mrkdwn(sender)),
+        fields: Vec::new(),
+        footer: "GitHub",
+    }
+    .into_message(format!(
+        "[{}] Issue #{number} {action}: {}",
+        escape_mrkdwn(repository),
+        escape_mrkdwn(&title)
+    ))?;
+
+    Ok(Some((
+        HookNotification {
+            group_key: Some(format!("github:{repository}#{number}")),
+            fields: Vec::new(),
+            message,
+            broadcast: action != "opened",
+        },
+        None,
+    )))
+}
+
+fn github_push(
+    root: RawJsonValue<'_, '_>,
+    repository: &str,
+    sender: &str,
+) -> Result<GithubNotification, ApiError> {
+    let commits = member(root, &["commits"])
+        .and_then(|commits| commits.to_array().ok())
+        .map(|commits| commits.collect::<Vec<_>>())
+        .unwrap_or_default();
+    if commits.is_empty() {
+        return Ok(None);
+    }
+    let git_ref = member_str(root, &["ref"]).unwrap_or_default();
+    let branch = git_ref
+        .strip_prefix("refs/heads/")
+        .unwrap_or(&git_ref)
+        .to_string();
+
+    let mut lines = commits
+        .iter()
+        .take(MAX_PUSH_COMMITS)
+        .map(|commit| {
+            let id = member_str(*commit, &["id"]).unwrap_or_default();
+            let short_id = id.get(..7).unwrap_or(&id);
+            let summary = member_str(*commit, &["message"])
+                .unwrap_or_default()
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            let commit_link = match member_str(*commit, &["url"]) {
+                Some(url) => link(&url, short_id),
+                None => format!("`{}`", escape_mrkdwn(short_id)),
+            };
+            format!("{commit_link} {}", escape_mrkdwn(&summary))
+        })
+        .collect::<Vec<_>>();
+    if commits.len() > MAX_PUSH_COMMITS {
+        lines.push(format!("… and {} more", commits.len() - MAX_PUSH_COMMITS));
+    }
+
+    let count = commits.len();
+    let noun = if count == 1 { "commit" } else { "commits" };
+    let message = Attachment {
+        color: COLOR_INFO,
+        title: format!("{count} {noun} pushed to {}", escape_mrkdwn(&branch)),
+        title_link: member_str(root, &["compare"]),
+        text: lines.join("\n"),
+        fields: vec![
+            ("Repository".to_string(), escape_mrkdwn(repository)),
+            ("Pusher".to_string(), escape_mrkdwn(sender)),
+        ],
+        footer: "GitHub",
+    }
+    .into_message(format!(
+        "[{}] {count} {noun} pushed to {} by {}",
+        escape_mrkdwn(repository),
+        escape_mrkdwn(&branch),
+        escape_mrkdwn(sender)
+    ))?;
+
+    Ok(Some((
+        HookNotification {
+            group_key: None,
+            fields: Vec::new(),
+            message,
+            broadcast: false,
+        },
+        Some(branch),
+    )))
+}
+
+fn github_workflow_run(
+    root: RawJsonValue<'_, '_>,
+    repository: &str,
+) -> Result<GithubNotification, ApiError> {
+    if member_str(root, &["action"]).as_deref() != Some("completed") {
+        return Ok(None);
+    }
+    let run = member(root, &["workflow_run"])
+        .ok_or_else(|| ApiError::BadRequest("'workflow_run' is required".to_string()))?;
+    let conclusion = member_str(run, &["conclusion"]).unwrap_or_else(|| "unknown".to_string());
+    let color = match conclusion.as_str() {
+        "success" => COLOR_GOOD,
+        "failure" | "timed_out" | "startup_failure" => COLOR_DANGER,
+        "cancelled" | "skipped" | "neutral" => COLOR_NEUTRAL,
+        _ => COLOR_WARNING,
+    };
+    let name = member_str(run, &["name"]).unwrap_or_else(|| "workflow".to_string());
+    let run_number = member_i64(run, &["run_number"])
+        .map(|n| format!(" #{n}"))
+        .unwrap_or_default();
+    let branch = member_str(run, &["head_branch"]);
+
+    let message = Attachment {
+        color,
+        title: format!(
+            "{}{run_number} {}",
+            escape_mrkdwn(&name),
+            escape_mrkdwn(&conclusion)
+        ),
+        title_link: member_str(run, &["html_url"]),
+        text: String::new(),
+        fields: [
+            Some(("Repository".to_string(), escape_mrkdwn(repository))),
+            branch
+                .as_deref()
+                .map(|branch| ("Branch".to_string(), escape_mrkdwn(branch))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        footer: "GitHub Actions",
+    }
+    .into_message(format!(
+        "[{}] {}{run_number} {}",
+        escape_mrkdwn(repository),
+        escape_mrkdwn(&name),
+        escape_mrkdwn(&conclusion)
+    ))?;
+
+    Ok(Some((
+        HookNotification {
+            group_key: None,
+            fields: Vec::new(),
+            message,
+            broadcast: false,
+        },
+        branch,
+    )))
+}
+
+fn github_number(root: RawJsonValue<'_, '_>, item: RawJsonValue<'_, '_>) -> Result<i64, ApiError> {
+    member_i64(item, &["number"])
+        .or_else(|| member_i64(root, &["number"]))
+        .ok_or_else(|| ApiError::BadRequest("'number' is required".to_string()))
+}
+
+/// `path` をたどった先の値。途中が無い・`null` なら `None`。
+fn member<'text, 'raw>(
+    value: RawJsonValue<'text, 'raw>,
+    path: &[&str],
+) -> Option<RawJsonValue<'text, 'raw>> {
+    path.iter().try_fold(value, |value, name| {
+        value
+            .to_member(name)
+            .ok()?
+            .optional()
+            .filter(|v| !v.kind().is_null())
+    })
+}
+
+fn member_str(value: RawJsonValue<'_, '_>, path: &[&str]) -> Option<String> {
+    member(value, path).and_then(|v| String::try_from(v).ok())
+}
+
+fn member_i64(value: RawJsonValue<'_, '_>, path: &[&str]) -> Option<i64> {
+    member(value, path).and_then(|v| i64::try_from(v).ok())
+}
+
+/// 文字列の値だけを取り出したオブジェクトのメンバー (ラベルなど)
+fn string_map(value: RawJsonValue<'_, '_>, name: &str) -> Vec<(String, String)> {
+    let Some(object) = member(value, &[name]).and_then(|v| v.to_object().ok()) else {
+        return Vec::new();
+    };
+    object
+        .filter_map(|(key, value)| {
+            Some((
+                key.to_unquoted_string_str().ok()?.into_owned(),
+                String::try_from(value).ok()?,
+            ))
+        })
+        .collect()
+}
+
+fn link(url: &str, label: &str) -> String {
+    format!(
+        "<{}|{}>",
+        escape_mrkdwn(url).replace('|', "%7C"),
+        escape_mrkdwn(label)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HookNotification, from_alertmanager, from_github, from_grafana};
+
+    fn attachment(notification: &HookNotification) -> String {
+        notification
+            .message
+            .attachments
+            .as_ref()
+            .expect("attachments")
+            .text()
+            .to_string()
+    }
+
+    #[test]
+    fn alertmanager_alerts_are_grouped_by_fingerprint() {
+        let notifications = from_alertmanager(
+            r#"{
+                "version": "4", "status": "firing", "receiver": "api-hub",
+                "alerts": [
+                    {"status": "firing", "fingerprint": "abc",
+                     "labels": {"alertname": "HighLatency", "severity": "critical", "service": "api"},
+                     "annotations": {"summary": "p99 > 1s <api>"},
+                     "generatorURL": "http://prometheus/graph"},
+                    {"status": "resolved", "fingerprint": "def",
+                     "labels": {"alertname": "DiskFull", "severity": "warning"}}
+                ]
+            }"#,
+        )
+        .expect("alerts");
+        assert_eq!(notifications.len(), 2);
+
+        let firing = &notifications[0];
+        assert_eq!(firing.group_key.as_deref(), Some("alertmanager:abc"));
+        assert!(!firing.broadcast);
+        assert_eq!(
+            firing.message.text.as_deref(),
+            Some("[FIRING] HighLatency (critical)")
+        );
+        assert!(
+            firing
+                .fields
+                .contains(&("severity".to_string(), "critical".to_string()))
+        );
+        assert!(
+            firing
+                .fields
+                .contains(&("receiver".to_string(), "api-hub".to_string()))
+        );
+        let json = attachment(firing);
+        assert!(json.contains(r##""color":"#E01E5A""##), "{json}");
+        assert!(json.contains("p99 &gt; 1s &lt;api&gt;"), "{json}");
+        assert!(!json.contains(r#""title":"alertname""#), "{json}");
+
+        let resolved = &notifications[1];
+        assert!(resolved.broadcast);
+        assert!(attachment(resolved).contains(r##""color":"#2EB67D""##));
+        assert!(from_alertmanager(r#"{"status": "firing"}"#).is_err());
+    }
+
+    #[test]
+    fn grafana_alerts_link_to_panel_and_silence() {
+        let notifications = from_grafana(
+            r#"{"status": "firing", "alerts": [{"status": "firing", "fingerprint": "f1",
+                "labels": {"alertname": "CPU", "severity": "info"},
+                "panelURL": "https://grafana/d/x?viewPanel=1",
+                "silenceURL": "https://grafana/alerting/silence/new?a=1&b=<2>",
+                "valueString": "[ var='A' value=93 ]"}]}"#,
+        )
+        .expect("alerts");
+        let json = attachment(&notifications[0]);
+        assert_eq!(notifications[0].group_key.as_deref(), Some("grafana:f1"));
+        assert!(json.contains(r#""title_link":"https://grafana/d/x?viewPanel=1""#));
+        assert!(
+            json.contains("<https://grafana/alerting/silence/new?a=1&amp;b=&lt;2&gt;|Silence>")
+        );
+        assert!(json.contains(r##""color":"#36C5F0""##));
+        assert!(json.contains(r#""footer":"Grafana""#));
+    }
+
+    #[test]
+    fn github_pull_requests_are_grouped_by_number() {
+        let body = |action: &str, merged: bool| {
+            format!(
+                r#"{{"action": "{action}", "number": 12,
+                    "pull_request": {{"number": 12, "title": "Fix <bug>", "html_url": "https://github.com/o/r/pull/12",
+                        "merged": {merged}, "head": {{"ref": "fix"}}, "base": {{"ref": "main"}}}},
+                    "repository": {{"full_name": "o/r"}}, "sender": {{"login": "octocat"}}}}"#
+            )
+        };
+
+        let opened = from_github("pull_request", &body("opened", false)).expect("opened");
+        assert_eq!(opened[0].group_key.as_deref(), Some("github:o/r#12"));
+        assert!(!opened[0].broadcast);
+        assert!(
+            opened[0]
+                .fields
+                .contains(&("branch".to_string(), "main".to_string()))
+        );
+        assert!(attachment(&opened[0]).contains("#12 Fix &lt;bug&gt;"));
+        assert_eq!(
+            opened[0].message.text.as_deref(),
+            Some("[o/r] Pull request #12 opened: Fix &lt;bug&gt;")
+        );
+
+        let merged = from_github("pull_request", &body("closed", true)).expect("merged");
+        assert!(merged[0].broadcast);
+        assert!(attachment(&merged[0]).contains(r##""color":"#8250DF""##));
+
+        assert!(
+            from_github("pull_request", &body("labeled", false))
+                .expect("ignored")
+                .is_empty()
+        );
+        assert!(
+            from_github("ping", r#"{"zen": "hi"}"#)
+                .expect("ping")
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn github_push_lists_commits() {
+        let commits = (0..7)
+            .map(|i| format!(r#"{{"id": "abcdef{i}123", "message": "change {i}\n\nbody", "url": "https://github.com/o/r/commit/{i}"}}"#))
+            .collect::<Vec<_>>()
+            .join(",");
+        let notifications = from_github(
+            "push",
+            &format!(
+                r#"{{"ref": "refs/heads/main", "compare": "https://github.com/o/r/compare/a...b",
+                    "commits": [{commits}], "repository": {{"full_name": "o/r"}}, "sender": {{"login": "octocat"}}}}"#
+            ),
+        )
+        .expect("push");
+        let json = attachment(&notifications[0]);
+        assert_eq!(notifications[0].group_key, None);
+        assert!(json.contains("7 commits pushed to main"), "{json}");
+        assert!(
+            json.contains("<https://github.com/o/r/commit/0|abcdef0> change 0"),
+            "{json}"
+        );
+        assert!(json.contains("… and 2 more"), "{json}");
+    }
+}
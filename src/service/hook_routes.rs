@@ -0,0 +1,173 @@
+use crate::auth::scope::glob_match;
+
+/// `/hooks/*` で受け付ける Webhook の送信元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookSource {
+    Alertmanager,
+    Github,
+    Grafana,
+}
+
+impl HookSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Alertmanager => "alertmanager",
+            Self::Github => "github",
+            Self::Grafana => "grafana",
+        }
+    }
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "alertmanager" => Some(Self::Alertmanager),
+            "github" => Some(Self::Github),
+            "grafana" => Some(Self::Grafana),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for HookSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 通知の送り先を決めるルール。`HOOK_ROUTES` に上から順に並べ、最初に一致したものを使う。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookRoute {
+    /// `None` はすべての送信元 (`"*"`)
+    pub source: Option<HookSource>,
+    /// 項目名と値の glob の組。すべて一致したときだけ使う
+    pub matchers: Vec<(String, String)>,
+    pub channel: String,
+}
+
+impl HookRoute {
+    fn matches(&self, source: HookSource, fields: &[(String, String)]) -> bool {
+        self.source.is_none_or(|s| s == source)
+            && self.matchers.iter().all(|(name, pattern)| {
+                fields
+                    .iter()
+                    .any(|(field, value)| field == name && glob_match(pattern, value))
+            })
+    }
+}
+
+/// `[{"source": "alertmanager", "match": {"severity": "critical"}, "channel": "#oncall"}, ...]` を読む。
+pub fn parse_routes(raw: &str) -> Result<Vec<HookRoute>, String> {
+    let json = nojson::RawJson::parse(raw).map_err(|e| format!("invalid JSON: {e}"))?;
+    let items = json
+        .value()
+        .to_array()
+        .map_err(|_| "must be a JSON array".to_string())?;
+
+    let mut routes = Vec::new();
+    for (i, item) in items.enumerate() {
+        let string_member = |name: &str| -> Result<Option<String>, String> {
+            item.to_member(name)
+                .and_then(|member| member.map(String::try_from))
+                .map_err(|_| format!("routes[{i}].{name} must be a string"))
+        };
+
+        let source = match string_member("source")?.as_deref() {
+            None | Some("*") => None,
+            Some(raw) => Some(HookSource::parse(raw).ok_or_else(|| {
+                format!("routes[{i}].source must be alertmanager, github, grafana or *")
+            })?),
+        };
+        let channel = string_member("channel")?
+            .filter(|channel| !channel.is_empty())
+            .ok_or_else(|| format!("routes[{i}].channel is required"))?;
+
+        let mut matchers = Vec::new();
+        let match_member = item
+            .to_member("match")
+            .map_err(|_| format!("routes[{i}] must be an object"))?;
+        if let Some(conditions) = match_member.optional() {
+            let conditions = conditions
+                .to_object()
+                .map_err(|_| format!("routes[{i}].match must be an object"))?;
+            for (name, pattern) in conditions {
+                let name = name
+                    .to_unquoted_string_str()
+                    .map_err(|e| e.to_string())?
+                    .into_owned();
+                let pattern = String::try_from(pattern)
+                    .map_err(|_| format!("routes[{i}].match.{name} must be a string"))?;
+                matchers.push((name, pattern));
+            }
+        }
+
+        routes.push(HookRoute {
+            source,
+            matchers,
+            channel,
+        });
+    }
+    Ok(routes)
+}
+
+/// 最初に一致したルールのチャンネルを返す。
+pub fn route<'a>(
+    routes: &'a [HookRoute],
+    source: HookSource,
+    fields: &[(String, String)],
+) -> Option<&'a str> {
+    routes
+        .iter()
+        .find(|route| route.matches(source, fields))
+        .map(|route| route.channel.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HookSource, parse_routes, route};
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn first_matching_route_wins() {
+        let routes = parse_routes(
+            r##"[
+                {"source": "alertmanager", "match": {"severity": "critical", "team": "db*"}, "channel": "#db-oncall"},
+                {"source": "alertmanager", "match": {"severity": "critical"}, "channel": "#oncall"},
+                {"source": "github", "match": {"repository": "ksera524/*"}, "channel": "#dev"},
+                {"source": "*", "channel": "#alerts"}
+            ]"##,
+        )
+        .expect("routes");
+
+        let critical_db = fields(&[("severity", "critical"), ("team", "dba")]);
+        assert_eq!(
+            route(&routes, HookSource::Alertmanager, &critical_db),
+            Some("#db-oncall")
+        );
+        let critical = fields(&[("severity", "critical")]);
+        assert_eq!(
+            route(&routes, HookSource::Alertmanager, &critical),
+            Some("#oncall")
+        );
+        assert_eq!(
+            route(&routes, HookSource::Grafana, &critical),
+            Some("#alerts")
+        );
+        let repo = fields(&[("repository", "ksera524/slack.rs")]);
+        assert_eq!(route(&routes, HookSource::Github, &repo), Some("#dev"));
+        assert_eq!(route(&routes[..3], HookSource::Grafana, &critical), None);
+    }
+
+    #[test]
+    fn invalid_routes_are_rejected() {
+        assert!(parse_routes("{}").is_err());
+        assert!(parse_routes(r##"[{"source": "jenkins", "channel": "#ci"}]"##).is_err());
+        assert!(parse_routes(r#"[{"source": "github"}]"#).is_err());
+        assert!(parse_routes(r##"[{"match": {"severity": 1}, "channel": "#a"}]"##).is_err());
+        assert_eq!(parse_routes("[]").expect("empty"), Vec::new());
+    }
+}
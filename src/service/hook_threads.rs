@@ -0,0 +1,116 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// 同じアラートや PR の通知をスレッドにまとめる期間
+pub const THREAD_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// 保持するスレッド数の上限。超えたら最も古いものから捨てる
+const MAX_THREADS: usize = 10_000;
+
+#[derive(Debug)]
+struct ThreadEntry {
+    channel: String,
+    ts: String,
+    updated_at: Instant,
+}
+
+/// `/hooks/*` の通知のまとめ先 (アラートの fingerprint や PR 番号 → 最初のメッセージの `ts`)。
+///
+/// プロセス内にだけ保持するため、再起動後の最初の通知は新しいスレッドになる。
+#[derive(Clone)]
+pub struct HookThreads {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, ThreadEntry>>>,
+}
+
+impl HookThreads {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// `channel` に投稿済みの `group_key` のスレッドの `ts`。送り先が変わった場合は `None`。
+    pub fn get(&self, group_key: &str, channel: &str, now: Instant) -> Option<String> {
+        let mut entries = self.lock_entries();
+        let entry = entries.get_mut(group_key)?;
+        if entry.channel != channel || now.saturating_duration_since(entry.updated_at) >= self.ttl {
+            return None;
+        }
+        entry.updated_at = now;
+        Some(entry.ts.clone())
+    }
+
+    pub fn insert(&self, group_key: &str, channel: &str, ts: &str, now: Instant) {
+        let mut entries = self.lock_entries();
+        if entries.len() >= MAX_THREADS && !entries.contains_key(group_key) {
+            let ttl = self.ttl;
+            entries.retain(|_, entry| now.saturating_duration_since(entry.updated_at) < ttl);
+            if entries.len() >= MAX_THREADS
+                && let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.updated_at)
+                    .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            group_key.to_string(),
+            ThreadEntry {
+                channel: channel.to_string(),
+                ts: ts.to_string(),
+                updated_at: now,
+            },
+        );
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, ThreadEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HookThreads;
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[test]
+    fn thread_is_reused_until_ttl_in_same_channel() {
+        let threads = HookThreads::new(Duration::from_secs(60));
+        let now = Instant::now();
+        threads.insert("alertmanager:abc", "#oncall", "1.000", now);
+
+        assert_eq!(
+            threads.get("alertmanager:abc", "#oncall", now + Duration::from_secs(59)),
+            Some("1.000".to_string())
+        );
+        assert_eq!(
+            threads.get("alertmanager:abc", "#alerts", now + Duration::from_secs(1)),
+            None
+        );
+        // 参照すると期限が延びる
+        assert_eq!(
+            threads.get(
+                "alertmanager:abc",
+                "#oncall",
+                now + Duration::from_secs(118)
+            ),
+            Some("1.000".to_string())
+        );
+        assert_eq!(
+            threads.get(
+                "alertmanager:abc",
+                "#oncall",
+                now + Duration::from_secs(200)
+            ),
+            None
+        );
+        assert_eq!(threads.get("alertmanager:other", "#oncall", now), None);
+    }
+}
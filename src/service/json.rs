@@ -0,0 +1,59 @@
+use crate::errors::api_error::ApiError;
+
+/// リクエストの JSON から必須の文字列を読む。読めない場合は `ApiError::BadRequest`。
+pub fn get_required_string(
+    root: nojson::RawJsonValue<'_, '_>,
+    name: &str,
+) -> Result<String, ApiError> {
+    root.to_member(name)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        .required()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        .try_into()
+        .map_err(|e| ApiError::BadRequest(format!("Invalid '{name}': {e}")))
+}
+
+pub fn optional_member<'text, 'raw>(
+    root: nojson::RawJsonValue<'text, 'raw>,
+    name: &str,
+) -> Result<Option<nojson::RawJsonValue<'text, 'raw>>, ApiError> {
+    let value = root
+        .to_member(name)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?
+        .optional();
+    Ok(value.filter(|v| !v.kind().is_null()))
+}
+
+pub fn get_optional_string(
+    root: nojson::RawJsonValue<'_, '_>,
+    name: &str,
+) -> Result<Option<String>, ApiError> {
+    optional_member(root, name)?
+        .map(|v| {
+            String::try_from(v).map_err(|e| ApiError::BadRequest(format!("Invalid '{name}': {e}")))
+        })
+        .transpose()
+}
+
+pub fn get_optional_bool(
+    root: nojson::RawJsonValue<'_, '_>,
+    name: &str,
+) -> Result<Option<bool>, ApiError> {
+    optional_member(root, name)?
+        .map(|v| {
+            bool::try_from(v).map_err(|e| ApiError::BadRequest(format!("Invalid '{name}': {e}")))
+        })
+        .transpose()
+}
+
+pub fn get_optional_json(
+    root: nojson::RawJsonValue<'_, '_>,
+    name: &str,
+) -> Result<Option<nojson::RawJsonOwned>, ApiError> {
+    optional_member(root, name)?
+        .map(|v| {
+            nojson::RawJsonOwned::try_from(v)
+                .map_err(|e| ApiError::BadRequest(format!("Invalid '{name}': {e}")))
+        })
+        .transpose()
+}
@@ -1,2 +1,19 @@
+pub mod file_type;
+pub mod hook_messages;
+pub mod hook_routes;
+pub mod hook_threads;
+pub mod json;
 pub mod s3_service;
+pub mod slack_archive;
+pub mod slack_channels;
+pub mod slack_commands;
+pub mod slack_events;
+pub mod slack_export;
+pub mod slack_interactions;
+pub mod slack_items;
+pub mod slack_markdown;
+pub mod slack_message;
+pub mod slack_rate_limiter;
+pub mod slack_schedule;
 pub mod slack_service;
+pub mod slack_template;
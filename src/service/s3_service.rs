@@ -1,13 +1,15 @@
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64_STANDARD};
 use shiguredo_s3::{
-    Credential, S3Client, S3Config, S3Request, S3Response,
-    types::{CompletedMultipartUpload, CompletedPart, HttpDate, ObjectIdentifier},
+    Credential, PresignedRequest, S3Client, S3Config, S3Request, S3Response,
+    types::{
+        CompletedMultipartUpload, CompletedPart, HttpDate, ListObjectsV2Output, ObjectIdentifier,
+    },
 };
 
 use crate::{
     config::settings::Settings,
     errors::api_error::ApiError,
-    http_client::{HttpClient, HttpRequest, HttpResponseStream},
+    http_client::{HttpClient, HttpClientError, HttpRequest, HttpResponseStream},
 };
 
 pub struct PutObjectInput {
@@ -15,6 +17,8 @@ pub struct PutObjectInput {
     pub key: String,
     pub body: Vec<u8>,
     pub content_type: Option<String>,
+    /// `x-amz-meta-*` として保存するユーザーメタデータ (名前は `x-amz-meta-` を除いたもの)
+    pub metadata: Vec<(String, String)>,
 }
 
 pub struct GetObjectInput {
@@ -22,6 +26,40 @@ pub struct GetObjectInput {
     pub key: String,
 }
 
+/// `read_object` で読み込んだオブジェクト
+pub struct FetchedObject {
+    pub body: Vec<u8>,
+    pub content_type: Option<String>,
+}
+
+/// `open_object` で本文を読み始める前のオブジェクト
+pub struct ObjectStream {
+    pub body: HttpResponseStream,
+    pub content_type: Option<String>,
+    pub content_length: Option<usize>,
+}
+
+impl ObjectStream {
+    /// 本文の先頭を最大 `len` バイト読む。本文がそれより短ければ全体を返す。
+    pub async fn read_prefix(&mut self, len: usize) -> Result<Vec<u8>, ApiError> {
+        let mut prefix = vec![0_u8; len];
+        let mut filled = 0;
+        while filled < len {
+            let n = self
+                .body
+                .read_chunk(&mut prefix[filled..])
+                .await
+                .map_err(map_http_client_error_to_api_error)?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        prefix.truncate(filled);
+        Ok(prefix)
+    }
+}
+
 pub struct ProxyObjectInput {
     pub bucket: String,
     pub key: String,
@@ -147,6 +185,9 @@ pub async fn put_object(
     if let Some(content_type) = input.content_type {
         req = req.content_type(content_type);
     }
+    for (name, value) in input.metadata {
+        req = req.metadata(name, value);
+    }
 
     let request = req
         .build_request()
@@ -184,7 +225,7 @@ pub async fn get_object(
         f.object(|f| {
             f.member("file_data_base64", BASE64_STANDARD.encode(&output.body))?;
             f.member("content_type", &output.content_type)?;
-            f.member("content_length", &output.content_length)?;
+            f.member("content_length", output.content_length)?;
             f.member("e_tag", &output.e_tag)?;
             f.member("last_modified", &output.last_modified)?;
             f.member("version_id", &output.version_id)?;
@@ -247,7 +288,108 @@ pub async fn get_object_proxy_stream(
             body: request.body,
         })
         .await
-        .map_err(|e| ApiError::InternalServerError(format!("S3 HTTP request failed: {e}")))
+        .map_err(map_http_client_error_to_api_error)
+}
+
+/// オブジェクトの GET を送り、本文を読まずに返す。`Content-Length` が `max_size` を超える場合は 413 を返す。
+pub async fn open_object(
+    http_client: &HttpClient,
+    settings: &Settings,
+    input: GetObjectInput,
+    max_size: usize,
+) -> Result<ObjectStream, ApiError> {
+    let location = format!("{}/{}", input.bucket, input.key);
+    let stream = get_object_proxy_stream(
+        http_client,
+        settings,
+        ProxyObjectStreamInput {
+            bucket: input.bucket,
+            key: input.key,
+            range: None,
+            if_match: None,
+            if_none_match: None,
+            if_modified_since: None,
+            if_unmodified_since: None,
+        },
+    )
+    .await?;
+
+    match stream.status_code {
+        200..=299 => {}
+        404 => {
+            return Err(ApiError::NotFound(format!(
+                "S3 object not found: {location}"
+            )));
+        }
+        403 => {
+            return Err(ApiError::Forbidden(format!(
+                "S3 denied access to {location}"
+            )));
+        }
+        status => {
+            return Err(ApiError::InternalServerError(format!(
+                "S3 GetObject for {location} failed with status {status}"
+            )));
+        }
+    }
+
+    let header = |name: &str| {
+        stream
+            .headers
+            .iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    };
+    let content_type = header("content-type");
+    let content_length = header("content-length").and_then(|value| value.parse::<usize>().ok());
+    if content_length.is_some_and(|length| length > max_size) {
+        return Err(too_large_object(&location, max_size));
+    }
+
+    Ok(ObjectStream {
+        body: stream,
+        content_type,
+        content_length,
+    })
+}
+
+/// オブジェクトを `max_size` バイトまで読み込む。`Content-Length` が上限を超える場合は本文を読まずに 413 を返す。
+pub async fn read_object(
+    http_client: &HttpClient,
+    settings: &Settings,
+    input: GetObjectInput,
+    max_size: usize,
+) -> Result<FetchedObject, ApiError> {
+    let location = format!("{}/{}", input.bucket, input.key);
+    let mut object = open_object(http_client, settings, input, max_size).await?;
+
+    let mut body = Vec::with_capacity(object.content_length.unwrap_or_default());
+    let mut chunk = vec![0_u8; 64 * 1024];
+    loop {
+        let n = object
+            .body
+            .read_chunk(&mut chunk)
+            .await
+            .map_err(map_http_client_error_to_api_error)?;
+        if n == 0 {
+            break;
+        }
+        if body.len() + n > max_size {
+            return Err(too_large_object(&location, max_size));
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+
+    Ok(FetchedObject {
+        body,
+        content_type: object.content_type,
+    })
+}
+
+fn too_large_object(location: &str, max_size: usize) -> ApiError {
+    ApiError::PayloadTooLarge(format!(
+        "S3 object {location} exceeds the transfer limit of {max_size} bytes"
+    ))
 }
 
 pub async fn head_object(
@@ -269,7 +411,7 @@ pub async fn head_object(
     Ok(nojson::json(|f| {
         f.object(|f| {
             f.member("content_type", &output.content_type)?;
-            f.member("content_length", &output.content_length)?;
+            f.member("content_length", output.content_length)?;
             f.member("e_tag", &output.e_tag)?;
             f.member("last_modified", &output.last_modified)?;
             f.member("version_id", &output.version_id)?;
@@ -297,7 +439,7 @@ pub async fn delete_object(
 
     Ok(nojson::json(|f| {
         f.object(|f| {
-            f.member("delete_marker", &output.delete_marker)?;
+            f.member("delete_marker", output.delete_marker)?;
             f.member("version_id", &output.version_id)
         })
     })
@@ -337,7 +479,7 @@ pub async fn delete_objects(
                         f.element(nojson::object(|f| {
                             f.member("key", &deleted.key)?;
                             f.member("version_id", &deleted.version_id)?;
-                            f.member("delete_marker", &deleted.delete_marker)?;
+                            f.member("delete_marker", deleted.delete_marker)?;
                             f.member(
                                 "delete_marker_version_id",
                                 &deleted.delete_marker_version_id,
@@ -370,39 +512,19 @@ pub async fn list_objects_v2(
     settings: &Settings,
     input: ListObjectsV2Input,
 ) -> Result<String, ApiError> {
-    let s3 = create_s3_client(settings)?;
-    let mut req = s3.list_objects_v2().bucket(input.bucket);
-    if let Some(prefix) = input.prefix {
-        req = req.prefix(prefix);
-    }
-    if let Some(delimiter) = input.delimiter {
-        req = req.delimiter(delimiter);
-    }
-    if let Some(max_keys) = input.max_keys {
-        req = req.max_keys(max_keys);
-    }
-    if let Some(start_after) = input.start_after {
-        req = req.start_after(start_after);
-    }
-
-    let request = req
-        .build_request()
-        .map_err(map_s3_input_error_to_api_error)?;
-    let response = execute_s3(http_client, request).await?;
-    let output = shiguredo_s3::api::ListObjectsV2FluentBuilder::parse_response(&response)
-        .map_err(map_s3_runtime_error_to_api_error)?;
+    let output = list_objects(http_client, settings, input).await?;
 
     let contents = output.contents.unwrap_or_default();
     let common_prefixes = output.common_prefixes.unwrap_or_default();
 
     Ok(nojson::json(|f| {
         f.object(|f| {
-            f.member("is_truncated", &output.is_truncated)?;
+            f.member("is_truncated", output.is_truncated)?;
             f.member("name", &output.name)?;
             f.member("prefix", &output.prefix)?;
             f.member("delimiter", &output.delimiter)?;
-            f.member("max_keys", &output.max_keys)?;
-            f.member("key_count", &output.key_count)?;
+            f.member("max_keys", output.max_keys)?;
+            f.member("key_count", output.key_count)?;
             f.member("continuation_token", &output.continuation_token)?;
             f.member("next_continuation_token", &output.next_continuation_token)?;
             f.member("start_after", &output.start_after)?;
@@ -435,6 +557,35 @@ pub async fn list_objects_v2(
     .to_string())
 }
 
+/// `list_objects_v2` の結果を JSON にせずそのまま返す。
+pub async fn list_objects(
+    http_client: &HttpClient,
+    settings: &Settings,
+    input: ListObjectsV2Input,
+) -> Result<ListObjectsV2Output, ApiError> {
+    let s3 = create_s3_client(settings)?;
+    let mut req = s3.list_objects_v2().bucket(input.bucket);
+    if let Some(prefix) = input.prefix {
+        req = req.prefix(prefix);
+    }
+    if let Some(delimiter) = input.delimiter {
+        req = req.delimiter(delimiter);
+    }
+    if let Some(max_keys) = input.max_keys {
+        req = req.max_keys(max_keys);
+    }
+    if let Some(start_after) = input.start_after {
+        req = req.start_after(start_after);
+    }
+
+    let request = req
+        .build_request()
+        .map_err(map_s3_input_error_to_api_error)?;
+    let response = execute_s3(http_client, request).await?;
+    shiguredo_s3::api::ListObjectsV2FluentBuilder::parse_response(&response)
+        .map_err(map_s3_runtime_error_to_api_error)
+}
+
 pub async fn create_multipart_upload(
     http_client: &HttpClient,
     settings: &Settings,
@@ -582,10 +733,10 @@ pub async fn list_parts(
             f.member("bucket", &output.bucket)?;
             f.member("key", &output.key)?;
             f.member("upload_id", &output.upload_id)?;
-            f.member("part_number_marker", &output.part_number_marker)?;
-            f.member("next_part_number_marker", &output.next_part_number_marker)?;
-            f.member("max_parts", &output.max_parts)?;
-            f.member("is_truncated", &output.is_truncated)?;
+            f.member("part_number_marker", output.part_number_marker)?;
+            f.member("next_part_number_marker", output.next_part_number_marker)?;
+            f.member("max_parts", output.max_parts)?;
+            f.member("is_truncated", output.is_truncated)?;
             f.member("storage_class", &output.storage_class)?;
             f.member(
                 "parts",
@@ -647,8 +798,8 @@ pub async fn list_multipart_uploads(
             f.member("next_upload_id_marker", &output.next_upload_id_marker)?;
             f.member("prefix", &output.prefix)?;
             f.member("delimiter", &output.delimiter)?;
-            f.member("max_uploads", &output.max_uploads)?;
-            f.member("is_truncated", &output.is_truncated)?;
+            f.member("max_uploads", output.max_uploads)?;
+            f.member("is_truncated", output.is_truncated)?;
             f.member(
                 "uploads",
                 nojson::array(|f| {
@@ -678,13 +829,7 @@ pub async fn list_multipart_uploads(
 }
 
 pub fn presigned_get(settings: &Settings, input: PresignedObjectInput) -> Result<String, ApiError> {
-    let s3 = create_s3_client(settings)?;
-    let output = s3
-        .get_object()
-        .bucket(input.bucket)
-        .key(input.key)
-        .presigned(input.expires_in_secs)
-        .map_err(map_s3_input_error_to_api_error)?;
+    let output = presign_get(settings, input)?;
 
     Ok(nojson::json(|f| {
         f.object(|f| {
@@ -707,6 +852,19 @@ pub fn presigned_get(settings: &Settings, input: PresignedObjectInput) -> Result
     .to_string())
 }
 
+/// GET の署名付きリクエストを JSON にせずそのまま返す。
+pub fn presign_get(
+    settings: &Settings,
+    input: PresignedObjectInput,
+) -> Result<PresignedRequest, ApiError> {
+    let s3 = create_s3_client(settings)?;
+    s3.get_object()
+        .bucket(input.bucket)
+        .key(input.key)
+        .presigned(input.expires_in_secs)
+        .map_err(map_s3_input_error_to_api_error)
+}
+
 pub fn presigned_put(settings: &Settings, input: PresignedObjectInput) -> Result<String, ApiError> {
     let s3 = create_s3_client(settings)?;
     let output = s3
@@ -876,7 +1034,7 @@ async fn execute_s3(http_client: &HttpClient, request: S3Request) -> Result<S3Re
             body: request.body,
         })
         .await
-        .map_err(|e| ApiError::InternalServerError(format!("S3 HTTP request failed: {e}")))?;
+        .map_err(map_http_client_error_to_api_error)?;
 
     Ok(S3Response {
         status_code: response.status_code,
@@ -885,6 +1043,15 @@ async fn execute_s3(http_client: &HttpClient, request: S3Request) -> Result<S3Re
     })
 }
 
+fn map_http_client_error_to_api_error(e: HttpClientError) -> ApiError {
+    match e {
+        HttpClientError::Timeout(_) => {
+            ApiError::GatewayTimeout(format!("S3 request timed out: {e}"))
+        }
+        e => ApiError::InternalServerError(format!("S3 HTTP request failed: {e}")),
+    }
+}
+
 fn build_s3_url(request: &S3Request) -> Result<String, ApiError> {
     let scheme = if request.https { "https" } else { "http" };
     let uri = if request.uri.starts_with('/') {
@@ -0,0 +1,111 @@
+use shiguredo_http11::uri::percent_encode_query;
+
+use crate::service::{slack_schedule, slack_service::SlackFile};
+
+/// 1回のリクエストでアーカイブするファイル数の上限
+pub const MAX_ARCHIVE_FILES: usize = 100;
+
+/// キーに使うチャンネル。絞り込みに使ったチャンネル、無ければ最初の共有先、どこにも共有されていなければ `unshared`。
+pub fn archive_channel(filter_channel: Option<&str>, file: &SlackFile) -> String {
+    filter_channel
+        .or(file.channels.first().map(String::as_str))
+        .unwrap_or("unshared")
+        .to_string()
+}
+
+/// `slack/{channel}/{date}/{file_id}-{name}`。`date` はファイルの作成日 (UTC)。
+pub fn archive_key(channel: &str, file: &SlackFile) -> String {
+    format!(
+        "slack/{}/{}/{}-{}",
+        sanitize_segment(channel),
+        slack_schedule::format_utc_date(file.created),
+        sanitize_segment(&file.id),
+        sanitize_segment(&file.name)
+    )
+}
+
+/// S3 のユーザーメタデータ。ヘッダーに載せるため、自由入力の値はパーセントエンコードする。
+pub fn archive_metadata(channel: &str, file: &SlackFile) -> Vec<(String, String)> {
+    let mut metadata = vec![
+        ("slack-file-id".to_string(), file.id.clone()),
+        ("slack-channel".to_string(), channel.to_string()),
+        ("slack-created".to_string(), file.created.to_string()),
+        ("slack-name".to_string(), percent_encode_query(&file.name)),
+    ];
+    let optional = [
+        (
+            "slack-title",
+            file.title.as_deref().map(percent_encode_query),
+        ),
+        ("slack-user", file.user.clone()),
+        ("slack-mimetype", file.mimetype.clone()),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value {
+            metadata.push((name.to_string(), value));
+        }
+    }
+    metadata
+}
+
+/// `/` と制御文字を `_` に置き換える。空なら `file`。
+fn sanitize_segment(value: &str) -> String {
+    let sanitized: String = value
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    if sanitized.is_empty() || sanitized == "." || sanitized == ".." {
+        "file".to_string()
+    } else {
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{archive_channel, archive_key, archive_metadata};
+    use crate::service::slack_service::SlackFile;
+
+    fn file() -> SlackFile {
+        SlackFile {
+            id: "F123".to_string(),
+            name: "週報/final.pdf".to_string(),
+            title: Some("週報".to_string()),
+            mimetype: Some("application/pdf".to_string()),
+            user: Some("U1".to_string()),
+            created: 1_792_195_200,
+            size: Some(42),
+            channels: vec!["C9".to_string()],
+            url_private_download: None,
+        }
+    }
+
+    #[test]
+    fn key_is_deterministic_and_path_safe() {
+        let file = file();
+        assert_eq!(archive_channel(None, &file), "C9");
+        assert_eq!(archive_channel(Some("C1"), &file), "C1");
+        assert_eq!(
+            archive_key("C9", &file),
+            "slack/C9/2026-10-17/F123-週報_final.pdf"
+        );
+    }
+
+    #[test]
+    fn metadata_values_are_header_safe() {
+        let metadata = archive_metadata("C9", &file());
+        assert!(
+            metadata
+                .iter()
+                .all(|(_, value)| value.bytes().all(|b| b.is_ascii_graphic()))
+        );
+        assert!(metadata.contains(&("slack-file-id".to_string(), "F123".to_string())));
+        assert!(metadata.contains(&("slack-user".to_string(), "U1".to_string())));
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// 未知の名前で `conversations.list` を取り直すまでの最短間隔。typo で Tier 2 を使い切らないようにする。
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// キャッシュを引いた結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelLookup {
+    Found(String),
+    /// 直近に取得した一覧に無い
+    NotFound,
+    /// 一覧が無い・期限切れ、または未知の名前で取り直してよい
+    Refresh,
+}
+
+#[derive(Debug)]
+struct Snapshot {
+    fetched_at: Instant,
+    ids_by_name: HashMap<String, String>,
+}
+
+/// `conversations.list` から作るチャンネル名→ID の対応表。`ttl` を過ぎると取り直す。
+#[derive(Clone)]
+pub struct ChannelDirectory {
+    ttl: Duration,
+    snapshot: Arc<Mutex<Option<Snapshot>>>,
+    refresh: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl ChannelDirectory {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            snapshot: Arc::new(Mutex::new(None)),
+            refresh: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// `#` を除いた小文字のチャンネル名を引く。
+    pub fn lookup(&self, name: &str, now: Instant) -> ChannelLookup {
+        let snapshot = self.lock_snapshot();
+        let Some(snapshot) = snapshot.as_ref() else {
+            return ChannelLookup::Refresh;
+        };
+        let age = now.saturating_duration_since(snapshot.fetched_at);
+        if age >= self.ttl {
+            return ChannelLookup::Refresh;
+        }
+        match snapshot.ids_by_name.get(name) {
+            Some(id) => ChannelLookup::Found(id.clone()),
+            None if age < MIN_REFRESH_INTERVAL => ChannelLookup::NotFound,
+            None => ChannelLookup::Refresh,
+        }
+    }
+
+    /// `since` 以降に一覧が取り直されていれば true
+    pub fn refreshed_since(&self, since: Instant) -> bool {
+        self.lock_snapshot()
+            .as_ref()
+            .is_some_and(|snapshot| snapshot.fetched_at >= since)
+    }
+
+    pub fn replace(&self, ids_by_name: HashMap<String, String>, now: Instant) {
+        *self.lock_snapshot() = Some(Snapshot {
+            fetched_at: now,
+            ids_by_name,
+        });
+    }
+
+    /// 一覧の取得を1リクエストずつに絞るためのロック
+    pub async fn lock_refresh(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.refresh.lock().await
+    }
+
+    fn lock_snapshot(&self) -> std::sync::MutexGuard<'_, Option<Snapshot>> {
+        self.snapshot.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// `#deploys` / `deploys` / `#Deploys` を同じ名前として扱う。
+pub fn normalize_channel_name(name: &str) -> String {
+    name.trim_start_matches('#').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChannelDirectory, ChannelLookup, normalize_channel_name};
+    use std::{collections::HashMap, time::Duration};
+    use tokio::time::Instant;
+
+    fn directory(now: Instant) -> ChannelDirectory {
+        let directory = ChannelDirectory::new(Duration::from_secs(300));
+        directory.replace(
+            HashMap::from([("deploys".to_string(), "C123".to_string())]),
+            now,
+        );
+        directory
+    }
+
+    #[test]
+    fn lookup_expires_after_ttl() {
+        let now = Instant::now();
+        let directory = directory(now);
+        assert_eq!(
+            directory.lookup("deploys", now + Duration::from_secs(299)),
+            ChannelLookup::Found("C123".to_string())
+        );
+        assert_eq!(
+            directory.lookup("deploys", now + Duration::from_secs(300)),
+            ChannelLookup::Refresh
+        );
+    }
+
+    #[test]
+    fn unknown_name_refreshes_only_after_min_interval() {
+        let now = Instant::now();
+        let directory = directory(now);
+        assert_eq!(
+            directory.lookup("alerts", now + Duration::from_secs(5)),
+            ChannelLookup::NotFound
+        );
+        assert_eq!(
+            directory.lookup("alerts", now + Duration::from_secs(31)),
+            ChannelLookup::Refresh
+        );
+    }
+
+    #[test]
+    fn empty_directory_needs_refresh() {
+        let directory = ChannelDirectory::new(Duration::from_secs(300));
+        let now = Instant::now();
+        assert_eq!(directory.lookup("deploys", now), ChannelLookup::Refresh);
+        assert!(!directory.refreshed_since(now));
+    }
+
+    #[test]
+    fn names_are_normalized() {
+        assert_eq!(normalize_channel_name("#Deploys"), "deploys");
+        assert_eq!(normalize_channel_name("deploys"), "deploys");
+    }
+}
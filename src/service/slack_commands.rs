@@ -0,0 +1,226 @@
+use shiguredo_s3::types::ListObjectsV2Output;
+
+use crate::{
+    auth::{CallerIdentity, scope::RequiredScope},
+    config::state::AppState,
+    errors::api_error::ApiError,
+    service::{
+        s3_service::{self, ListObjectsV2Input, PresignedObjectInput},
+        slack_interactions::{
+            BoxFuture, Reply, ReplyMessage, SlackHandlerRegistry, SlashCommand, SlashCommandHandler,
+        },
+    },
+};
+
+/// `/s3ls` で表示するキーの上限
+const S3LS_MAX_KEYS: i32 = 20;
+/// `/presign` の既定の有効期限 (`/s3/presigned_get_object` と同じ 15 分)
+const PRESIGN_DEFAULT_EXPIRES_SECS: u64 = 900;
+/// `/presign` で指定できる有効期限の上限 (SigV4 の上限の 7 日)
+const PRESIGN_MAX_EXPIRES_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// 組み込みのスラッシュコマンドを登録した表
+pub fn builtin_registry() -> SlackHandlerRegistry {
+    SlackHandlerRegistry::new()
+        .with_command("/s3ls", S3LsCommand)
+        .with_command("/presign", PresignCommand)
+}
+
+/// `/s3ls bucket[/prefix]`: プレフィックス直下のキーを一覧する。
+pub struct S3LsCommand;
+
+impl SlashCommandHandler for S3LsCommand {
+    fn handle<'a>(
+        &'a self,
+        app_state: &'a AppState,
+        caller: &'a CallerIdentity,
+        command: &'a SlashCommand,
+    ) -> BoxFuture<'a, Result<Reply, ApiError>> {
+        Box::pin(async move {
+            let Some((bucket, prefix)) = split_bucket_path(&command.text) else {
+                return Ok(Reply::Now(ReplyMessage::ephemeral(
+                    "Usage: /s3ls bucket[/prefix]",
+                )));
+            };
+            caller.require(RequiredScope::S3Read {
+                bucket: bucket.clone(),
+                key: prefix.clone(),
+            })?;
+
+            let app_state = app_state.clone();
+            Ok(Reply::Later(Box::pin(async move {
+                let listed = s3_service::list_objects(
+                    &app_state.client,
+                    &app_state.settings,
+                    ListObjectsV2Input {
+                        bucket: bucket.clone(),
+                        prefix: prefix.clone(),
+                        delimiter: Some("/".to_string()),
+                        max_keys: Some(S3LS_MAX_KEYS),
+                        start_after: None,
+                    },
+                )
+                .await;
+                let location = format!("s3://{bucket}/{}", prefix.unwrap_or_default());
+                match listed {
+                    Ok(output) => ReplyMessage::ephemeral(format_listing(&location, &output)),
+                    Err(e) => ReplyMessage::ephemeral(format!("Failed to list {location}: {e}")),
+                }
+            })))
+        })
+    }
+}
+
+/// `/presign bucket/key [expires_secs]`: 署名付き GET URL を実行したユーザーにだけ返す。
+pub struct PresignCommand;
+
+impl SlashCommandHandler for PresignCommand {
+    fn handle<'a>(
+        &'a self,
+        app_state: &'a AppState,
+        caller: &'a CallerIdentity,
+        command: &'a SlashCommand,
+    ) -> BoxFuture<'a, Result<Reply, ApiError>> {
+        Box::pin(async move {
+            let Some((bucket, key, expires_in_secs)) = parse_presign_args(&command.text) else {
+                return Ok(Reply::Now(ReplyMessage::ephemeral(format!(
+                    "Usage: /presign bucket/key [expires_secs (1-{PRESIGN_MAX_EXPIRES_SECS})]"
+                ))));
+            };
+            caller.require(RequiredScope::S3Read {
+                bucket: bucket.clone(),
+                key: Some(key.clone()),
+            })?;
+
+            let presigned = s3_service::presign_get(
+                &app_state.settings,
+                PresignedObjectInput {
+                    bucket,
+                    key,
+                    expires_in_secs,
+                },
+            )?;
+            Ok(Reply::Now(ReplyMessage::ephemeral(format!(
+                "{} (expires in {expires_in_secs}s)",
+                presigned.url
+            ))))
+        })
+    }
+}
+
+/// `bucket/prefix` をバケットとプレフィックスに分ける。プレフィックスが空なら `None`。
+fn split_bucket_path(text: &str) -> Option<(String, Option<String>)> {
+    let path = text.split_whitespace().next()?.trim_start_matches("s3://");
+    let (bucket, prefix) = path.split_once('/').unwrap_or((path, ""));
+    if bucket.is_empty() {
+        return None;
+    }
+    Some((
+        bucket.to_string(),
+        Some(prefix.to_string()).filter(|prefix| !prefix.is_empty()),
+    ))
+}
+
+fn parse_presign_args(text: &str) -> Option<(String, String, u64)> {
+    let mut args = text.split_whitespace();
+    let (bucket, key) = split_bucket_path(args.next()?)?;
+    let expires_in_secs = match args.next() {
+        Some(raw) => raw
+            .parse()
+            .ok()
+            .filter(|secs| (1..=PRESIGN_MAX_EXPIRES_SECS).contains(secs))?,
+        None => PRESIGN_DEFAULT_EXPIRES_SECS,
+    };
+    if args.next().is_some() {
+        return None;
+    }
+    Some((bucket, key?, expires_in_secs))
+}
+
+fn format_listing(location: &str, output: &ListObjectsV2Output) -> String {
+    let prefixes = output
+        .common_prefixes
+        .iter()
+        .flatten()
+        .filter_map(|prefix| prefix.prefix.as_deref())
+        .map(ToString::to_string);
+    let objects = output.contents.iter().flatten().filter_map(|object| {
+        let key = object.key.as_deref()?;
+        Some(format!("{key}  {} bytes", object.size.unwrap_or_default()))
+    });
+    let lines = prefixes.chain(objects).collect::<Vec<_>>();
+
+    if lines.is_empty() {
+        return format!("No objects under {location}");
+    }
+    let mut text = format!("{location}\n```\n{}\n```", lines.join("\n"));
+    if output.is_truncated.unwrap_or(false) {
+        text.push_str(&format!("\n(showing the first {S3LS_MAX_KEYS} entries)"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_listing, parse_presign_args, split_bucket_path};
+    use shiguredo_s3::types::{CommonPrefix, ListObjectsV2Output, Object};
+
+    #[test]
+    fn bucket_path_is_split() {
+        assert_eq!(
+            split_bucket_path("reports/daily/ extra"),
+            Some(("reports".to_string(), Some("daily/".to_string())))
+        );
+        assert_eq!(
+            split_bucket_path("s3://reports"),
+            Some(("reports".to_string(), None))
+        );
+        assert_eq!(split_bucket_path(""), None);
+        assert_eq!(split_bucket_path("/daily"), None);
+    }
+
+    #[test]
+    fn presign_args_require_key_and_bounded_expiry() {
+        assert_eq!(
+            parse_presign_args("reports/a.pdf"),
+            Some(("reports".to_string(), "a.pdf".to_string(), 900))
+        );
+        assert_eq!(
+            parse_presign_args("reports/a.pdf 60"),
+            Some(("reports".to_string(), "a.pdf".to_string(), 60))
+        );
+        assert_eq!(parse_presign_args("reports"), None);
+        assert_eq!(parse_presign_args("reports/a.pdf 0"), None);
+        assert_eq!(parse_presign_args("reports/a.pdf 604801"), None);
+        assert_eq!(parse_presign_args("reports/a.pdf 60 x"), None);
+    }
+
+    #[test]
+    fn listing_shows_prefixes_then_objects() {
+        let output = ListObjectsV2Output {
+            is_truncated: Some(true),
+            contents: Some(vec![Object {
+                key: Some("daily/a.pdf".to_string()),
+                last_modified: None,
+                e_tag: None,
+                size: Some(1024),
+                storage_class: None,
+            }]),
+            name: Some("reports".to_string()),
+            prefix: Some("daily/".to_string()),
+            delimiter: Some("/".to_string()),
+            max_keys: Some(20),
+            common_prefixes: Some(vec![CommonPrefix {
+                prefix: Some("daily/2026/".to_string()),
+            }]),
+            key_count: Some(2),
+            continuation_token: None,
+            next_continuation_token: None,
+            start_after: None,
+        };
+        assert_eq!(
+            format_listing("s3://reports/daily/", &output),
+            "s3://reports/daily/\n```\ndaily/2026/\ndaily/a.pdf  1024 bytes\n```\n(showing the first 20 entries)"
+        );
+    }
+}
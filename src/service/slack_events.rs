@@ -0,0 +1,280 @@
+use tracing::{info, warn};
+
+use crate::{
+    config::settings::Settings,
+    errors::api_error::ApiError,
+    http_client::{HttpClient, HttpRequest},
+    service::{
+        json::{get_optional_string, get_required_string, optional_member},
+        s3_service::{self, PutObjectInput},
+        slack_schedule,
+    },
+};
+
+/// `s3:<bucket>` でプレフィックスを省略した場合のキーの先頭
+const DEFAULT_S3_PREFIX: &str = "slack/events";
+
+/// `event_callback` の転送先。`SLACK_EVENT_SINKS` で指定する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventSink {
+    /// `log`: イベントの種類と ID をログに出す
+    Log,
+    /// `http:<url>`: 受け取った本文をそのまま POST する
+    Http(String),
+    /// `s3:<bucket>[/<prefix>]`: `{prefix}/{YYYY-MM-DD}/{event_id}.json` に保存する
+    S3 { bucket: String, prefix: String },
+}
+
+impl EventSink {
+    /// `log,http:https://example.com/hook,s3:bucket/prefix` のようなカンマ区切りの一覧を読む。
+    pub fn parse_list(raw: &str) -> Result<Vec<Self>, String> {
+        raw.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    fn parse(entry: &str) -> Result<Self, String> {
+        match entry.split_once(':') {
+            None if entry == "log" => Ok(Self::Log),
+            Some(("http", url)) if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(Self::Http(url.to_string()))
+            }
+            Some(("s3", target)) => {
+                let (bucket, prefix) = target.split_once('/').unwrap_or((target, ""));
+                let prefix = prefix.trim_matches('/');
+                if bucket.is_empty() {
+                    return Err(format!("sink '{entry}' has an empty bucket"));
+                }
+                Ok(Self::S3 {
+                    bucket: bucket.to_string(),
+                    prefix: if prefix.is_empty() {
+                        DEFAULT_S3_PREFIX.to_string()
+                    } else {
+                        prefix.to_string()
+                    },
+                })
+            }
+            _ => Err(format!(
+                "unknown sink '{entry}' (expected log, http:<url> or s3:<bucket>[/<prefix>])"
+            )),
+        }
+    }
+}
+
+/// Events API のリクエスト本文
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlackEnvelope {
+    /// Request URL の登録時に届く確認。`challenge` をそのまま返す。
+    UrlVerification {
+        challenge: String,
+    },
+    EventCallback(EventCallback),
+    /// `app_rate_limited` など、転送しない通知
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventCallback {
+    pub event_id: String,
+    pub event_type: String,
+    pub team_id: Option<String>,
+    pub event_time: i64,
+}
+
+pub fn parse_envelope(body: &str) -> Result<SlackEnvelope, ApiError> {
+    let json = nojson::RawJson::parse(body)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid JSON: {e}")))?;
+    let root = json.value();
+
+    let envelope_type = get_required_string(root, "type")?;
+    match envelope_type.as_str() {
+        "url_verification" => Ok(SlackEnvelope::UrlVerification {
+            challenge: get_required_string(root, "challenge")?,
+        }),
+        "event_callback" => {
+            let event = root
+                .to_member("event")
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?
+                .required()
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+            let team_id = get_optional_string(root, "team_id")?;
+            let event_time = optional_member(root, "event_time")?
+                .map(|v| {
+                    i64::try_from(v)
+                        .map_err(|e| ApiError::BadRequest(format!("Invalid 'event_time': {e}")))
+                })
+                .transpose()?
+                .unwrap_or_else(slack_schedule::unix_now);
+            Ok(SlackEnvelope::EventCallback(EventCallback {
+                event_id: get_required_string(root, "event_id")?,
+                event_type: get_required_string(event, "type")?,
+                team_id,
+                event_time,
+            }))
+        }
+        _ => Ok(SlackEnvelope::Other(envelope_type)),
+    }
+}
+
+/// `{prefix}/{YYYY-MM-DD}/{event_id}.json`。日付はイベントの発生日 (UTC)。
+pub fn event_key(prefix: &str, event: &EventCallback) -> String {
+    let event_id = event
+        .event_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    format!(
+        "{prefix}/{}/{event_id}.json",
+        slack_schedule::format_utc_date(event.event_time)
+    )
+}
+
+/// 設定されたすべての転送先にイベントを渡す。転送先ごとの失敗はログに出して続行する。
+pub async fn dispatch(
+    http_client: &HttpClient,
+    settings: &Settings,
+    event: &EventCallback,
+    body: &[u8],
+) {
+    for sink in &settings.slack_event_sinks {
+        if let Err(e) = deliver(http_client, settings, sink, event, body).await {
+            warn!(
+                error = %e,
+                sink = ?sink,
+                event_id = %event.event_id,
+                event_type = %event.event_type,
+                "Failed to deliver Slack event"
+            );
+        }
+    }
+}
+
+async fn deliver(
+    http_client: &HttpClient,
+    settings: &Settings,
+    sink: &EventSink,
+    event: &EventCallback,
+    body: &[u8],
+) -> Result<(), String> {
+    match sink {
+        EventSink::Log => {
+            info!(
+                event_id = %event.event_id,
+                event_type = %event.event_type,
+                team_id = ?event.team_id,
+                event_time = event.event_time,
+                "Received Slack event"
+            );
+            Ok(())
+        }
+        EventSink::Http(url) => {
+            let response = http_client
+                .send(HttpRequest {
+                    method: "POST".to_string(),
+                    url: url.clone(),
+                    headers: vec![(
+                        "Content-Type".to_string(),
+                        "application/json; charset=utf-8".to_string(),
+                    )],
+                    body: body.to_vec(),
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            if !(200..300).contains(&response.status_code) {
+                return Err(format!("Forward target returned {}", response.status_code));
+            }
+            Ok(())
+        }
+        EventSink::S3 { bucket, prefix } => s3_service::put_object(
+            http_client,
+            settings,
+            PutObjectInput {
+                bucket: bucket.clone(),
+                key: event_key(prefix, event),
+                body: body.to_vec(),
+                content_type: Some("application/json".to_string()),
+                metadata: Vec::new(),
+            },
+        )
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EventCallback, EventSink, SlackEnvelope, event_key, parse_envelope};
+
+    #[test]
+    fn sinks_are_parsed_from_a_list() {
+        assert_eq!(
+            EventSink::parse_list("log, http:https://example.com/hook, s3:archive, s3:logs/slack/")
+                .expect("sinks"),
+            vec![
+                EventSink::Log,
+                EventSink::Http("https://example.com/hook".to_string()),
+                EventSink::S3 {
+                    bucket: "archive".to_string(),
+                    prefix: "slack/events".to_string()
+                },
+                EventSink::S3 {
+                    bucket: "logs".to_string(),
+                    prefix: "slack".to_string()
+                },
+            ]
+        );
+        assert!(EventSink::parse_list("http:ftp://example.com").is_err());
+        assert!(EventSink::parse_list("s3:").is_err());
+        assert!(EventSink::parse_list("stdout").is_err());
+    }
+
+    #[test]
+    fn envelopes_are_classified() {
+        assert_eq!(
+            parse_envelope(r#"{"type": "url_verification", "challenge": "abc"}"#).expect("url"),
+            SlackEnvelope::UrlVerification {
+                challenge: "abc".to_string()
+            }
+        );
+        assert_eq!(
+            parse_envelope(
+                r#"{"type": "event_callback", "team_id": "T1", "event_id": "Ev1", "event_time": 1792195200, "event": {"type": "app_mention"}}"#
+            )
+            .expect("callback"),
+            SlackEnvelope::EventCallback(EventCallback {
+                event_id: "Ev1".to_string(),
+                event_type: "app_mention".to_string(),
+                team_id: Some("T1".to_string()),
+                event_time: 1_792_195_200,
+            })
+        );
+        assert_eq!(
+            parse_envelope(r#"{"type": "app_rate_limited"}"#).expect("other"),
+            SlackEnvelope::Other("app_rate_limited".to_string())
+        );
+        assert!(parse_envelope(r#"{"type": "event_callback", "event_id": "Ev1"}"#).is_err());
+    }
+
+    #[test]
+    fn event_key_uses_event_date() {
+        let event = EventCallback {
+            event_id: "Ev1/..".to_string(),
+            event_type: "message".to_string(),
+            team_id: None,
+            event_time: 1_792_195_200,
+        };
+        assert_eq!(
+            event_key("slack/events", &event),
+            "slack/events/2026-10-17/Ev1___.json"
+        );
+    }
+}
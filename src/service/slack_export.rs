@@ -0,0 +1,645 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::{
+    config::settings::Settings,
+    errors::api_error::ApiError,
+    http_client::HttpClient,
+    service::{
+        s3_service::{
+            self, AbortMultipartUploadInput, CompleteMultipartUploadInput, CompletePartInput,
+            CreateMultipartUploadInput, PutObjectInput, UploadPartInput,
+        },
+        slack_items::{self, Reaction},
+        slack_schedule,
+    },
+};
+
+/// 1回のエクスポートで書き出すメッセージ数の上限。超えた分は `truncated` として打ち切る。
+pub const MAX_EXPORT_MESSAGES: usize = 100_000;
+/// `conversations.history` / `conversations.replies` の1ページの件数
+pub const HISTORY_PAGE_LIMIT: u32 = 200;
+/// マルチパートアップロードの1パートの大きさ。これを超えたらマルチパートに切り替える。
+pub const EXPORT_PART_BYTES: usize = 8 * 1024 * 1024;
+
+/// 終わったエクスポートジョブの状態を返す期間
+pub const EXPORT_JOB_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+/// 保持するジョブ数の上限。超えたら最も古いものから捨てる
+const MAX_EXPORT_JOBS: usize = 1_000;
+
+const JSONL_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// `conversations.history` の取得範囲 (Unix 秒)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryRange {
+    pub oldest: Option<i64>,
+    pub latest: Option<i64>,
+}
+
+/// メッセージに添付されたファイル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedFile {
+    pub id: String,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub mimetype: Option<String>,
+    pub size: Option<i64>,
+    pub url_private: Option<String>,
+}
+
+/// JSONL の1行になるメッセージ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedMessage {
+    pub ts: String,
+    pub thread_ts: Option<String>,
+    pub user: Option<String>,
+    pub bot_id: Option<String>,
+    pub username: Option<String>,
+    pub subtype: Option<String>,
+    pub text: String,
+    pub reply_count: Option<i64>,
+    pub files: Vec<ExportedFile>,
+    pub reactions: Vec<Reaction>,
+}
+
+impl ExportedMessage {
+    /// `conversations.history` / `conversations.replies` の `messages` の要素を読む。
+    pub fn parse(item: nojson::RawJsonValue<'_, '_>) -> Result<Self, nojson::JsonParseError> {
+        let mut files = Vec::new();
+        if let Some(items) = item.to_member("files")?.optional() {
+            for file in items.to_array()? {
+                files.push(ExportedFile {
+                    id: String::try_from(file.to_member("id")?.required()?)?,
+                    name: optional_string(file, "name")?,
+                    title: optional_string(file, "title")?,
+                    mimetype: optional_string(file, "mimetype")?,
+                    size: file
+                        .to_member("size")?
+                        .optional()
+                        .map(i64::try_from)
+                        .transpose()?,
+                    url_private: optional_string(file, "url_private")?,
+                });
+            }
+        }
+        Ok(Self {
+            ts: String::try_from(item.to_member("ts")?.required()?)?,
+            thread_ts: optional_string(item, "thread_ts")?,
+            user: optional_string(item, "user")?,
+            bot_id: optional_string(item, "bot_id")?,
+            username: optional_string(item, "username")?,
+            subtype: optional_string(item, "subtype")?,
+            text: optional_string(item, "text")?.unwrap_or_default(),
+            reply_count: item
+                .to_member("reply_count")?
+                .optional()
+                .map(i64::try_from)
+                .transpose()?,
+            files,
+            reactions: slack_items::parse_reactions(item)?,
+        })
+    }
+
+    /// 投稿者とリアクションしたユーザーの ID
+    pub fn user_ids(&self) -> impl Iterator<Item = &str> {
+        self.user.as_deref().into_iter().chain(
+            self.reactions
+                .iter()
+                .flat_map(|reaction| reaction.users.iter().map(String::as_str)),
+        )
+    }
+
+    /// JSONL の1行 (改行を含まない)。`names` で引けたユーザー ID には `user_name` / `user_names` を付ける。
+    pub fn to_json_line(&self, names: &HashMap<String, String>) -> String {
+        nojson::json(|f| {
+            f.object(|f| {
+                f.member("ts", &self.ts)?;
+                if let Some(thread_ts) = &self.thread_ts {
+                    f.member("thread_ts", thread_ts)?;
+                }
+                if let Some(user) = &self.user {
+                    f.member("user", user)?;
+                    if let Some(name) = names.get(user) {
+                        f.member("user_name", name)?;
+                    }
+                }
+                if let Some(bot_id) = &self.bot_id {
+                    f.member("bot_id", bot_id)?;
+                }
+                if let Some(username) = &self.username {
+                    f.member("username", username)?;
+                }
+                if let Some(subtype) = &self.subtype {
+                    f.member("subtype", subtype)?;
+                }
+                f.member("text", &self.text)?;
+                if let Some(reply_count) = self.reply_count {
+                    f.member("reply_count", reply_count)?;
+                }
+                f.member(
+                    "files",
+                    nojson::array(|f| {
+                        for file in &self.files {
+                            f.element(nojson::object(|f| {
+                                f.member("id", &file.id)?;
+                                f.member("name", &file.name)?;
+                                f.member("title", &file.title)?;
+                                f.member("mimetype", &file.mimetype)?;
+                                f.member("size", file.size)?;
+                                f.member("url_private", &file.url_private)
+                            }))?;
+                        }
+                        Ok(())
+                    }),
+                )?;
+                f.member(
+                    "reactions",
+                    nojson::array(|f| {
+                        for reaction in &self.reactions {
+                            f.element(nojson::object(|f| {
+                                f.member("name", &reaction.name)?;
+                                f.member("count", reaction.count)?;
+                                f.member("users", &reaction.users)?;
+                                f.member(
+                                    "user_names",
+                                    nojson::array(|f| {
+                                        for user in &reaction.users {
+                                            f.element(names.get(user))?;
+                                        }
+                                        Ok(())
+                                    }),
+                                )
+                            }))?;
+                        }
+                        Ok(())
+                    }),
+                )
+            })
+        })
+        .to_string()
+    }
+}
+
+fn optional_string(
+    value: nojson::RawJsonValue<'_, '_>,
+    name: &str,
+) -> Result<Option<String>, nojson::JsonParseError> {
+    value
+        .to_member(name)?
+        .optional()
+        .map(String::try_from)
+        .transpose()
+}
+
+/// 名前を引く必要のあるユーザー ID (重複なし・順序固定)
+pub fn collect_user_ids(messages: &[ExportedMessage]) -> BTreeSet<String> {
+    messages
+        .iter()
+        .flat_map(ExportedMessage::user_ids)
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// 古い順に並べ、スレッドの返信を重ねて取得した場合の重複を除く。
+pub fn sort_messages(messages: &mut Vec<ExportedMessage>) {
+    messages.sort_by(|a, b| compare_ts(&a.ts, &b.ts));
+    messages.dedup_by(|a, b| a.ts == b.ts);
+}
+
+/// `ts` は `秒.マイクロ秒` なので、秒の桁数が違っても数値として比べる。
+fn compare_ts(a: &str, b: &str) -> std::cmp::Ordering {
+    let split = |ts: &str| {
+        let (secs, micros) = ts.split_once('.').unwrap_or((ts, ""));
+        (secs.len(), secs.to_string(), micros.to_string())
+    };
+    split(a).cmp(&split(b))
+}
+
+/// `slack/{channel}/exports/{date}/{history|thread-<ts>}-{exported_at}.jsonl`。`date` はエクスポートした日 (UTC)。
+pub fn export_key(channel: &str, thread_ts: Option<&str>, exported_at: i64) -> String {
+    let target = match thread_ts {
+        Some(ts) => format!("thread-{ts}"),
+        None => "history".to_string(),
+    };
+    format!(
+        "slack/{channel}/exports/{}/{target}-{exported_at}.jsonl",
+        slack_schedule::format_utc_date(exported_at)
+    )
+}
+
+/// Slack API に渡す `oldest` / `latest` の形式
+pub fn slack_ts(secs: i64) -> String {
+    format!("{secs}.000000")
+}
+
+/// JSONL を S3 に書き出す。
+///
+/// `EXPORT_PART_BYTES` までは手元に溜めて最後に `put_object` し、超えたらマルチパートアップロードに切り替える。
+pub struct JsonlUpload<'a> {
+    client: &'a HttpClient,
+    settings: &'a Settings,
+    bucket: String,
+    key: String,
+    buffer: Vec<u8>,
+    upload_id: Option<String>,
+    parts: Vec<CompletePartInput>,
+    bytes: usize,
+}
+
+/// 書き出した結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedExport {
+    pub bytes: usize,
+    /// マルチパートアップロードのパート数。`put_object` で書いた場合は 0
+    pub parts: usize,
+}
+
+impl<'a> JsonlUpload<'a> {
+    pub fn new(client: &'a HttpClient, settings: &'a Settings, bucket: &str, key: &str) -> Self {
+        Self {
+            client,
+            settings,
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            buffer: Vec::new(),
+            upload_id: None,
+            parts: Vec::new(),
+            bytes: 0,
+        }
+    }
+
+    pub async fn write_line(&mut self, line: &str) -> Result<(), ApiError> {
+        self.buffer.extend_from_slice(line.as_bytes());
+        self.buffer.push(b'\n');
+        self.bytes += line.len() + 1;
+        if self.buffer.len() >= EXPORT_PART_BYTES {
+            self.flush_part().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush_part(&mut self) -> Result<(), ApiError> {
+        let upload_id = match &self.upload_id {
+            Some(upload_id) => upload_id.clone(),
+            None => {
+                let created = s3_service::create_multipart_upload(
+                    self.client,
+                    self.settings,
+                    CreateMultipartUploadInput {
+                        bucket: self.bucket.clone(),
+                        key: self.key.clone(),
+                        content_type: Some(JSONL_CONTENT_TYPE.to_string()),
+                    },
+                )
+                .await?;
+                let upload_id = response_string(&created, "upload_id")?;
+                self.upload_id = Some(upload_id.clone());
+                upload_id
+            }
+        };
+        let part_number = self.parts.len() as i32 + 1;
+        let uploaded = s3_service::upload_part(
+            self.client,
+            self.settings,
+            UploadPartInput {
+                bucket: self.bucket.clone(),
+                key: self.key.clone(),
+                upload_id,
+                part_number,
+                body: std::mem::take(&mut self.buffer),
+            },
+        )
+        .await?;
+        self.parts.push(CompletePartInput {
+            part_number,
+            e_tag: response_string(&uploaded, "e_tag")?,
+        });
+        Ok(())
+    }
+
+    /// 残りを書き出してオブジェクトを確定する。マルチパートの確定に失敗した場合はアップロードを破棄する。
+    pub async fn finish(mut self) -> Result<UploadedExport, ApiError> {
+        if self.upload_id.is_none() {
+            s3_service::put_object(
+                self.client,
+                self.settings,
+                PutObjectInput {
+                    bucket: self.bucket.clone(),
+                    key: self.key.clone(),
+                    body: std::mem::take(&mut self.buffer),
+                    content_type: Some(JSONL_CONTENT_TYPE.to_string()),
+                    metadata: Vec::new(),
+                },
+            )
+            .await?;
+            return Ok(UploadedExport {
+                bytes: self.bytes,
+                parts: 0,
+            });
+        }
+
+        let parts = self.parts.len() + usize::from(!self.buffer.is_empty());
+        if let Err(e) = self.complete().await {
+            if let Err(abort_error) = self.abort_started().await {
+                warn!(error = %abort_error, key = %self.key, "Failed to abort multipart upload");
+            }
+            return Err(e);
+        }
+        Ok(UploadedExport {
+            bytes: self.bytes,
+            parts,
+        })
+    }
+
+    async fn complete(&mut self) -> Result<(), ApiError> {
+        if !self.buffer.is_empty() {
+            self.flush_part().await?;
+        }
+        s3_service::complete_multipart_upload(
+            self.client,
+            self.settings,
+            CompleteMultipartUploadInput {
+                bucket: self.bucket.clone(),
+                key: self.key.clone(),
+                upload_id: self.upload_id.clone().unwrap_or_default(),
+                parts: std::mem::take(&mut self.parts),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// 途中で失敗した場合に、開始済みのマルチパートアップロードを破棄する。
+    pub async fn abort(self) -> Result<(), ApiError> {
+        self.abort_started().await
+    }
+
+    async fn abort_started(&self) -> Result<(), ApiError> {
+        let Some(upload_id) = &self.upload_id else {
+            return Ok(());
+        };
+        s3_service::abort_multipart_upload(
+            self.client,
+            self.settings,
+            AbortMultipartUploadInput {
+                bucket: self.bucket.clone(),
+                key: self.key.clone(),
+                upload_id: upload_id.clone(),
+            },
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// エクスポートの書き出し先
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportTarget {
+    pub bucket: String,
+    pub key: String,
+    /// 解決済みのチャンネル ID
+    pub channel: String,
+    pub thread_ts: Option<String>,
+}
+
+/// 書き出し終えたエクスポート
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub message_count: usize,
+    pub uploaded: UploadedExport,
+    /// `MAX_EXPORT_MESSAGES` で打ち切ったかどうか
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportStatus {
+    Running,
+    Succeeded(ExportSummary),
+    Failed(String),
+}
+
+impl ExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Succeeded(_) => "succeeded",
+            Self::Failed(_) => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportJob {
+    /// ジョブを作った呼び出し元の `key_name`。他の呼び出し元には見せない
+    owner: String,
+    pub target: ExportTarget,
+    pub status: ExportStatus,
+    updated_at: Instant,
+}
+
+/// `/slack/export` のジョブの状態 (ジョブ ID → 状態)。
+///
+/// プロセス内にだけ保持するため、再起動すると実行中のジョブも含めて失われる。
+#[derive(Clone)]
+pub struct ExportJobs {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, ExportJob>>>,
+}
+
+impl ExportJobs {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 実行中のジョブを登録し、ジョブ ID を返す。
+    pub fn start(&self, owner: &str, target: ExportTarget, now: Instant) -> String {
+        let job_id = new_job_id();
+        let mut entries = self.lock_entries();
+        if entries.len() >= MAX_EXPORT_JOBS {
+            let ttl = self.ttl;
+            entries.retain(|_, job| {
+                job.status == ExportStatus::Running
+                    || now.saturating_duration_since(job.updated_at) < ttl
+            });
+            if entries.len() >= MAX_EXPORT_JOBS
+                && let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, job)| job.updated_at)
+                    .map(|(id, _)| id.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            job_id.clone(),
+            ExportJob {
+                owner: owner.to_string(),
+                target,
+                status: ExportStatus::Running,
+                updated_at: now,
+            },
+        );
+        job_id
+    }
+
+    pub fn finish(&self, job_id: &str, status: ExportStatus, now: Instant) {
+        if let Some(job) = self.lock_entries().get_mut(job_id) {
+            job.status = status;
+            job.updated_at = now;
+        }
+    }
+
+    /// `owner` が作ったジョブの状態。期限切れや他の呼び出し元のジョブは `None`。
+    pub fn get(&self, job_id: &str, owner: &str, now: Instant) -> Option<ExportJob> {
+        let entries = self.lock_entries();
+        let job = entries.get(job_id)?;
+        let expired = job.status != ExportStatus::Running
+            && now.saturating_duration_since(job.updated_at) >= self.ttl;
+        (job.owner == owner && !expired).then(|| job.clone())
+    }
+
+    fn lock_entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, ExportJob>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn new_job_id() -> String {
+    static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let counter = JOB_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("export-{:x}-{counter:x}", slack_schedule::unix_now())
+}
+
+/// `s3_service` が返す JSON から文字列の項目を読む。
+fn response_string(response: &str, name: &str) -> Result<String, ApiError> {
+    let parsed = nojson::RawJson::parse(response)
+        .map_err(|e| ApiError::InternalServerError(format!("Invalid S3 response: {e}")))?;
+    parsed
+        .value()
+        .to_member(name)
+        .and_then(|member| member.required())
+        .and_then(String::try_from)
+        .map_err(|e| ApiError::InternalServerError(format!("Invalid S3 response: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+    use tokio::time::Instant;
+
+    use super::{
+        ExportJobs, ExportStatus, ExportTarget, ExportedMessage, collect_user_ids, export_key,
+        sort_messages,
+    };
+
+    fn parse(text: &str) -> ExportedMessage {
+        let json = nojson::RawJson::parse(text).expect("valid JSON");
+        ExportedMessage::parse(json.value()).expect("message should parse")
+    }
+
+    #[test]
+    fn message_line_includes_files_reactions_and_names() {
+        let message = parse(
+            r#"{
+                "type": "message",
+                "ts": "1712345678.000100",
+                "user": "U1",
+                "text": "rollback done",
+                "files": [{"id": "F1", "name": "graph.png", "mimetype": "image/png", "size": 42}],
+                "reactions": [{"name": "eyes", "count": 2, "users": ["U2", "U3"]}]
+            }"#,
+        );
+        assert_eq!(
+            collect_user_ids(std::slice::from_ref(&message))
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["U1", "U2", "U3"]
+        );
+
+        let names = HashMap::from([
+            ("U1".to_string(), "alice".to_string()),
+            ("U2".to_string(), "bob".to_string()),
+        ]);
+        assert_eq!(
+            message.to_json_line(&names),
+            concat!(
+                r#"{"ts":"1712345678.000100","user":"U1","user_name":"alice","text":"rollback done","#,
+                r#""files":[{"id":"F1","name":"graph.png","title":null,"mimetype":"image/png","size":42,"url_private":null}],"#,
+                r#""reactions":[{"name":"eyes","count":2,"users":["U2","U3"],"user_names":["bob",null]}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn messages_are_sorted_oldest_first_without_duplicates() {
+        let mut messages = ["1712345678.000300", "999999999.000001", "1712345678.000100"]
+            .into_iter()
+            .chain(["1712345678.000100"])
+            .map(|ts| parse(&format!(r#"{{"ts": "{ts}", "text": "x"}}"#)))
+            .collect::<Vec<_>>();
+        sort_messages(&mut messages);
+        assert_eq!(
+            messages.iter().map(|m| m.ts.as_str()).collect::<Vec<_>>(),
+            ["999999999.000001", "1712345678.000100", "1712345678.000300"]
+        );
+    }
+
+    #[test]
+    fn export_key_names_the_channel_and_target() {
+        assert_eq!(
+            export_key("C9", None, 1_792_195_200),
+            "slack/C9/exports/2026-10-17/history-1792195200.jsonl"
+        );
+        assert_eq!(
+            export_key("C9", Some("1712345678.000100"), 1_792_195_200),
+            "slack/C9/exports/2026-10-17/thread-1712345678.000100-1792195200.jsonl"
+        );
+    }
+
+    #[test]
+    fn export_jobs_are_visible_to_their_owner_until_ttl() {
+        let jobs = ExportJobs::new(Duration::from_secs(60));
+        let now = Instant::now();
+        let target = ExportTarget {
+            bucket: "archive".to_string(),
+            key: "slack/C9/export.jsonl".to_string(),
+            channel: "C9".to_string(),
+            thread_ts: None,
+        };
+        let job_id = jobs.start("ci", target.clone(), now);
+        assert_ne!(jobs.start("ci", target.clone(), now), job_id);
+
+        let job = jobs.get(&job_id, "ci", now).expect("job");
+        assert_eq!(job.target, target);
+        assert_eq!(job.status, ExportStatus::Running);
+        assert!(jobs.get(&job_id, "other", now).is_none());
+        // 実行中のジョブは期限切れにしない
+        assert!(
+            jobs.get(&job_id, "ci", now + Duration::from_secs(120))
+                .is_some()
+        );
+
+        let finished = now + Duration::from_secs(120);
+        jobs.finish(&job_id, ExportStatus::Failed("boom".to_string()), finished);
+        assert_eq!(
+            jobs.get(&job_id, "ci", finished + Duration::from_secs(59))
+                .expect("job")
+                .status,
+            ExportStatus::Failed("boom".to_string())
+        );
+        assert!(
+            jobs.get(&job_id, "ci", finished + Duration::from_secs(60))
+                .is_none()
+        );
+    }
+}
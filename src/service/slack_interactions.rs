@@ -0,0 +1,356 @@
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use shiguredo_http11::uri::percent_decode;
+
+use crate::{
+    auth::CallerIdentity,
+    config::state::AppState,
+    errors::api_error::ApiError,
+    http_client::{HttpClient, HttpRequest},
+};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// `application/x-www-form-urlencoded` の本文を名前と値の組にする。
+pub fn parse_form(body: &str) -> Result<Vec<(String, String)>, ApiError> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((decode_form_component(name)?, decode_form_component(value)?))
+        })
+        .collect()
+}
+
+fn decode_form_component(value: &str) -> Result<String, ApiError> {
+    percent_decode(&value.replace('+', " "))
+        .map_err(|_| ApiError::BadRequest("Invalid form body".to_string()))
+}
+
+fn form_value<'a>(fields: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    fields
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// スラッシュコマンドの呼び出し
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlashCommand {
+    /// `/s3ls` のように `/` から始まるコマンド名
+    pub command: String,
+    pub text: String,
+    pub user_id: String,
+    /// 実行したユーザーのワークスペース
+    pub team_id: Option<String>,
+    pub channel_id: String,
+    pub response_url: String,
+}
+
+impl SlashCommand {
+    pub fn from_form(fields: &[(String, String)]) -> Result<Self, ApiError> {
+        let required = |name: &str| {
+            form_value(fields, name)
+                .filter(|value| !value.is_empty())
+                .map(ToString::to_string)
+                .ok_or_else(|| ApiError::BadRequest(format!("'{name}' is required")))
+        };
+        Ok(Self {
+            command: required("command")?,
+            text: form_value(fields, "text")
+                .unwrap_or_default()
+                .trim()
+                .to_string(),
+            user_id: required("user_id")?,
+            team_id: form_value(fields, "team_id")
+                .filter(|value| !value.is_empty())
+                .map(ToString::to_string),
+            channel_id: required("channel_id")?,
+            response_url: required("response_url")?,
+        })
+    }
+}
+
+/// ボタンやショートカットなどの操作。`payload` フィールドの JSON から作る。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interaction {
+    /// `block_actions` / `shortcut` / `view_submission` など
+    pub interaction_type: String,
+    /// ハンドラーを選ぶ ID。`block_actions` は最初のアクションの `action_id`、それ以外は `callback_id`
+    pub handler_id: String,
+    /// `block_actions` のアクションの `value`
+    pub value: Option<String>,
+    pub user_id: String,
+    pub team_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub response_url: Option<String>,
+    /// ハンドラーが独自に読むための元の JSON
+    pub payload: String,
+}
+
+impl Interaction {
+    pub fn from_form(fields: &[(String, String)]) -> Result<Self, ApiError> {
+        let payload = form_value(fields, "payload")
+            .ok_or_else(|| ApiError::BadRequest("'payload' is required".to_string()))?;
+        parse_interaction_payload(payload)
+            .map_err(|e| ApiError::BadRequest(format!("Invalid 'payload': {e}")))
+    }
+}
+
+fn parse_interaction_payload(payload: &str) -> Result<Interaction, nojson::JsonParseError> {
+    let json = nojson::RawJson::parse(payload)?;
+    let root = json.value();
+    let interaction_type = String::try_from(root.to_member("type")?.required()?)?;
+
+    let (handler_id, value) = match interaction_type.as_str() {
+        "block_actions" => {
+            let action = root
+                .to_member("actions")?
+                .required()?
+                .to_array()?
+                .next()
+                .ok_or_else(|| root.invalid("'actions' must not be empty"))?;
+            let value = action
+                .to_member("value")?
+                .optional()
+                .map(String::try_from)
+                .transpose()?;
+            (
+                String::try_from(action.to_member("action_id")?.required()?)?,
+                value,
+            )
+        }
+        "view_submission" | "view_closed" => {
+            let view = root.to_member("view")?.required()?;
+            (
+                String::try_from(view.to_member("callback_id")?.required()?)?,
+                None,
+            )
+        }
+        _ => (
+            String::try_from(root.to_member("callback_id")?.required()?)?,
+            None,
+        ),
+    };
+
+    let user_id = String::try_from(
+        root.to_member("user")?
+            .required()?
+            .to_member("id")?
+            .required()?,
+    )?;
+    let team_id = match root.to_member("team")?.optional() {
+        Some(team) => Some(String::try_from(team.to_member("id")?.required()?)?),
+        None => None,
+    };
+    let channel_id = match root.to_member("channel")?.optional() {
+        Some(channel) => Some(String::try_from(channel.to_member("id")?.required()?)?),
+        None => None,
+    };
+    let response_url = match root.to_member("response_url")?.optional() {
+        Some(url) => Some(String::try_from(url)?),
+        None => None,
+    };
+
+    Ok(Interaction {
+        interaction_type,
+        handler_id,
+        value,
+        user_id,
+        team_id,
+        channel_id,
+        response_url,
+        payload: payload.to_string(),
+    })
+}
+
+/// Slack に返すメッセージ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyMessage {
+    pub text: String,
+    /// true ならチャンネル全体に、false なら実行したユーザーにだけ表示する
+    pub in_channel: bool,
+}
+
+impl ReplyMessage {
+    pub fn ephemeral(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            in_channel: false,
+        }
+    }
+
+    pub fn in_channel(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            in_channel: true,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let response_type = if self.in_channel {
+            "in_channel"
+        } else {
+            "ephemeral"
+        };
+        nojson::json(|f| {
+            f.object(|f| {
+                f.member("response_type", response_type)?;
+                f.member("text", &self.text)
+            })
+        })
+        .to_string()
+    }
+}
+
+/// ハンドラーの応答
+pub enum Reply {
+    /// HTTP レスポンスとしてすぐに返す
+    Now(ReplyMessage),
+    /// 空の 200 をすぐに返し、完了後に `response_url` へ投稿する。3 秒を超えうる処理に使う。
+    Later(BoxFuture<'static, ReplyMessage>),
+}
+
+/// スラッシュコマンドのハンドラー。`SlackHandlerRegistry::with_command` で登録する。
+pub trait SlashCommandHandler: Send + Sync {
+    fn handle<'a>(
+        &'a self,
+        app_state: &'a AppState,
+        caller: &'a CallerIdentity,
+        command: &'a SlashCommand,
+    ) -> BoxFuture<'a, Result<Reply, ApiError>>;
+}
+
+/// 操作のハンドラー。`SlackHandlerRegistry::with_interaction` で `action_id` / `callback_id` ごとに登録する。
+pub trait InteractionHandler: Send + Sync {
+    fn handle<'a>(
+        &'a self,
+        app_state: &'a AppState,
+        caller: &'a CallerIdentity,
+        interaction: &'a Interaction,
+    ) -> BoxFuture<'a, Result<Reply, ApiError>>;
+}
+
+/// コマンド名と `action_id` / `callback_id` からハンドラーを引く表
+#[derive(Clone, Default)]
+pub struct SlackHandlerRegistry {
+    commands: HashMap<String, Arc<dyn SlashCommandHandler>>,
+    interactions: HashMap<String, Arc<dyn InteractionHandler>>,
+}
+
+impl SlackHandlerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_command(
+        mut self,
+        command: impl Into<String>,
+        handler: impl SlashCommandHandler + 'static,
+    ) -> Self {
+        self.commands.insert(command.into(), Arc::new(handler));
+        self
+    }
+
+    pub fn with_interaction(
+        mut self,
+        id: impl Into<String>,
+        handler: impl InteractionHandler + 'static,
+    ) -> Self {
+        self.interactions.insert(id.into(), Arc::new(handler));
+        self
+    }
+
+    pub fn command(&self, command: &str) -> Option<Arc<dyn SlashCommandHandler>> {
+        self.commands.get(command).cloned()
+    }
+
+    pub fn interaction(&self, id: &str) -> Option<Arc<dyn InteractionHandler>> {
+        self.interactions.get(id).cloned()
+    }
+}
+
+/// `response_url` にメッセージを投稿する。
+pub async fn post_to_response_url(
+    client: &HttpClient,
+    response_url: &str,
+    message: &ReplyMessage,
+) -> Result<(), String> {
+    if !response_url.starts_with("https://") {
+        return Err(format!("response_url must be https: {response_url}"));
+    }
+    let response = client
+        .send(HttpRequest {
+            method: "POST".to_string(),
+            url: response_url.to_string(),
+            headers: vec![(
+                "Content-Type".to_string(),
+                "application/json; charset=utf-8".to_string(),
+            )],
+            body: message.to_json().into_bytes(),
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    if !(200..300).contains(&response.status_code) {
+        return Err(format!("response_url returned {}", response.status_code));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interaction, ReplyMessage, SlashCommand, parse_form};
+
+    #[test]
+    fn slash_command_is_read_from_form() {
+        let fields = parse_form(
+            "command=%2Fs3ls&text=reports%2Fdaily+2026&user_id=U1&team_id=T1&channel_id=C1&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2F1",
+        )
+        .expect("form");
+        assert_eq!(
+            SlashCommand::from_form(&fields).expect("command"),
+            SlashCommand {
+                command: "/s3ls".to_string(),
+                text: "reports/daily 2026".to_string(),
+                user_id: "U1".to_string(),
+                team_id: Some("T1".to_string()),
+                channel_id: "C1".to_string(),
+                response_url: "https://hooks.slack.com/commands/1".to_string(),
+            }
+        );
+        assert!(SlashCommand::from_form(&parse_form("text=x").expect("form")).is_err());
+    }
+
+    #[test]
+    fn interaction_uses_action_id_or_callback_id() {
+        let block_action = r#"{"type":"block_actions","user":{"id":"U1"},"team":{"id":"T1"},"channel":{"id":"C1"},"response_url":"https://hooks.slack.com/actions/1","actions":[{"action_id":"approve","value":"42"}]}"#;
+        let form = vec![("payload".to_string(), block_action.to_string())];
+        let interaction = Interaction::from_form(&form).expect("block_actions");
+        assert_eq!(interaction.handler_id, "approve");
+        assert_eq!(interaction.value.as_deref(), Some("42"));
+        assert_eq!(interaction.team_id.as_deref(), Some("T1"));
+        assert_eq!(interaction.channel_id.as_deref(), Some("C1"));
+
+        let shortcut = r#"{"type":"shortcut","callback_id":"open_report","user":{"id":"U1"}}"#;
+        let form = vec![("payload".to_string(), shortcut.to_string())];
+        let interaction = Interaction::from_form(&form).expect("shortcut");
+        assert_eq!(interaction.handler_id, "open_report");
+        assert_eq!(interaction.response_url, None);
+
+        let empty = r#"{"type":"block_actions","user":{"id":"U1"},"actions":[]}"#;
+        let form = vec![("payload".to_string(), empty.to_string())];
+        assert!(Interaction::from_form(&form).is_err());
+    }
+
+    #[test]
+    fn reply_sets_response_type() {
+        assert_eq!(
+            ReplyMessage::ephemeral("hi").to_json(),
+            r#"{"response_type":"ephemeral","text":"hi"}"#
+        );
+        assert_eq!(
+            ReplyMessage::in_channel("hi").to_json(),
+            r#"{"response_type":"in_channel","text":"hi"}"#
+        );
+    }
+}
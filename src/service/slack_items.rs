@@ -0,0 +1,150 @@
+/// メッセージに付いたリアクション。`reactions.get` の要素に対応する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reaction {
+    pub name: String,
+    pub count: i64,
+    pub users: Vec<String>,
+}
+
+/// ピン留めされたメッセージ。`pins.list` の `type: "message"` の要素に対応する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedMessage {
+    pub ts: String,
+    pub text: Option<String>,
+    pub user: Option<String>,
+    pub created: Option<i64>,
+    pub created_by: Option<String>,
+}
+
+/// チャンネルのブックマーク。`bookmarks.add` / `bookmarks.list` の要素に対応する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub emoji: Option<String>,
+    pub date_created: Option<i64>,
+}
+
+/// `bookmarks.add` で追加するリンクのブックマーク
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewBookmark {
+    pub title: String,
+    pub link: String,
+    /// `:book:` 形式の絵文字
+    pub emoji: Option<String>,
+}
+
+/// メッセージの `reactions` を読む。`count` が無ければ `users` の数にする。
+pub fn parse_reactions(
+    message: nojson::RawJsonValue<'_, '_>,
+) -> Result<Vec<Reaction>, nojson::JsonParseError> {
+    let mut reactions = Vec::new();
+    if let Some(items) = message.to_member("reactions")?.optional() {
+        for item in items.to_array()? {
+            let mut users = Vec::new();
+            if let Some(ids) = item.to_member("users")?.optional() {
+                for id in ids.to_array()? {
+                    users.push(String::try_from(id)?);
+                }
+            }
+            let count = match item.to_member("count")?.optional() {
+                Some(count) => i64::try_from(count)?,
+                None => users.len() as i64,
+            };
+            reactions.push(Reaction {
+                name: String::try_from(item.to_member("name")?.required()?)?,
+                count,
+                users,
+            });
+        }
+    }
+    Ok(reactions)
+}
+
+/// `:white_check_mark:` / `white_check_mark` / `+1::skin-tone-2` を `reactions.*` に渡す名前にする。
+/// エラーはそのまま 400 の detail に使う。
+pub fn normalize_reaction_name(raw: &str) -> Result<String, String> {
+    let name = raw.trim().trim_matches(':');
+    let (base, skin_tone) = match name.split_once("::") {
+        Some((base, skin_tone)) => (base, Some(skin_tone)),
+        None => (name, None),
+    };
+    let valid_base = !base.is_empty()
+        && base.chars().all(|c| {
+            c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '+' | '\'')
+        });
+    let valid_skin_tone = skin_tone.is_none_or(|tone| {
+        tone.strip_prefix("skin-tone-")
+            .is_some_and(|n| matches!(n, "2" | "3" | "4" | "5" | "6"))
+    });
+    if !valid_base || !valid_skin_tone {
+        return Err(format!("'name' is not a valid emoji name: '{raw}'"));
+    }
+    Ok(name.to_string())
+}
+
+/// メッセージの `ts` (`1712345678.000100`) の形式を検証する。
+pub fn validate_message_ts(ts: &str) -> Result<(), String> {
+    let valid = ts.split_once('.').is_some_and(|(secs, micros)| {
+        !secs.is_empty()
+            && !micros.is_empty()
+            && secs.bytes().all(|b| b.is_ascii_digit())
+            && micros.bytes().all(|b| b.is_ascii_digit())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'ts' must be a Slack message timestamp like 1712345678.000100 (got '{ts}')"
+        ))
+    }
+}
+
+/// ブックマークのリンクは `http://` / `https://` の URL のみ受け付ける。
+pub fn validate_bookmark_link(link: &str) -> Result<(), String> {
+    let rest = link
+        .strip_prefix("https://")
+        .or_else(|| link.strip_prefix("http://"));
+    match rest {
+        Some(rest) if !rest.is_empty() && !link.chars().any(char::is_whitespace) => Ok(()),
+        _ => Err(format!("'link' must be an http(s) URL (got '{link}')")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_reaction_name, validate_bookmark_link, validate_message_ts};
+
+    #[test]
+    fn reaction_names_are_normalized() {
+        assert_eq!(
+            normalize_reaction_name(":white_check_mark:").as_deref(),
+            Ok("white_check_mark")
+        );
+        assert_eq!(normalize_reaction_name("+1").as_deref(), Ok("+1"));
+        assert_eq!(
+            normalize_reaction_name(":+1::skin-tone-3:").as_deref(),
+            Ok("+1::skin-tone-3")
+        );
+
+        assert!(normalize_reaction_name("").is_err());
+        assert!(normalize_reaction_name("::").is_err());
+        assert!(normalize_reaction_name("white check").is_err());
+        assert!(normalize_reaction_name("Eyes").is_err());
+        assert!(normalize_reaction_name("+1::skin-tone-9").is_err());
+    }
+
+    #[test]
+    fn message_ts_and_bookmark_link_are_validated() {
+        assert!(validate_message_ts("1712345678.000100").is_ok());
+        assert!(validate_message_ts("1712345678").is_err());
+        assert!(validate_message_ts("abc.def").is_err());
+
+        assert!(validate_bookmark_link("https://runbooks.example.com/oncall").is_ok());
+        assert!(validate_bookmark_link("http://grafana.internal/d/abc").is_ok());
+        assert!(validate_bookmark_link("javascript:alert(1)").is_err());
+        assert!(validate_bookmark_link("https://").is_err());
+        assert!(validate_bookmark_link("https://example.com/a b").is_err());
+    }
+}
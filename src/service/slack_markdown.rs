@@ -0,0 +1,809 @@
+use nojson::RawJsonOwned;
+
+use crate::service::slack_message::{
+    MAX_BLOCKS, MAX_TEXT_OBJECT_CHARS, SlackMessage, escape_mrkdwn,
+};
+
+/// 1メッセージの `text` の上限。Slack は 4,000 文字を超える `text` の表示を保証しない
+pub const MAX_MESSAGE_TEXT_CHARS: usize = 4000;
+/// `header` ブロックの `plain_text` の上限
+const MAX_HEADER_CHARS: usize = 150;
+
+/// メッセージ本文の書式。`format` で指定する
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// `text` を mrkdwn としてそのまま送る
+    #[default]
+    Mrkdwn,
+    /// `text` を CommonMark として mrkdwn に変換する
+    Markdown,
+    /// `text` を CommonMark として `header` / `section` ブロックに変換する
+    MarkdownBlocks,
+}
+
+impl MessageFormat {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "mrkdwn" => Some(Self::Mrkdwn),
+            "markdown" => Some(Self::Markdown),
+            "markdown_blocks" => Some(Self::MarkdownBlocks),
+            _ => None,
+        }
+    }
+}
+
+/// `message.text` を `format` に従って変換し、上限ごとに分けたメッセージを返す。
+///
+/// 先頭のメッセージは元の `thread_ts` / `reply_broadcast` を持ち、続きはスレッドに投稿する前提でどちらも持たない。
+pub fn split_message(message: &SlackMessage, format: MessageFormat) -> Vec<SlackMessage> {
+    let markdown = message.text.as_deref().unwrap_or_default();
+    let mut parts = match format {
+        MessageFormat::Mrkdwn => return vec![message.clone()],
+        MessageFormat::Markdown => chunk_mrkdwn(&to_mrkdwn(markdown), MAX_MESSAGE_TEXT_CHARS)
+            .into_iter()
+            .map(|text| SlackMessage {
+                text: Some(text),
+                ..follow_up(message)
+            })
+            .collect::<Vec<_>>(),
+        MessageFormat::MarkdownBlocks => to_blocks(markdown)
+            .into_iter()
+            .map(|(text, blocks)| SlackMessage {
+                text: Some(text),
+                blocks: Some(blocks),
+                ..follow_up(message)
+            })
+            .collect::<Vec<_>>(),
+    };
+    if let Some(first) = parts.first_mut() {
+        first.thread_ts = message.thread_ts.clone();
+        first.reply_broadcast = message.reply_broadcast;
+    }
+    parts
+}
+
+/// 本文以外の表示オプションだけを引き継いだメッセージ
+fn follow_up(message: &SlackMessage) -> SlackMessage {
+    SlackMessage {
+        text: None,
+        blocks: None,
+        attachments: None,
+        thread_ts: None,
+        reply_broadcast: None,
+        ..message.clone()
+    }
+}
+
+/// 変換途中の行。見出しは `header` ブロックにするため区別する
+enum Line {
+    Heading(String),
+    Text(String),
+}
+
+/// CommonMark を mrkdwn に変換する。
+pub fn to_mrkdwn(markdown: &str) -> String {
+    convert(markdown)
+        .into_iter()
+        .map(|line| match line {
+            Line::Heading(plain) => format!("*{}*", escape_mrkdwn(&plain)),
+            Line::Text(text) => text,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// CommonMark を `header` / `section` ブロックに変換し、`MAX_BLOCKS` ごとに分ける。通知用の `text` も返す。
+fn to_blocks(markdown: &str) -> Vec<(String, RawJsonOwned)> {
+    let mut blocks = Vec::new();
+    let mut pending = Vec::new();
+    let flush = |pending: &mut Vec<String>, blocks: &mut Vec<Block>| {
+        let text = pending.join("\n");
+        pending.clear();
+        for chunk in chunk_mrkdwn(&text, MAX_TEXT_OBJECT_CHARS) {
+            blocks.push(Block::Section(chunk));
+        }
+    };
+    for line in convert(markdown) {
+        match line {
+            // Slack は空の `header` を受け付けない
+            Line::Heading(plain) if plain.trim().is_empty() => {}
+            Line::Heading(plain) => {
+                flush(&mut pending, &mut blocks);
+                if plain.chars().count() <= MAX_HEADER_CHARS {
+                    blocks.push(Block::Header(plain));
+                } else {
+                    blocks.push(Block::Section(format!("*{}*", escape_mrkdwn(&plain))));
+                }
+            }
+            Line::Text(text) => pending.push(text),
+        }
+    }
+    flush(&mut pending, &mut blocks);
+
+    blocks
+        .chunks(MAX_BLOCKS)
+        .filter_map(|blocks| {
+            let json = nojson::json(|f| {
+                f.value(nojson::array(|f| {
+                    for block in blocks {
+                        f.element(block)?;
+                    }
+                    Ok(())
+                }))
+            })
+            .to_string();
+            let fallback = blocks.iter().find_map(Block::summary)?;
+            Some((fallback, RawJsonOwned::parse(json).ok()?))
+        })
+        .collect()
+}
+
+enum Block {
+    Header(String),
+    Section(String),
+}
+
+impl Block {
+    /// 通知に表示する先頭の1行
+    fn summary(&self) -> Option<String> {
+        let text = match self {
+            Self::Header(plain) => escape_mrkdwn(plain),
+            Self::Section(text) => text.clone(),
+        };
+        let line = text.lines().find(|line| !line.trim().is_empty())?;
+        Some(line.chars().take(MAX_HEADER_CHARS).collect())
+    }
+}
+
+impl nojson::DisplayJson for Block {
+    fn fmt(&self, f: &mut nojson::JsonFormatter<'_, '_>) -> std::fmt::Result {
+        f.object(|f| match self {
+            Self::Header(plain) => {
+                f.member("type", "header")?;
+                f.member(
+                    "text",
+                    nojson::object(|f| {
+                        f.member("type", "plain_text")?;
+                        f.member("text", plain)?;
+                        f.member("emoji", true)
+                    }),
+                )
+            }
+            Self::Section(text) => {
+                f.member("type", "section")?;
+                f.member(
+                    "text",
+                    nojson::object(|f| {
+                        f.member("type", "mrkdwn")?;
+                        f.member("text", text)
+                    }),
+                )
+            }
+        })
+    }
+}
+
+/// `limit` 文字以下に行単位で分ける。コードブロックの途中で分けた場合は閉じてから次で開き直す。
+pub fn chunk_mrkdwn(text: &str, limit: usize) -> Vec<String> {
+    const FENCE: &str = "```";
+    // 開き直した ``` と閉じる \n``` の分
+    let room = limit.saturating_sub(FENCE.len() * 2 + 2).max(1);
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+    let mut in_code = false;
+    for line in text.split('\n') {
+        for piece in split_chars(line, room) {
+            let piece_chars = piece.chars().count();
+            let closing = if in_code { FENCE.len() + 1 } else { 0 };
+            if !current.is_empty() && current_chars + 1 + piece_chars + closing > limit {
+                if in_code {
+                    current.push('\n');
+                    current.push_str(FENCE);
+                }
+                chunks.push(std::mem::take(&mut current));
+                current_chars = 0;
+                if in_code {
+                    // 閉じる行で分けた場合は、閉じたところで終わり
+                    if line.trim() == FENCE {
+                        continue;
+                    }
+                    current.push_str(FENCE);
+                    current_chars = FENCE.len();
+                }
+            }
+            if !current.is_empty() {
+                current.push('\n');
+                current_chars += 1;
+            }
+            current.push_str(piece);
+            current_chars += piece_chars;
+        }
+        if line.trim() == FENCE {
+            in_code = !in_code;
+        }
+    }
+    chunks.push(current);
+
+    chunks
+        .into_iter()
+        .map(|chunk| chunk.trim_matches('\n').to_string())
+        .filter(|chunk| !chunk.trim().is_empty() && chunk != FENCE)
+        .collect()
+}
+
+/// `limit` 文字を超える行を文字境界で分ける。
+fn split_chars(line: &str, limit: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = line;
+    while rest.chars().count() > limit {
+        let (end, _) = rest.char_indices().nth(limit).unwrap_or((rest.len(), ' '));
+        pieces.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    pieces.push(rest);
+    pieces
+}
+
+fn convert(markdown: &str) -> Vec<Line> {
+    let lines = markdown.lines().collect::<Vec<_>>();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        i += 1;
+
+        if let Some(fence) = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f)) {
+            out.push(Line::Text("```".to_string()));
+            while i < lines.len() && !lines[i].trim_start().starts_with(fence) {
+                out.push(Line::Text(escape_mrkdwn(lines[i])));
+                i += 1;
+            }
+            i += 1;
+            out.push(Line::Text("```".to_string()));
+        } else if trimmed.starts_with('|') && lines.get(i).is_some_and(|l| is_table_separator(l)) {
+            let mut rows = vec![table_cells(trimmed)];
+            i += 1;
+            while i < lines.len() && lines[i].trim_start().starts_with('|') {
+                rows.push(table_cells(lines[i].trim_start()));
+                i += 1;
+            }
+            out.extend(render_table(&rows).into_iter().map(Line::Text));
+        } else if let Some(heading) = atx_heading(trimmed) {
+            out.push(Line::Heading(strip_inline(heading)));
+        } else if !trimmed.is_empty()
+            && !is_block_start(trimmed)
+            && lines.get(i).is_some_and(|next| is_setext_underline(next))
+        {
+            out.push(Line::Heading(strip_inline(trimmed.trim_end())));
+            i += 1;
+        } else if is_thematic_break(trimmed) {
+            out.push(Line::Text("──────────".to_string()));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            let quote = quote.strip_prefix(' ').unwrap_or(quote);
+            out.push(Line::Text(format!("> {}", inline(quote.trim_end()))));
+        } else if let Some((marker, item)) = list_item(trimmed) {
+            let indent = "    ".repeat((line.len() - trimmed.len()) / 2);
+            out.push(Line::Text(format!(
+                "{indent}{marker} {}",
+                inline(item.trim_end())
+            )));
+        } else {
+            out.push(Line::Text(inline(line.trim_end())));
+        }
+    }
+    out
+}
+
+fn atx_heading(line: &str) -> Option<&str> {
+    let level = line.bytes().take_while(|&b| b == b'#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some(rest.trim().trim_end_matches('#').trim_end())
+}
+
+fn is_setext_underline(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && (line.bytes().all(|b| b == b'=') || line.bytes().all(|b| b == b'-'))
+}
+
+fn is_thematic_break(line: &str) -> bool {
+    let marks = line
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<_>>();
+    marks.len() >= 3
+        && ['-', '*', '_']
+            .into_iter()
+            .any(|mark| marks.iter().all(|&c| c == mark))
+}
+
+fn is_block_start(line: &str) -> bool {
+    line.starts_with('>')
+        || line.starts_with('|')
+        || line.starts_with("```")
+        || list_item(line).is_some()
+        || is_thematic_break(line)
+}
+
+/// 箇条書きなら表示する記号と本文
+fn list_item(line: &str) -> Option<(String, &str)> {
+    if let Some(item) = ["- ", "* ", "+ "]
+        .into_iter()
+        .find_map(|m| line.strip_prefix(m))
+    {
+        if let Some(task) = item.strip_prefix("[ ] ") {
+            return Some(("☐".to_string(), task));
+        }
+        if let Some(task) = item
+            .strip_prefix("[x] ")
+            .or_else(|| item.strip_prefix("[X] "))
+        {
+            return Some(("☑".to_string(), task));
+        }
+        return Some(("•".to_string(), item));
+    }
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    if (1..=9).contains(&digits) {
+        let rest = &line[digits..];
+        if let Some(item) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return Some((format!("{}.", &line[..digits]), item));
+        }
+    }
+    None
+}
+
+fn is_table_separator(line: &str) -> bool {
+    let cells = table_cells(line.trim());
+    !cells.is_empty()
+        && cells.iter().all(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.bytes().all(|b| b == b'-')
+        })
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|')
+        .map(|cell| cell.trim().to_string())
+        .collect()
+}
+
+/// Slack には表が無いため、列をそろえたコードブロックにする。
+fn render_table(rows: &[Vec<String>]) -> Vec<String> {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| strip_inline(cell))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths = (0..columns)
+        .map(|c| {
+            rows.iter()
+                .filter_map(|row| row.get(c))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect::<Vec<_>>();
+    let format_row = |row: &[String]| {
+        widths
+            .iter()
+            .enumerate()
+            .map(|(c, &width)| {
+                let cell = row.get(c).map(String::as_str).unwrap_or_default();
+                format!("{cell}{}", " ".repeat(width - cell.chars().count()))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut out = vec!["```".to_string()];
+    for (i, row) in rows.iter().enumerate() {
+        out.push(escape_mrkdwn(&format_row(row)));
+        if i == 0 {
+            out.push(
+                widths
+                    .iter()
+                    .map(|&width| "-".repeat(width))
+                    .collect::<Vec<_>>()
+                    .join("-+-"),
+            );
+        }
+    }
+    out.push("```".to_string());
+    out
+}
+
+/// 行内の CommonMark を mrkdwn にする。
+fn inline(text: &str) -> String {
+    inline_chars(&text.chars().collect::<Vec<_>>())
+}
+
+fn inline_chars(chars: &[char]) -> String {
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if chars.get(i + 1).is_some_and(char::is_ascii_punctuation) => {
+                push_escaped(&mut out, chars[i + 1]);
+                i += 2;
+            }
+            '`' => {
+                let run = run_length(chars, i, c);
+                match find_code_end(chars, i + run, run) {
+                    Some(end) => {
+                        let code = chars[i + run..end].iter().collect::<String>();
+                        out.push('`');
+                        out.push_str(&escape_mrkdwn(code.trim()));
+                        out.push('`');
+                        i = end + run;
+                    }
+                    None => {
+                        out.extend(&chars[i..i + run]);
+                        i += run;
+                    }
+                }
+            }
+            '[' | '!' => {
+                let start = if c == '!' { i + 1 } else { i };
+                match (chars.get(start), parse_link(chars, start)) {
+                    (Some('['), Some((label, url, next))) => {
+                        let label = inline_chars(label);
+                        let label = if label.is_empty() {
+                            escape_mrkdwn(&url)
+                        } else {
+                            label
+                        };
+                        out.push_str(&format!("<{}|{label}>", link_url(&url)));
+                        i = next;
+                    }
+                    _ => {
+                        out.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            '<' => match autolink(chars, i) {
+                Some((url, next)) => {
+                    out.push_str(&format!("<{}>", link_url(&url)));
+                    i = next;
+                }
+                None => {
+                    out.push_str("&lt;");
+                    i += 1;
+                }
+            },
+            '*' | '_' | '~' => {
+                let run = run_length(chars, i, c);
+                let opens = chars.get(i + run).is_some_and(|n| !n.is_whitespace())
+                    && (c != '_' || i == 0 || !chars[i - 1].is_alphanumeric());
+                let (width, mark) = match (c, run) {
+                    ('~', 2..) => (2, '~'),
+                    ('~', _) => (0, '~'),
+                    (_, 2..) => (2, '*'),
+                    _ => (1, '_'),
+                };
+                match (width > 0 && opens)
+                    .then(|| find_closing(chars, i + width, c, width))
+                    .flatten()
+                {
+                    Some(end) => {
+                        out.push(mark);
+                        out.push_str(&inline_chars(&chars[i + width..end]));
+                        out.push(mark);
+                        i = end + width;
+                    }
+                    None => {
+                        out.extend(&chars[i..i + run]);
+                        i += run;
+                    }
+                }
+            }
+            _ => {
+                push_escaped(&mut out, c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        _ => out.push(c),
+    }
+}
+
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while(|&&x| x == c).count()
+}
+
+/// `run` 個ちょうどのバッククォートの位置
+fn find_code_end(chars: &[char], from: usize, run: usize) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        if chars[j] == '`' {
+            let len = run_length(chars, j, '`');
+            if len == run {
+                return Some(j);
+            }
+            j += len;
+        } else {
+            j += 1;
+        }
+    }
+    None
+}
+
+/// 強調を閉じる `width` 個の `c` の位置。直前が空白のものは閉じとみなさない
+fn find_closing(chars: &[char], from: usize, c: char, width: usize) -> Option<usize> {
+    let mut j = from + 1;
+    while j + width <= chars.len() {
+        if chars[j] == '`' {
+            j += run_length(chars, j, '`').max(1);
+            continue;
+        }
+        let run = run_length(chars, j, c);
+        let closes = run >= width
+            && (width == 2 || run == 1)
+            && !chars[j - 1].is_whitespace()
+            && (c != '_' || chars.get(j + run).is_none_or(|n| !n.is_alphanumeric()));
+        if closes {
+            return Some(j);
+        }
+        j += run.max(1);
+    }
+    None
+}
+
+/// `[label](url "title")` を読み、ラベルと URL と続きの位置を返す。
+fn parse_link(chars: &[char], open: usize) -> Option<(&[char], String, usize)> {
+    let mut depth = 0;
+    let mut close = None;
+    for (j, &c) in chars.iter().enumerate().skip(open) {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+    let mut depth = 0;
+    let mut end = None;
+    for (j, &c) in chars.iter().enumerate().skip(close + 1) {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let end = end?;
+    let target = chars[close + 2..end].iter().collect::<String>();
+    let url = target.split_whitespace().next()?.to_string();
+    let url = url
+        .strip_prefix('<')
+        .and_then(|u| u.strip_suffix('>'))
+        .map(ToString::to_string)
+        .unwrap_or(url);
+    Some((&chars[open + 1..close], url, end + 1))
+}
+
+/// `<https://...>` / `<mailto:...>` / `<user@example.com>`
+fn autolink(chars: &[char], open: usize) -> Option<(String, usize)> {
+    let len = chars[open + 1..].iter().position(|&c| c == '>')?;
+    let inner = chars[open + 1..open + 1 + len].iter().collect::<String>();
+    if inner.is_empty() || inner.contains(char::is_whitespace) || inner.contains('<') {
+        return None;
+    }
+    let url = if inner.contains("://") || inner.starts_with("mailto:") {
+        inner
+    } else if inner.contains('@') {
+        format!("mailto:{inner}")
+    } else {
+        return None;
+    };
+    Some((url, open + len + 2))
+}
+
+fn link_url(url: &str) -> String {
+    escape_mrkdwn(url).replace('|', "%7C")
+}
+
+/// 見出しや表のセルに使うため、行内の記法を取り除いた文字列にする。
+fn strip_inline(text: &str) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && chars.get(i + 1).is_some_and(char::is_ascii_punctuation) {
+            out.push(chars[i + 1]);
+            i += 2;
+        } else if c == '['
+            && let Some((label, _, next)) = parse_link(&chars, i)
+        {
+            out.push_str(&strip_inline(&label.iter().collect::<String>()));
+            i = next;
+        } else if c == '`'
+            || c == '*'
+            || (c == '~' && chars.get(i + 1) == Some(&c))
+            || (c == '_' && !is_intraword(&chars, i))
+        {
+            i += run_length(&chars, i, c);
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// `snake_case` のように英数字に挟まれた `_`
+fn is_intraword(chars: &[char], i: usize) -> bool {
+    let run = run_length(chars, i, chars[i]);
+    i > 0
+        && chars[i - 1].is_alphanumeric()
+        && chars.get(i + run).is_some_and(|c| c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MessageFormat, chunk_mrkdwn, split_message, to_mrkdwn};
+    use crate::service::slack_message::SlackMessage;
+
+    #[test]
+    fn inline_markdown_is_translated() {
+        assert_eq!(
+            to_mrkdwn("**bold** and *em* and _em_ and ~~gone~~ in snake_case_name"),
+            "*bold* and _em_ and _em_ and ~gone~ in snake_case_name"
+        );
+        assert_eq!(
+            to_mrkdwn("see [the docs](https://example.com/a?b=1&c=2) or <https://x.dev>"),
+            "see <https://example.com/a?b=1&amp;c=2|the docs> or <https://x.dev>"
+        );
+        assert_eq!(
+            to_mrkdwn("a < b && `x<y>` \\*not em\\* 2 * 3"),
+            "a &lt; b &amp;&amp; `x&lt;y&gt;` *not em* 2 * 3"
+        );
+    }
+
+    #[test]
+    fn blocks_are_translated() {
+        let markdown = "# Release *v1*\n\n- [x] build\n  - nested\n1. first\n> quoted <b>\n\n```rust\nlet a = b && c;\n```\n\n| name | status |\n|------|:------:|\n| api  | **ok** |\n| worker | ng |\n---";
+        assert_eq!(
+            to_mrkdwn(markdown),
+            [
+                "*Release v1*",
+                "",
+                "☑ build",
+                "    • nested",
+                "1. first",
+                "> quoted &lt;b&gt;",
+                "",
+                "```",
+                "let a = b &amp;&amp; c;",
+                "```",
+                "",
+                "```",
+                "name   | status",
+                "-------+-------",
+                "api    | ok",
+                "worker | ng",
+                "```",
+                "──────────",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn long_text_is_chunked_without_breaking_code_blocks() {
+        let text = format!("intro\n```\n{}\n```\nouter", ["line"; 6].join("\n"));
+        let chunks = chunk_mrkdwn(&text, 20);
+        assert!(
+            chunks.iter().all(|chunk| chunk.chars().count() <= 20),
+            "{chunks:?}"
+        );
+        assert!(
+            chunks
+                .iter()
+                .all(|chunk| chunk.matches("```").count().is_multiple_of(2)),
+            "{chunks:?}"
+        );
+        assert!(
+            chunks
+                .last()
+                .is_some_and(|chunk| chunk.ends_with("```\nouter"))
+        );
+        assert_eq!(
+            chunks.concat().matches("line").count(),
+            6,
+            "no line should be lost"
+        );
+
+        let long = "x".repeat(45);
+        assert_eq!(chunk_mrkdwn(&long, 20).concat(), long);
+    }
+
+    #[test]
+    fn markdown_messages_are_split_into_follow_ups() {
+        let message = SlackMessage {
+            text: Some(format!("# Report\n{}", "- item\n".repeat(1000))),
+            username: Some("reporter".to_string()),
+            thread_ts: Some("1.2".to_string()),
+            ..SlackMessage::default()
+        };
+        let parts = split_message(&message, MessageFormat::Markdown);
+        assert!(parts.len() > 1);
+        assert_eq!(parts[0].thread_ts.as_deref(), Some("1.2"));
+        assert!(parts[1..].iter().all(|part| part.thread_ts.is_none()));
+        assert!(
+            parts
+                .iter()
+                .all(|part| part.username.as_deref() == Some("reporter"))
+        );
+        assert!(parts.iter().all(|part| part.validate().is_ok()));
+
+        let blocks = split_message(&message, MessageFormat::MarkdownBlocks);
+        assert_eq!(blocks[0].text.as_deref(), Some("Report"));
+        let json = blocks[0]
+            .blocks
+            .as_ref()
+            .expect("blocks")
+            .text()
+            .to_string();
+        assert!(
+            json.starts_with(r#"[{"type":"header","text":{"type":"plain_text","text":"Report","emoji":true}},{"type":"section""#),
+            "{json}"
+        );
+        assert!(blocks.iter().all(|part| part.validate().is_ok()));
+    }
+
+    #[test]
+    fn empty_headings_are_skipped_in_blocks() {
+        let message = SlackMessage {
+            text: Some("#\nbody".to_string()),
+            ..SlackMessage::default()
+        };
+        let parts = split_message(&message, MessageFormat::MarkdownBlocks);
+        assert_eq!(parts.len(), 1);
+        assert_eq!(
+            parts[0].blocks.as_ref().expect("blocks").text(),
+            r#"[{"type":"section","text":{"type":"mrkdwn","text":"body"}}]"#
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use nojson::{JsonObjectFormatter, RawJsonOwned, RawJsonValue};
+
+/// 1メッセージあたりのブロック数の上限
+pub const MAX_BLOCKS: usize = 50;
+/// テキストオブジェクト (`plain_text` / `mrkdwn`) 1つあたりの文字数の上限
+pub const MAX_TEXT_OBJECT_CHARS: usize = 3000;
+
+/// `chat.postMessage` などに渡すメッセージ本文と表示オプション。
+///
+/// `blocks` と `attachments` は受け取った JSON をそのまま Slack へ渡す。
+#[derive(Debug, Clone, Default)]
+pub struct SlackMessage {
+    pub text: Option<String>,
+    pub blocks: Option<RawJsonOwned>,
+    pub attachments: Option<RawJsonOwned>,
+    pub unfurl_links: Option<bool>,
+    pub unfurl_media: Option<bool>,
+    pub mrkdwn: Option<bool>,
+    pub username: Option<String>,
+    pub icon_emoji: Option<String>,
+    /// 返信先の親メッセージの `ts`
+    pub thread_ts: Option<String>,
+    /// スレッドへの返信をチャンネルにも表示する
+    pub reply_broadcast: Option<bool>,
+}
+
+impl SlackMessage {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    /// 本文の有無と Block Kit の上限を検証する。エラーはそのまま 400 の detail に使う。
+    pub fn validate(&self) -> Result<(), String> {
+        let has_text = self.text.as_deref().is_some_and(|t| !t.is_empty());
+        if !has_text && self.blocks.is_none() && self.attachments.is_none() {
+            return Err("One of 'text', 'blocks' or 'attachments' is required".to_string());
+        }
+        if self.reply_broadcast == Some(true) && self.thread_ts.is_none() {
+            return Err("'reply_broadcast' requires 'thread_ts'".to_string());
+        }
+
+        if let Some(blocks) = &self.blocks {
+            validate_blocks(blocks.value(), "blocks")?;
+        }
+
+        if let Some(attachments) = &self.attachments {
+            let items = attachments
+                .value()
+                .to_array()
+                .map_err(|_| "'attachments' must be an array".to_string())?;
+            for (i, attachment) in items.enumerate() {
+                let path = format!("attachments[{i}]");
+                if attachment.to_object().is_err() {
+                    return Err(format!("'{path}' must be an object"));
+                }
+                if let Some(blocks) = attachment
+                    .to_member("blocks")
+                    .ok()
+                    .and_then(|m| m.optional())
+                {
+                    validate_blocks(blocks, &format!("{path}.blocks"))?;
+                }
+                validate_text_objects(attachment, &path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// メッセージのメンバーを `f` に書き出す。`channel` などの宛先は呼び出し元が書く。
+    pub fn write_members(&self, f: &mut JsonObjectFormatter<'_, '_, '_>) -> std::fmt::Result {
+        if let Some(text) = &self.text {
+            f.member("text", text)?;
+        }
+        if let Some(blocks) = &self.blocks {
+            f.member("blocks", blocks)?;
+        }
+        if let Some(attachments) = &self.attachments {
+            f.member("attachments", attachments)?;
+        }
+        if let Some(unfurl_links) = self.unfurl_links {
+            f.member("unfurl_links", unfurl_links)?;
+        }
+        if let Some(unfurl_media) = self.unfurl_media {
+            f.member("unfurl_media", unfurl_media)?;
+        }
+        if let Some(mrkdwn) = self.mrkdwn {
+            f.member("mrkdwn", mrkdwn)?;
+        }
+        if let Some(username) = &self.username {
+            f.member("username", username)?;
+        }
+        if let Some(icon_emoji) = &self.icon_emoji {
+            f.member("icon_emoji", icon_emoji)?;
+        }
+        if let Some(thread_ts) = &self.thread_ts {
+            f.member("thread_ts", thread_ts)?;
+        }
+        if let Some(reply_broadcast) = self.reply_broadcast {
+            f.member("reply_broadcast", reply_broadcast)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_blocks(blocks: RawJsonValue<'_, '_>, path: &str) -> Result<(), String> {
+    let items = blocks
+        .to_array()
+        .map_err(|_| format!("'{path}' must be an array"))?
+        .collect::<Vec<_>>();
+    if items.len() > MAX_BLOCKS {
+        return Err(format!(
+            "'{path}' must contain at most {MAX_BLOCKS} blocks (got {})",
+            items.len()
+        ));
+    }
+
+    for (i, block) in items.into_iter().enumerate() {
+        let block_path = format!("{path}[{i}]");
+        let has_type = block
+            .to_member("type")
+            .ok()
+            .and_then(|m| m.optional())
+            .is_some_and(|t| t.as_string_str().is_ok());
+        if !has_type {
+            return Err(format!(
+                "'{block_path}' must be an object with a string 'type'"
+            ));
+        }
+    }
+    validate_text_objects(blocks, path)
+}
+
+/// `plain_text` / `mrkdwn` のテキストオブジェクトを再帰的に探し、文字数を検証する。
+fn validate_text_objects(value: RawJsonValue<'_, '_>, path: &str) -> Result<(), String> {
+    if let Ok(members) = value.to_object() {
+        let members = members.collect::<Vec<_>>();
+        let is_text_object = members.iter().any(|(name, v)| {
+            name.to_unquoted_string_str().is_ok_and(|n| n == "type")
+                && v.to_unquoted_string_str()
+                    .is_ok_and(|t| t == "plain_text" || t == "mrkdwn")
+        });
+
+        for (name, child) in members {
+            let name = name.to_unquoted_string_str().unwrap_or_default();
+            let child_path = format!("{path}.{name}");
+            if is_text_object && name == "text" {
+                if let Ok(text) = child.to_unquoted_string_str() {
+                    let chars = text.chars().count();
+                    if chars > MAX_TEXT_OBJECT_CHARS {
+                        return Err(format!(
+                            "'{child_path}' must be at most {MAX_TEXT_OBJECT_CHARS} characters (got {chars})"
+                        ));
+                    }
+                }
+                continue;
+            }
+            validate_text_objects(child, &child_path)?;
+        }
+    } else if let Ok(items) = value.to_array() {
+        for (i, child) in items.enumerate() {
+            validate_text_objects(child, &format!("{path}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+/// mrkdwn の制御文字 (`&` `<` `>`) をエスケープする。
+pub fn escape_mrkdwn(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_BLOCKS, SlackMessage};
+    use nojson::RawJsonOwned;
+
+    fn blocks(raw: &str) -> SlackMessage {
+        SlackMessage {
+            blocks: Some(RawJsonOwned::parse(raw).expect("json")),
+            ..SlackMessage::default()
+        }
+    }
+
+    fn section(text: &str) -> String {
+        format!(
+            r#"{{"type":"section","text":{{"type":"mrkdwn","text":{}}}}}"#,
+            nojson::json(|f| f.value(text))
+        )
+    }
+
+    #[test]
+    fn message_without_body_is_rejected() {
+        assert!(SlackMessage::default().validate().is_err());
+        assert!(SlackMessage::text("hello").validate().is_ok());
+    }
+
+    #[test]
+    fn too_many_blocks_are_rejected() {
+        let raw = format!("[{}]", vec![section("x"); MAX_BLOCKS + 1].join(","));
+        let err = blocks(&raw).validate().expect_err("should fail");
+        assert!(err.contains("at most 50 blocks"), "{err}");
+
+        let raw = format!("[{}]", vec![section("x"); MAX_BLOCKS].join(","));
+        assert!(blocks(&raw).validate().is_ok());
+    }
+
+    #[test]
+    fn long_text_object_is_rejected_with_path() {
+        let raw = format!("[{},{}]", section("ok"), section(&"あ".repeat(3001)));
+        let err = blocks(&raw).validate().expect_err("should fail");
+        assert!(err.contains("'blocks[1].text.text'"), "{err}");
+    }
+
+    #[test]
+    fn long_text_in_nested_fields_is_rejected() {
+        let raw = format!(
+            r#"[{{"type":"section","fields":[{{"type":"plain_text","text":"{}"}}]}}]"#,
+            "a".repeat(3001)
+        );
+        let err = blocks(&raw).validate().expect_err("should fail");
+        assert!(err.contains("'blocks[0].fields[0].text'"), "{err}");
+    }
+
+    #[test]
+    fn reply_broadcast_requires_thread() {
+        let mut message = SlackMessage::text("deploy finished");
+        message.reply_broadcast = Some(true);
+        assert!(message.validate().is_err());
+
+        message.thread_ts = Some("1712345678.000100".to_string());
+        assert!(message.validate().is_ok());
+    }
+
+    #[test]
+    fn block_without_type_is_rejected() {
+        assert!(blocks(r#"[{"text":"x"}]"#).validate().is_err());
+        assert!(blocks(r#"{"type":"section"}"#).validate().is_err());
+    }
+
+    #[test]
+    fn write_members_passes_raw_json_through() {
+        let message = SlackMessage {
+            text: Some("fallback".to_string()),
+            blocks: Some(RawJsonOwned::parse(r#"[{"type":"divider"}]"#).expect("json")),
+            unfurl_links: Some(false),
+            icon_emoji: Some(":robot_face:".to_string()),
+            ..SlackMessage::default()
+        };
+        let json = nojson::json(|f| {
+            f.object(|f| {
+                f.member("channel", "C1")?;
+                message.write_members(f)
+            })
+        })
+        .to_string();
+        assert_eq!(
+            json,
+            r#"{"channel":"C1","text":"fallback","blocks":[{"type":"divider"}],"unfurl_links":false,"icon_emoji":":robot_face:"}"#
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::http_client::{HttpClient, HttpClientError, HttpRequest, HttpResponse, retry_after};
+
+/// 429 を受けたときに待ってから送り直す最大回数
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+/// `Retry-After` がない 429 の既定の待ち時間
+const DEFAULT_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(1);
+
+/// Slack Web API のメソッドごとの送信間隔。
+///
+/// Tier 2〜4 はそれぞれ 20 / 50 / 100 回毎分。投稿系はチャンネルごとに約1回毎秒。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MethodLimit {
+    interval: Duration,
+    per_channel: bool,
+}
+
+const TIER_2: MethodLimit = MethodLimit {
+    interval: Duration::from_secs(3),
+    per_channel: false,
+};
+const TIER_3: MethodLimit = MethodLimit {
+    interval: Duration::from_millis(1200),
+    per_channel: false,
+};
+const TIER_4: MethodLimit = MethodLimit {
+    interval: Duration::from_millis(600),
+    per_channel: false,
+};
+const PER_CHANNEL_POST: MethodLimit = MethodLimit {
+    interval: Duration::from_secs(1),
+    per_channel: true,
+};
+
+fn method_limit(method: &str) -> MethodLimit {
+    match method {
+        "chat.postMessage" | "chat.postEphemeral" | "chat.scheduleMessage" => PER_CHANNEL_POST,
+        "files.getUploadURLExternal"
+        | "files.completeUploadExternal"
+        | "files.info"
+        | "users.info" => TIER_4,
+        "conversations.list" | "pins.add" | "pins.remove" | "pins.list" | "bookmarks.add"
+        | "bookmarks.remove" => TIER_2,
+        _ => TIER_3,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct BucketKey {
+    method: String,
+    channel: Option<String>,
+}
+
+impl BucketKey {
+    fn new(method: &str, channel: Option<&str>, limit: MethodLimit) -> Self {
+        Self {
+            method: method.to_string(),
+            channel: channel
+                .filter(|_| limit.per_channel)
+                .map(ToString::to_string),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    next_slot: Instant,
+}
+
+/// Slack API 呼び出しをメソッド (投稿系はチャンネルも) ごとのキューに並べ、
+/// Tier の上限を超えないよう間隔を空けて送信する。
+///
+/// 429 を受けるとそのバケット全体を `Retry-After` の間止め、待機後に送り直す。
+#[derive(Clone, Default)]
+pub struct SlackRateLimiter {
+    buckets: Arc<Mutex<HashMap<BucketKey, Bucket>>>,
+}
+
+impl SlackRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `channel` には解決済みのチャンネル ID を渡す。`#name` と ID で別のバケットにならないようにするため。
+    pub async fn send(
+        &self,
+        client: &HttpClient,
+        method: &str,
+        channel: Option<&str>,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, HttpClientError> {
+        debug_assert!(
+            channel.is_none_or(|channel| !channel.starts_with('#')),
+            "rate limiter must be keyed on a resolved channel ID"
+        );
+        let limit = method_limit(method);
+        let key = BucketKey::new(method, channel, limit);
+        let mut rate_limited = 0;
+
+        loop {
+            let slot = self.reserve(&key, limit, Instant::now());
+            let wait = slot.saturating_duration_since(Instant::now());
+            if !wait.is_zero() {
+                debug!(
+                    api_method = %method,
+                    channel = ?key.channel,
+                    wait_ms = wait.as_millis() as u64,
+                    "Waiting for Slack rate limit slot"
+                );
+                tokio::time::sleep_until(slot).await;
+            }
+
+            let response = client
+                .send_without_rate_limit_retry(request.clone())
+                .await?;
+            if response.status_code != 429 || rate_limited >= MAX_RATE_LIMIT_RETRIES {
+                return Ok(response);
+            }
+
+            rate_limited += 1;
+            let pause = retry_after(&response.headers).unwrap_or(DEFAULT_RATE_LIMIT_PAUSE);
+            warn!(
+                api_method = %method,
+                channel = ?key.channel,
+                attempt = rate_limited,
+                pause_ms = pause.as_millis() as u64,
+                "Slack API rate limited, pausing bucket"
+            );
+            self.pause(&key, Instant::now() + pause);
+        }
+    }
+
+    /// 次に送信してよい時刻を予約して返す。予約順に `interval` ずつ後ろへずれていく。
+    fn reserve(&self, key: &BucketKey, limit: MethodLimit, now: Instant) -> Instant {
+        let mut buckets = self.lock_buckets();
+        let bucket = buckets
+            .entry(key.clone())
+            .or_insert(Bucket { next_slot: now });
+
+        let slot = bucket.next_slot.max(now);
+        bucket.next_slot = slot + limit.interval;
+        slot
+    }
+
+    /// `until` まではバケットの予約を受け付けない。予約済みの送信は影響を受けない。
+    fn pause(&self, key: &BucketKey, until: Instant) {
+        let mut buckets = self.lock_buckets();
+        if let Some(bucket) = buckets.get_mut(key) {
+            bucket.next_slot = bucket.next_slot.max(until);
+        }
+    }
+
+    fn lock_buckets(&self) -> std::sync::MutexGuard<'_, HashMap<BucketKey, Bucket>> {
+        self.buckets.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BucketKey, PER_CHANNEL_POST, SlackRateLimiter, TIER_3, method_limit};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[test]
+    fn post_message_is_limited_per_channel() {
+        let limiter = SlackRateLimiter::new();
+        let limit = method_limit("chat.postMessage");
+        assert_eq!(limit, PER_CHANNEL_POST);
+
+        let now = Instant::now();
+        let alerts = BucketKey::new("chat.postMessage", Some("C0ALERTS"), limit);
+        let deploys = BucketKey::new("chat.postMessage", Some("C0DEPLOYS"), limit);
+
+        assert_eq!(limiter.reserve(&alerts, limit, now), now);
+        assert_eq!(
+            limiter.reserve(&alerts, limit, now),
+            now + Duration::from_secs(1)
+        );
+        assert_eq!(limiter.reserve(&deploys, limit, now), now);
+    }
+
+    #[test]
+    fn workspace_methods_ignore_channel() {
+        let limit = method_limit("conversations.history");
+        assert_eq!(limit, TIER_3);
+        assert_eq!(
+            BucketKey::new("conversations.history", Some("C1"), limit),
+            BucketKey::new("conversations.history", Some("C2"), limit)
+        );
+    }
+
+    #[test]
+    fn pause_delays_following_reservations() {
+        let limiter = SlackRateLimiter::new();
+        let limit = method_limit("chat.postMessage");
+        let key = BucketKey::new("chat.postMessage", Some("C1"), limit);
+        let now = Instant::now();
+
+        limiter.reserve(&key, limit, now);
+        limiter.pause(&key, now + Duration::from_secs(30));
+
+        assert_eq!(
+            limiter.reserve(&key, limit, now),
+            now + Duration::from_secs(30)
+        );
+        assert_eq!(
+            limiter.reserve(&key, limit, now),
+            now + Duration::from_secs(31)
+        );
+    }
+
+    #[test]
+    fn idle_bucket_does_not_accumulate_burst() {
+        let limiter = SlackRateLimiter::new();
+        let limit = method_limit("chat.postMessage");
+        let key = BucketKey::new("chat.postMessage", Some("C1"), limit);
+        let now = Instant::now();
+
+        limiter.reserve(&key, limit, now);
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(&key, limit, later), later);
+        assert_eq!(
+            limiter.reserve(&key, limit, later),
+            later + Duration::from_secs(1)
+        );
+    }
+}
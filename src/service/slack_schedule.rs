@@ -0,0 +1,243 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `chat.scheduleMessage` で予約できる最も先の時刻 (現在から 120 日)
+pub const MAX_SCHEDULE_AHEAD_SECS: i64 = 120 * 24 * 60 * 60;
+
+/// 予約済みメッセージ。`chat.scheduledMessages.list` の要素に対応する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledMessage {
+    pub id: String,
+    pub channel: String,
+    pub post_at: i64,
+    pub date_created: Option<i64>,
+    pub text: Option<String>,
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// `post_at` が未来かつ 120 日以内であることを検証する。エラーはそのまま 400 の detail に使う。
+pub fn validate_post_at(post_at: i64, now: i64) -> Result<i64, String> {
+    if post_at <= now {
+        return Err(format!(
+            "'post_at' must be in the future (got {post_at}, now {now})"
+        ));
+    }
+    if post_at - now > MAX_SCHEDULE_AHEAD_SECS {
+        return Err(format!(
+            "'post_at' must be within 120 days from now (got {post_at}, now {now})"
+        ));
+    }
+    Ok(post_at)
+}
+
+/// `2026-10-17T09:30:00+09:00` 形式の RFC 3339 タイムスタンプを Unix 秒に変換する。小数秒は切り捨てる。
+pub fn parse_rfc3339(value: &str) -> Option<i64> {
+    // 以降はバイト位置で切り出すため、文字境界の途中で切らないよう ASCII 以外は先に弾く
+    if !value.is_ascii() {
+        return None;
+    }
+    let bytes = value.as_bytes();
+    if bytes.len() < 20
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || !matches!(bytes[10], b'T' | b't' | b' ')
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+    {
+        return None;
+    }
+
+    let year = digits(&value[0..4])?;
+    let month = digits(&value[5..7])?;
+    let day = digits(&value[8..10])?;
+    let hour = digits(&value[11..13])?;
+    let minute = digits(&value[14..16])?;
+    let second = digits(&value[17..19])?;
+    if !(1..=12).contains(&month)
+        || day == 0
+        || day > days_in_month(year, month)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut rest = &value[19..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if len == 0 {
+            return None;
+        }
+        rest = &fraction[len..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let (sign, hhmm) = match rest.as_bytes().first()? {
+                b'+' => (1, &rest[1..]),
+                b'-' => (-1, &rest[1..]),
+                _ => return None,
+            };
+            let (hh, mm) = hhmm.split_once(':')?;
+            if hh.len() != 2 || mm.len() != 2 {
+                return None;
+            }
+            let (hh, mm) = (digits(hh)?, digits(mm)?);
+            if hh > 23 || mm > 59 {
+                return None;
+            }
+            sign * (hh * 3600 + mm * 60)
+        }
+    };
+
+    let days = days_from_civil(year, month, day);
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second - offset)
+}
+
+fn digits(value: &str) -> Option<i64> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Unix 秒の UTC の日付を `2026-10-17` 形式で返す。
+pub fn format_utc_date(secs: i64) -> String {
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// `days_from_civil` の逆変換
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+/// 1970-01-01 からの日数 (proleptic Gregorian)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MAX_SCHEDULE_AHEAD_SECS, civil_from_days, format_utc_date, parse_rfc3339, validate_post_at,
+    };
+    use proptest::{prelude::ProptestConfig, prop_assert_eq, proptest};
+
+    #[test]
+    fn parses_utc_and_offsets() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_rfc3339("2026-10-17T00:00:00Z"), Some(1_792_195_200));
+        assert_eq!(
+            parse_rfc3339("2026-10-17T09:00:00+09:00"),
+            Some(1_792_195_200)
+        );
+        assert_eq!(
+            parse_rfc3339("2026-10-16T19:00:00.250-05:00"),
+            Some(1_792_195_200)
+        );
+        assert_eq!(parse_rfc3339("2024-02-29T12:00:00Z"), Some(1_709_208_000));
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        for value in [
+            "2026-10-17",
+            "2026-10-17T09:00:00",
+            "2026-13-01T00:00:00Z",
+            "2025-02-29T00:00:00Z",
+            "2026-10-17T24:00:00Z",
+            "2026-10-17T09:00:00+0900",
+            "2026-10-17T09:00:00.Z",
+            "1792195200",
+        ] {
+            assert_eq!(parse_rfc3339(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn rejects_multibyte_characters_without_panicking() {
+        for value in [
+            "2026-10-17T09:00:0\u{e9}0Z",
+            "2026-10-17T09:00:\u{3042}Z",
+            "2026-10-17T09:0\u{e9}:00Z",
+            "2026-10-17T09:00:00\u{e9}Z",
+            "2026-10-17T09:00:00+\u{e9}:00",
+        ] {
+            assert_eq!(parse_rfc3339(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn formats_utc_dates() {
+        assert_eq!(format_utc_date(0), "1970-01-01");
+        assert_eq!(format_utc_date(1_792_195_200 + 86_399), "2026-10-17");
+        assert_eq!(format_utc_date(1_709_208_000), "2024-02-29");
+    }
+
+    #[test]
+    fn post_at_must_be_future_and_within_window() {
+        let now = 1_792_195_200;
+        assert!(validate_post_at(now, now).is_err());
+        assert!(validate_post_at(now + 60, now).is_ok());
+        assert!(validate_post_at(now + MAX_SCHEDULE_AHEAD_SECS, now).is_ok());
+        assert!(validate_post_at(now + MAX_SCHEDULE_AHEAD_SECS + 1, now).is_err());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+
+        #[test]
+        fn offset_form_matches_utc(secs in 0_i64..4_102_444_800, offset_min in -1439_i64..=1439) {
+            prop_assert_eq!(parse_rfc3339(&format_local(secs, 0)), Some(secs));
+            prop_assert_eq!(parse_rfc3339(&format_local(secs, offset_min)), Some(secs));
+        }
+    }
+
+    /// `secs` を `offset_min` 分ずらしたローカル時刻として書式化する。
+    fn format_local(secs: i64, offset_min: i64) -> String {
+        let local = secs + offset_min * 60;
+        let (y, m, d) = civil_from_days(local.div_euclid(86_400));
+        let rem = local.rem_euclid(86_400);
+        let time = format!("{:02}:{:02}:{:02}", rem / 3600, rem % 3600 / 60, rem % 60);
+        let zone = if offset_min == 0 {
+            "Z".to_string()
+        } else {
+            let sign = if offset_min < 0 { '-' } else { '+' };
+            let abs = offset_min.abs();
+            format!("{sign}{:02}:{:02}", abs / 60, abs % 60)
+        };
+        format!("{y:04}-{m:02}-{d:02}T{time}{zone}")
+    }
+}
@@ -1,8 +1,45 @@
-use shiguredo_http11::uri::percent_encode_query;
-use std::error::Error as StdError;
+use shiguredo_http11::uri::{Uri, percent_encode_query};
+use std::{collections::HashMap, error::Error as StdError, fmt, future::Future, sync::Arc};
+use tokio::{sync::Semaphore, task::JoinSet, time::Instant};
 use tracing::{debug, error, info, instrument, warn};
 
-use crate::http_client::{HttpClient, HttpRequest};
+use crate::{
+    http_client::{HttpClient, HttpClientError, HttpRequest, StreamedBody},
+    service::{
+        slack_channels::{ChannelDirectory, ChannelLookup, normalize_channel_name},
+        slack_export::{ExportedMessage, HISTORY_PAGE_LIMIT, HistoryRange, slack_ts},
+        slack_items::{self, Bookmark, NewBookmark, PinnedMessage, Reaction},
+        slack_message::SlackMessage,
+        slack_rate_limiter::SlackRateLimiter,
+        slack_schedule::ScheduledMessage,
+    },
+};
+
+/// Slack が `ok: false` で返したエラー。表示はエラーコード (`channel_not_found` など) のみ。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlackApiError {
+    pub code: String,
+}
+
+impl SlackApiError {
+    pub fn new(code: impl Into<String>) -> Self {
+        Self { code: code.into() }
+    }
+}
+
+impl fmt::Display for SlackApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+impl StdError for SlackApiError {}
+
+/// `e` が Slack の `code` エラーなら true
+pub fn is_slack_error(e: &(dyn StdError + 'static), code: &str) -> bool {
+    e.downcast_ref::<SlackApiError>()
+        .is_some_and(|e| e.code == code)
+}
 
 fn get_required_string(
     root: nojson::RawJsonValue<'_, '_>,
@@ -30,51 +67,117 @@ fn get_required_bool(
     Ok(converted)
 }
 
+fn get_optional_i64(root: nojson::RawJsonValue<'_, '_>, name: &str) -> Option<i64> {
+    let value = root.to_member(name).ok()?.optional()?;
+    i64::try_from(value).ok()
+}
+
 fn get_optional_string(root: nojson::RawJsonValue<'_, '_>, name: &str) -> Option<String> {
     let value = root.to_member(name).ok()?.optional()?;
     String::try_from(value).ok()
 }
 
-#[instrument(skip(client, slack_bot_token, text), fields(channel = %channel))]
-pub async fn post_message(
+/// `channel` と `ts` の組で特定されるメッセージ。`ts` を `thread_ts` に渡すとスレッドに返信できる。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageRef {
+    pub channel: String,
+    pub ts: String,
+}
+
+impl MessageRef {
+    fn from_response(root: nojson::RawJsonValue<'_, '_>) -> Result<Self, Box<dyn StdError>> {
+        Ok(Self {
+            channel: get_required_string(root, "channel")?,
+            ts: get_required_string(root, "ts")?,
+        })
+    }
+}
+
+/// JSON ボディで Slack Web API を呼び出し、`ok` を確認したレスポンス本文を返す。
+/// `ok: false` の場合は Slack の `error` をメッセージにしたエラーを返す。
+async fn call_json_api(
     client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
     slack_bot_token: &str,
     slack_api_base_url: &str,
-    channel: &str,
-    text: &str,
+    api_method: &str,
+    channel: Option<&str>,
+    payload: String,
 ) -> Result<String, Box<dyn StdError>> {
-    let url = format!("{}/chat.postMessage", slack_api_base_url);
-
-    let payload = nojson::json(|f| {
-        f.object(|f| {
-            f.member("channel", channel)?;
-            f.member("text", text)
-        })
-    });
-
     debug!(
-        api_endpoint = "chat.postMessage",
-        channel = %channel,
+        api_endpoint = api_method,
+        channel = ?channel,
         "Calling Slack API"
     );
 
-    let response = client
-        .send(HttpRequest {
-            method: "POST".to_string(),
-            url,
-            headers: vec![
-                (
+    let response = rate_limiter
+        .send(
+            client,
+            api_method,
+            channel,
+            HttpRequest {
+                method: "POST".to_string(),
+                url: format!("{slack_api_base_url}/{api_method}"),
+                headers: vec![
+                    (
+                        "Authorization".to_string(),
+                        format!("Bearer {slack_bot_token}"),
+                    ),
+                    (
+                        "Content-Type".to_string(),
+                        "application/json; charset=utf-8".to_string(),
+                    ),
+                ],
+                body: payload.into_bytes(),
+            },
+        )
+        .await?;
+
+    check_ok_response(api_method, channel, response.body)
+}
+
+/// クエリパラメータのみを受け付ける参照系メソッドを GET で呼び出す。
+async fn call_query_api(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    api_method: &str,
+    query: &[(&str, &str)],
+) -> Result<String, Box<dyn StdError>> {
+    debug!(api_endpoint = api_method, "Calling Slack API");
+
+    let query = query
+        .iter()
+        .map(|(name, value)| format!("{name}={}", percent_encode_query(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let response = rate_limiter
+        .send(
+            client,
+            api_method,
+            None,
+            HttpRequest {
+                method: "GET".to_string(),
+                url: format!("{slack_api_base_url}/{api_method}?{query}"),
+                headers: vec![(
                     "Authorization".to_string(),
                     format!("Bearer {slack_bot_token}"),
-                ),
-                ("Content-Type".to_string(), "application/json".to_string()),
-            ],
-            body: payload.to_string().into_bytes(),
-        })
+                )],
+                body: Vec::new(),
+            },
+        )
         .await?;
 
-    let response = String::from_utf8(response.body)
-        .map_err(|e| Box::<dyn StdError>::from(std::io::Error::other(e.to_string())))?;
+    check_ok_response(api_method, None, response.body)
+}
+
+fn check_ok_response(
+    api_method: &str,
+    channel: Option<&str>,
+    body: Vec<u8>,
+) -> Result<String, Box<dyn StdError>> {
+    let response = String::from_utf8(body).map_err(Box::<dyn StdError>::from)?;
 
     let parsed = nojson::RawJson::parse(&response)?;
     let root = parsed.value();
@@ -84,83 +187,793 @@ pub async fn post_message(
         let error_message =
             get_optional_string(root, "error").unwrap_or_else(|| "unknown_error".to_string());
         warn!(
+            api_endpoint = api_method,
             error = %error_message,
-            channel = %channel,
+            channel = ?channel,
             "Slack API returned error response"
         );
-        return Err(Box::new(std::io::Error::other(error_message)));
+        return Err(Box::new(SlackApiError::new(error_message)));
     }
 
+    Ok(response)
+}
+
+#[instrument(skip(client, rate_limiter, slack_bot_token, message), fields(channel = %channel))]
+pub async fn post_message(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    message: &SlackMessage,
+) -> Result<MessageRef, Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", channel)?;
+            message.write_members(f)
+        })
+    });
+
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "chat.postMessage",
+        Some(channel),
+        payload.to_string(),
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    let posted = MessageRef::from_response(parsed.value())?;
+
     debug!(
-        channel = %channel,
+        channel = %posted.channel,
+        ts = %posted.ts,
         "Slack API call successful"
     );
 
-    Ok(response)
+    Ok(posted)
 }
 
-#[instrument(skip(client, slack_bot_token, file_data), fields(file_name = %file_name, file_size = file_data.len()))]
-pub async fn upload_file(
+/// `conversations.list` を最後のページまでたどり、チャンネル名→ID の対応表を作る。
+/// 公開チャンネルと、ボットが参加しているプライベートチャンネルが対象。
+#[instrument(skip(client, rate_limiter, slack_bot_token))]
+pub async fn list_channel_ids(
     client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
     slack_bot_token: &str,
     slack_api_base_url: &str,
-    file_name: &str,
-    file_data: &[u8],
-) -> Result<(String, String), Box<dyn StdError>> {
-    let url = format!("{}/files.getUploadURLExternal", slack_api_base_url);
+) -> Result<HashMap<String, String>, Box<dyn StdError>> {
+    let mut ids_by_name = HashMap::new();
+    let mut cursor = String::new();
+    loop {
+        let response = call_query_api(
+            client,
+            rate_limiter,
+            slack_bot_token,
+            slack_api_base_url,
+            "conversations.list",
+            &[
+                ("types", "public_channel,private_channel"),
+                ("exclude_archived", "true"),
+                ("limit", "1000"),
+                ("cursor", &cursor),
+            ],
+        )
+        .await?;
+        let parsed = nojson::RawJson::parse(&response)?;
+        let root = parsed.value();
+        for channel in root.to_member("channels")?.required()?.to_array()? {
+            let id = get_required_string(channel, "id")?;
+            let name = get_required_string(channel, "name")?;
+            ids_by_name.insert(normalize_channel_name(&name), id);
+        }
+
+        cursor = root
+            .to_member("response_metadata")
+            .ok()
+            .and_then(|m| m.optional())
+            .and_then(|metadata| get_optional_string(metadata, "next_cursor"))
+            .unwrap_or_default();
+        if cursor.is_empty() {
+            break;
+        }
+    }
 
     debug!(
-        api_endpoint = "files.getUploadURLExternal",
-        file_name = %file_name,
-        file_size = file_data.len(),
-        "Getting upload URL from Slack"
+        channels = ids_by_name.len(),
+        "Fetched Slack channel directory"
     );
+    Ok(ids_by_name)
+}
 
-    let response = client
-        .send(HttpRequest {
-            method: "GET".to_string(),
-            url: format!(
-                "{}?filename={}&length={}",
-                url,
-                percent_encode_query(file_name),
-                file_data.len()
-            ),
-            headers: vec![(
-                "Authorization".to_string(),
-                format!("Bearer {slack_bot_token}"),
-            )],
-            body: Vec::new(),
+/// `#name` をチャンネル ID に解決する。`#` で始まらない値は ID としてそのまま返す。
+///
+/// キャッシュが無い・期限切れ、または `force_refresh` の場合は `conversations.list` を取り直す。
+/// 見つからない場合は `channel_not_found` の `SlackApiError` を返す。
+pub async fn resolve_channel(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    directory: &ChannelDirectory,
+    channel: &str,
+    force_refresh: bool,
+) -> Result<String, Box<dyn StdError>> {
+    if !channel.starts_with('#') {
+        return Ok(channel.to_string());
+    }
+    let name = normalize_channel_name(channel);
+    let requested_at = Instant::now();
+
+    if !force_refresh {
+        match directory.lookup(&name, requested_at) {
+            ChannelLookup::Found(id) => return Ok(id),
+            ChannelLookup::NotFound => {
+                return Err(Box::new(SlackApiError::new("channel_not_found")));
+            }
+            ChannelLookup::Refresh => {}
+        }
+    }
+
+    let _refresh = directory.lock_refresh().await;
+    // 待っている間に別のリクエストが取り直していれば、その結果を使う
+    if !directory.refreshed_since(requested_at) {
+        let ids_by_name =
+            list_channel_ids(client, rate_limiter, slack_bot_token, slack_api_base_url).await?;
+        directory.replace(ids_by_name, Instant::now());
+    }
+
+    match directory.lookup(&name, Instant::now()) {
+        ChannelLookup::Found(id) => {
+            debug!(channel = %channel, channel_id = %id, "Resolved Slack channel name");
+            Ok(id)
+        }
+        _ => Err(Box::new(SlackApiError::new("channel_not_found"))),
+    }
+}
+
+/// `channel` を解決して `call` を実行する。`#name` から解決した ID で `channel_not_found` が返った場合は
+/// 名前が付け替えられた可能性があるため、一覧を取り直して1度だけ再実行する。
+pub async fn with_resolved_channel<T, F, Fut>(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    directory: &ChannelDirectory,
+    channel: &str,
+    call: F,
+) -> Result<T, Box<dyn StdError>>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn StdError>>>,
+{
+    let resolved = resolve_channel(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        directory,
+        channel,
+        false,
+    )
+    .await?;
+
+    match call(resolved.clone()).await {
+        Err(e) if channel.starts_with('#') && is_slack_error(e.as_ref(), "channel_not_found") => {}
+        result => return result,
+    }
+
+    let refreshed = resolve_channel(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        directory,
+        channel,
+        true,
+    )
+    .await?;
+    if refreshed == resolved {
+        return Err(Box::new(SlackApiError::new("channel_not_found")));
+    }
+    warn!(
+        channel = %channel,
+        stale_id = %resolved,
+        channel_id = %refreshed,
+        "Slack channel ID changed, retrying with refreshed directory"
+    );
+    call(refreshed).await
+}
+
+/// `chat.postEphemeral` でチャンネル内の `user` にだけ見えるメッセージを送り、`message_ts` を返す。
+#[instrument(skip(client, rate_limiter, slack_bot_token, message), fields(channel = %channel, user = %user))]
+pub async fn post_ephemeral(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    user: &str,
+    message: &SlackMessage,
+) -> Result<String, Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", channel)?;
+            f.member("user", user)?;
+            message.write_members(f)
         })
-        .await?;
+    });
+
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "chat.postEphemeral",
+        Some(channel),
+        payload.to_string(),
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    get_required_string(parsed.value(), "message_ts")
+}
+
+/// `users.lookupByEmail` でメールアドレスからユーザー ID を引く。
+#[instrument(skip(client, rate_limiter, slack_bot_token, email))]
+pub async fn lookup_user_by_email(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    email: &str,
+) -> Result<String, Box<dyn StdError>> {
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "users.lookupByEmail",
+        &[("email", email)],
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    let user = parsed.value().to_member("user")?.required()?;
+    get_required_string(user, "id")
+}
+
+/// `conversations.open` でユーザーとの DM を開き、その channel ID を返す。既に開いている場合も同じ ID が返る。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(user = %user))]
+pub async fn open_direct_message(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    user: &str,
+) -> Result<String, Box<dyn StdError>> {
+    let payload = nojson::json(|f| f.object(|f| f.member("users", user)));
+
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "conversations.open",
+        None,
+        payload.to_string(),
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    let channel = parsed.value().to_member("channel")?.required()?;
+    get_required_string(channel, "id")
+}
+
+/// `chat.update` で投稿済みメッセージの本文を置き換える。
+#[instrument(skip(client, rate_limiter, slack_bot_token, message), fields(channel = %target.channel, ts = %target.ts))]
+pub async fn update_message(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    target: &MessageRef,
+    message: &SlackMessage,
+) -> Result<MessageRef, Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &target.channel)?;
+            f.member("ts", &target.ts)?;
+            message.write_members(f)
+        })
+    });
+
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "chat.update",
+        Some(&target.channel),
+        payload.to_string(),
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    MessageRef::from_response(parsed.value())
+}
 
-    let response = String::from_utf8(response.body)
-        .map_err(|e| Box::<dyn StdError>::from(std::io::Error::other(e.to_string())))?;
+/// `chat.delete` で投稿済みメッセージを削除する。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %target.channel, ts = %target.ts))]
+pub async fn delete_message(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    target: &MessageRef,
+) -> Result<MessageRef, Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &target.channel)?;
+            f.member("ts", &target.ts)
+        })
+    });
+
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "chat.delete",
+        Some(&target.channel),
+        payload.to_string(),
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    MessageRef::from_response(parsed.value())
+}
+
+/// `chat.scheduleMessage` で `post_at` (Unix 秒) に投稿を予約し、`scheduled_message_id` を返す。
+#[instrument(skip(client, rate_limiter, slack_bot_token, message), fields(channel = %channel, post_at = post_at))]
+pub async fn schedule_message(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    post_at: i64,
+    message: &SlackMessage,
+) -> Result<String, Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", channel)?;
+            f.member("post_at", post_at)?;
+            message.write_members(f)
+        })
+    });
 
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "chat.scheduleMessage",
+        Some(channel),
+        payload.to_string(),
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    get_required_string(parsed.value(), "scheduled_message_id")
+}
+
+/// `chat.scheduledMessages.list` でチャンネルの予約済みメッセージと次ページのカーソルを返す。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %channel))]
+pub async fn list_scheduled_messages(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    cursor: Option<&str>,
+    limit: Option<u32>,
+) -> Result<(Vec<ScheduledMessage>, Option<String>), Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", channel)?;
+            if let Some(cursor) = cursor {
+                f.member("cursor", cursor)?;
+            }
+            if let Some(limit) = limit {
+                f.member("limit", limit)?;
+            }
+            Ok(())
+        })
+    });
+
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "chat.scheduledMessages.list",
+        Some(channel),
+        payload.to_string(),
+    )
+    .await?;
     let parsed = nojson::RawJson::parse(&response)?;
     let root = parsed.value();
-    let ok = get_required_bool(root, "ok")?;
 
-    if !ok {
-        let error_message =
-            get_optional_string(root, "error").unwrap_or_else(|| "unknown_error".to_string());
-        error!(
-            error = %error_message,
-            file_name = %file_name,
-            "Failed to get upload URL from Slack"
-        );
-        return Err(Box::new(std::io::Error::other(error_message)));
+    let mut messages = Vec::new();
+    if let Some(items) = root.to_member("scheduled_messages")?.optional() {
+        for item in items.to_array()? {
+            messages.push(ScheduledMessage {
+                id: get_required_string(item, "id")?,
+                channel: get_optional_string(item, "channel_id")
+                    .unwrap_or_else(|| channel.to_string()),
+                post_at: i64::try_from(item.to_member("post_at")?.required()?)?,
+                date_created: get_optional_i64(item, "date_created"),
+                text: get_optional_string(item, "text"),
+            });
+        }
     }
+    let next_cursor = root
+        .to_member("response_metadata")
+        .ok()
+        .and_then(|m| m.optional())
+        .and_then(|metadata| get_optional_string(metadata, "next_cursor"))
+        .filter(|cursor| !cursor.is_empty());
 
-    let upload_url = get_required_string(root, "upload_url")?;
-    let file_id = get_required_string(root, "file_id")?;
+    Ok((messages, next_cursor))
+}
+
+/// `chat.deleteScheduledMessage` で予約を取り消す。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %channel, scheduled_message_id = %scheduled_message_id))]
+pub async fn delete_scheduled_message(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    scheduled_message_id: &str,
+) -> Result<(), Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", channel)?;
+            f.member("scheduled_message_id", scheduled_message_id)
+        })
+    });
+
+    call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "chat.deleteScheduledMessage",
+        Some(channel),
+        payload.to_string(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// `reactions.add` でメッセージにリアクションを付ける。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %target.channel, ts = %target.ts, name = %name))]
+pub async fn add_reaction(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    target: &MessageRef,
+    name: &str,
+) -> Result<(), Box<dyn StdError>> {
+    call_item_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "reactions.add",
+        target,
+        Some(name),
+    )
+    .await
+}
+
+/// `reactions.remove` でボットが付けたリアクションを外す。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %target.channel, ts = %target.ts, name = %name))]
+pub async fn remove_reaction(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    target: &MessageRef,
+    name: &str,
+) -> Result<(), Box<dyn StdError>> {
+    call_item_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "reactions.remove",
+        target,
+        Some(name),
+    )
+    .await
+}
+
+/// `reactions.get` でメッセージのリアクションを取得する。`full=true` で全ユーザーを返させる。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %target.channel, ts = %target.ts))]
+pub async fn get_reactions(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    target: &MessageRef,
+) -> Result<Vec<Reaction>, Box<dyn StdError>> {
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "reactions.get",
+        &[
+            ("channel", &target.channel),
+            ("timestamp", &target.ts),
+            ("full", "true"),
+        ],
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    let message = parsed.value().to_member("message")?.required()?;
+    Ok(slack_items::parse_reactions(message)?)
+}
+
+/// `pins.add` でメッセージをチャンネルにピン留めする。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %target.channel, ts = %target.ts))]
+pub async fn add_pin(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    target: &MessageRef,
+) -> Result<(), Box<dyn StdError>> {
+    call_item_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "pins.add",
+        target,
+        None,
+    )
+    .await
+}
+
+/// `pins.remove` でピン留めを外す。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %target.channel, ts = %target.ts))]
+pub async fn remove_pin(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    target: &MessageRef,
+) -> Result<(), Box<dyn StdError>> {
+    call_item_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "pins.remove",
+        target,
+        None,
+    )
+    .await
+}
+
+/// `reactions.*` / `pins.*` のうち、対象メッセージ (と絵文字名) だけを渡して結果を使わないメソッドを呼ぶ。
+async fn call_item_api(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    api_method: &str,
+    target: &MessageRef,
+    name: Option<&str>,
+) -> Result<(), Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel", &target.channel)?;
+            f.member("timestamp", &target.ts)?;
+            if let Some(name) = name {
+                f.member("name", name)?;
+            }
+            Ok(())
+        })
+    });
+
+    call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        api_method,
+        Some(&target.channel),
+        payload.to_string(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// `pins.list` でチャンネルのピン留めを取得する。メッセージ以外 (ファイルなど) は含めない。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %channel))]
+pub async fn list_pins(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+) -> Result<Vec<PinnedMessage>, Box<dyn StdError>> {
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "pins.list",
+        &[("channel", channel)],
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+
+    let mut pins = Vec::new();
+    if let Some(items) = parsed.value().to_member("items")?.optional() {
+        for item in items.to_array()? {
+            let Some(message) = item.to_member("message")?.optional() else {
+                continue;
+            };
+            pins.push(PinnedMessage {
+                ts: get_required_string(message, "ts")?,
+                text: get_optional_string(message, "text"),
+                user: get_optional_string(message, "user"),
+                created: get_optional_i64(item, "created"),
+                created_by: get_optional_string(item, "created_by"),
+            });
+        }
+    }
+    Ok(pins)
+}
+
+fn parse_bookmark(item: nojson::RawJsonValue<'_, '_>) -> Result<Bookmark, Box<dyn StdError>> {
+    Ok(Bookmark {
+        id: get_required_string(item, "id")?,
+        title: get_optional_string(item, "title").unwrap_or_default(),
+        link: get_optional_string(item, "link").filter(|link| !link.is_empty()),
+        emoji: get_optional_string(item, "emoji").filter(|emoji| !emoji.is_empty()),
+        date_created: get_optional_i64(item, "date_created"),
+    })
+}
+
+/// `bookmarks.add` でチャンネルにリンクのブックマークを追加する。
+#[instrument(skip(client, rate_limiter, slack_bot_token, bookmark), fields(channel = %channel, title = %bookmark.title))]
+pub async fn add_bookmark(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    bookmark: &NewBookmark,
+) -> Result<Bookmark, Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel_id", channel)?;
+            f.member("title", &bookmark.title)?;
+            f.member("type", "link")?;
+            f.member("link", &bookmark.link)?;
+            if let Some(emoji) = &bookmark.emoji {
+                f.member("emoji", emoji)?;
+            }
+            Ok(())
+        })
+    });
+
+    let response = call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "bookmarks.add",
+        Some(channel),
+        payload.to_string(),
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    parse_bookmark(parsed.value().to_member("bookmark")?.required()?)
+}
+
+/// `bookmarks.list` でチャンネルのブックマークを取得する。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %channel))]
+pub async fn list_bookmarks(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+) -> Result<Vec<Bookmark>, Box<dyn StdError>> {
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "bookmarks.list",
+        &[("channel_id", channel)],
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+
+    let mut bookmarks = Vec::new();
+    if let Some(items) = parsed.value().to_member("bookmarks")?.optional() {
+        for item in items.to_array()? {
+            bookmarks.push(parse_bookmark(item)?);
+        }
+    }
+    Ok(bookmarks)
+}
+
+/// `bookmarks.remove` でブックマークを削除する。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %channel, bookmark_id = %bookmark_id))]
+pub async fn remove_bookmark(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    bookmark_id: &str,
+) -> Result<(), Box<dyn StdError>> {
+    let payload = nojson::json(|f| {
+        f.object(|f| {
+            f.member("channel_id", channel)?;
+            f.member("bookmark_id", bookmark_id)
+        })
+    });
+
+    call_json_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "bookmarks.remove",
+        Some(channel),
+        payload.to_string(),
+    )
+    .await?;
+    Ok(())
+}
+
+#[instrument(skip(client, rate_limiter, slack_bot_token, file_data), fields(file_name = %file_name, file_size = file_data.len()))]
+pub async fn upload_file(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    file_name: &str,
+    file_data: &[u8],
+    alt_text: Option<&str>,
+) -> Result<(String, String), Box<dyn StdError>> {
+    let (upload_url, file_id) = get_upload_url(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        file_name,
+        file_data.len(),
+        alt_text,
+    )
+    .await?;
 
     debug!(
         file_id = %file_id,
         "Uploading file content to Slack"
     );
 
+    // アップロード URL はファイルごとに発行され、同じ内容を送り直しても結果は変わらない
     client
-        .send(HttpRequest {
+        .send_idempotent(HttpRequest {
             method: "POST".to_string(),
             url: upload_url.clone(),
             headers: vec![(
@@ -179,18 +992,270 @@ pub async fn upload_file(
     Ok((file_id, upload_url))
 }
 
-#[instrument(skip(client, token, file_data), fields(file_name = %file_name, channel = %channel, file_size = file_data.len()))]
+/// `body` を読み進めながらアップロードし、ファイル ID を返す。本文を送り直せないため再試行しない。
+#[instrument(skip(client, rate_limiter, slack_bot_token, body), fields(file_name = %file_name, file_size = body.length))]
+pub async fn upload_file_stream(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    file_name: &str,
+    body: StreamedBody<'_>,
+) -> Result<String, Box<dyn StdError>> {
+    let (upload_url, file_id) = get_upload_url(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        file_name,
+        body.length,
+        None,
+    )
+    .await?;
+
+    debug!(
+        file_id = %file_id,
+        "Streaming file content to Slack"
+    );
+
+    let response = client
+        .send_streamed_body(
+            HttpRequest {
+                method: "POST".to_string(),
+                url: upload_url,
+                headers: vec![(
+                    "Content-Type".to_string(),
+                    "application/octet-stream".to_string(),
+                )],
+                body: Vec::new(),
+            },
+            body,
+        )
+        .await?;
+    if !(200..300).contains(&response.status_code) {
+        return Err(format!(
+            "File upload to Slack failed with status {}",
+            response.status_code
+        )
+        .into());
+    }
+
+    debug!(
+        file_id = %file_id,
+        "File upload completed"
+    );
+
+    Ok(file_id)
+}
+
+/// `files.getUploadURLExternal` で長さ `length` のファイルのアップロード URL とファイル ID を取得する。
+async fn get_upload_url(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    file_name: &str,
+    length: usize,
+    alt_text: Option<&str>,
+) -> Result<(String, String), Box<dyn StdError>> {
+    let url = format!("{}/files.getUploadURLExternal", slack_api_base_url);
+    let alt_text_query = alt_text
+        .map(|alt| format!("&alt_txt={}", percent_encode_query(alt)))
+        .unwrap_or_default();
+
+    debug!(
+        api_endpoint = "files.getUploadURLExternal",
+        file_name = %file_name,
+        file_size = length,
+        "Getting upload URL from Slack"
+    );
+
+    let response = rate_limiter
+        .send(
+            client,
+            "files.getUploadURLExternal",
+            None,
+            HttpRequest {
+                method: "GET".to_string(),
+                url: format!(
+                    "{}?filename={}&length={}{}",
+                    url,
+                    percent_encode_query(file_name),
+                    length,
+                    alt_text_query
+                ),
+                headers: vec![(
+                    "Authorization".to_string(),
+                    format!("Bearer {slack_bot_token}"),
+                )],
+                body: Vec::new(),
+            },
+        )
+        .await?;
+
+    let response = String::from_utf8(response.body).map_err(Box::<dyn StdError>::from)?;
+
+    let parsed = nojson::RawJson::parse(&response)?;
+    let root = parsed.value();
+    let ok = get_required_bool(root, "ok")?;
+
+    if !ok {
+        let error_message =
+            get_optional_string(root, "error").unwrap_or_else(|| "unknown_error".to_string());
+        error!(
+            error = %error_message,
+            file_name = %file_name,
+            "Failed to get upload URL from Slack"
+        );
+        return Err(Box::new(SlackApiError::new(error_message)));
+    }
+
+    let upload_url = get_required_string(root, "upload_url")?;
+    let file_id = get_required_string(root, "file_id")?;
+    Ok((upload_url, file_id))
+}
+
+#[instrument(skip(client, rate_limiter, token, file_data), fields(file_name = %file_name, channel = %channel, file_size = file_data.len()))]
 pub async fn send_single_file_to_slack(
     client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
     token: &str,
     slack_api_base_url: &str,
     file_data: &[u8],
     file_name: &str,
     channel: &str,
 ) -> Result<String, Box<dyn StdError>> {
-    let (file_id, _upload_url) =
-        upload_file(client, token, slack_api_base_url, file_name, file_data).await?;
+    let (file_id, _upload_url) = upload_file(
+        client,
+        rate_limiter,
+        token,
+        slack_api_base_url,
+        file_name,
+        file_data,
+        None,
+    )
+    .await?;
+
+    let files = [UploadedFile {
+        id: file_id,
+        title: file_name.to_string(),
+    }];
+    complete_uploads(
+        client,
+        rate_limiter,
+        token,
+        slack_api_base_url,
+        channel,
+        &files,
+        &UploadPlacement::default(),
+    )
+    .await
+}
+
+/// `files.getUploadURLExternal` でアップロード済みのファイル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadedFile {
+    pub id: String,
+    pub title: String,
+}
+
+/// アップロードしたファイルを共有するメッセージの本文とスレッド
+#[derive(Debug, Clone, Default)]
+pub struct UploadPlacement {
+    pub initial_comment: Option<String>,
+    pub thread_ts: Option<String>,
+}
+
+/// 1件ずつアップロードするファイル
+#[derive(Debug, Clone)]
+pub struct FileToUpload {
+    pub file_name: String,
+    pub title: String,
+    pub alt_text: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// 同時に `upload_file` する最大数
+const MAX_CONCURRENT_UPLOADS: usize = 4;
+
+/// `files` を最大 `MAX_CONCURRENT_UPLOADS` 件ずつ並行してアップロードし、入力と同じ順で返す。
+/// 1件でも失敗した場合は残りを中断してエラーを返す。
+#[instrument(skip(client, rate_limiter, token, files), fields(file_count = files.len()))]
+pub async fn upload_files(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    token: &str,
+    slack_api_base_url: &str,
+    files: Vec<FileToUpload>,
+) -> Result<Vec<UploadedFile>, Box<dyn StdError>> {
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_UPLOADS));
+    let mut tasks = JoinSet::new();
+    let file_count = files.len();
+
+    for (index, file) in files.into_iter().enumerate() {
+        let client = client.clone();
+        let rate_limiter = rate_limiter.clone();
+        let token = token.to_string();
+        let slack_api_base_url = slack_api_base_url.to_string();
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            let (id, _upload_url) = upload_file(
+                &client,
+                &rate_limiter,
+                &token,
+                &slack_api_base_url,
+                &file.file_name,
+                &file.data,
+                file.alt_text.as_deref(),
+            )
+            .await
+            .map_err(into_send_error)?;
+            Ok::<_, Box<dyn StdError + Send + Sync>>((
+                index,
+                UploadedFile {
+                    id,
+                    title: file.title,
+                },
+            ))
+        });
+    }
+
+    let mut uploaded: Vec<Option<UploadedFile>> = vec![None; file_count];
+    while let Some(joined) = tasks.join_next().await {
+        let (index, file) = joined
+            .map_err(Box::<dyn StdError>::from)?
+            .map_err(|e| -> Box<dyn StdError> { e })?;
+        uploaded[index] = Some(file);
+    }
+
+    Ok(uploaded.into_iter().flatten().collect())
+}
+
+/// タスクから返せるよう `Send` にする。呼び出し元がエラーの種類で応答を変えられるよう、
+/// `SlackApiError` と `HttpClientError` は型を保つ。
+fn into_send_error(e: Box<dyn StdError>) -> Box<dyn StdError + Send + Sync> {
+    let e = match e.downcast::<SlackApiError>() {
+        Ok(e) => return e,
+        Err(e) => e,
+    };
+    match e.downcast::<HttpClientError>() {
+        Ok(e) => e,
+        Err(e) => e.to_string().into(),
+    }
+}
 
+/// `files.completeUploadExternal` で `files` を1つのメッセージとしてチャンネル (またはスレッド) に共有する。
+#[instrument(skip(client, rate_limiter, token, files, placement), fields(channel = %channel, file_count = files.len()))]
+pub async fn complete_uploads(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    files: &[UploadedFile],
+    placement: &UploadPlacement,
+) -> Result<String, Box<dyn StdError>> {
     let url = format!("{}/files.completeUploadExternal", slack_api_base_url);
 
     let data = nojson::json(|f| {
@@ -198,37 +1263,51 @@ pub async fn send_single_file_to_slack(
             f.member(
                 "files",
                 nojson::array(|f| {
-                    f.element(nojson::object(|f| {
-                        f.member("id", &file_id)?;
-                        f.member("title", file_name)
-                    }))
+                    for file in files {
+                        f.element(nojson::object(|f| {
+                            f.member("id", &file.id)?;
+                            f.member("title", &file.title)
+                        }))?;
+                    }
+                    Ok(())
                 }),
             )?;
-            f.member("channel_id", channel)
+            f.member("channel_id", channel)?;
+            if let Some(initial_comment) = &placement.initial_comment {
+                f.member("initial_comment", initial_comment)?;
+            }
+            if let Some(thread_ts) = &placement.thread_ts {
+                f.member("thread_ts", thread_ts)?;
+            }
+            Ok(())
         })
     });
 
     debug!(
         api_endpoint = "files.completeUploadExternal",
-        file_id = %file_id,
+        file_count = files.len(),
         channel = %channel,
         "Completing file upload to Slack"
     );
 
-    let response_text = client
-        .send(HttpRequest {
-            method: "POST".to_string(),
-            url,
-            headers: vec![
-                ("Authorization".to_string(), format!("Bearer {token}")),
-                ("Content-Type".to_string(), "application/json".to_string()),
-            ],
-            body: data.to_string().into_bytes(),
-        })
+    let response_text = rate_limiter
+        .send(
+            client,
+            "files.completeUploadExternal",
+            Some(channel),
+            HttpRequest {
+                method: "POST".to_string(),
+                url,
+                headers: vec![
+                    ("Authorization".to_string(), format!("Bearer {token}")),
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                ],
+                body: data.to_string().into_bytes(),
+            },
+        )
         .await?;
 
-    let response_text = String::from_utf8(response_text.body)
-        .map_err(|e| Box::<dyn StdError>::from(std::io::Error::other(e.to_string())))?;
+    let response_text = String::from_utf8(response_text.body).map_err(Box::<dyn StdError>::from)?;
 
     let parsed = nojson::RawJson::parse(&response_text)?;
     let root = parsed.value();
@@ -236,21 +1315,392 @@ pub async fn send_single_file_to_slack(
 
     if ok {
         info!(
-            file_id = %file_id,
-            file_name = %file_name,
+            file_count = files.len(),
             channel = %channel,
-            "File successfully shared to Slack channel"
+            thread_ts = ?placement.thread_ts,
+            "Files successfully shared to Slack channel"
         );
         Ok(response_text)
     } else {
+        let error_message =
+            get_optional_string(root, "error").unwrap_or_else(|| "unknown_error".to_string());
         error!(
-            error = %get_optional_string(root, "error").unwrap_or_else(|| "unknown_error".to_string()),
-            file_id = %file_id,
+            error = %error_message,
+            file_count = files.len(),
             channel = %channel,
             "Failed to complete file upload"
         );
-        let error_message =
-            get_optional_string(root, "error").unwrap_or_else(|| "unknown_error".to_string());
-        Err(Box::new(std::io::Error::other(error_message)))
+        Err(Box::new(SlackApiError::new(error_message)))
+    }
+}
+
+/// `files.info` / `files.list` のファイル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlackFile {
+    pub id: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub mimetype: Option<String>,
+    pub user: Option<String>,
+    pub created: i64,
+    pub size: Option<i64>,
+    /// 共有先のチャンネル (`channels` / `groups` / `ims` の順)
+    pub channels: Vec<String>,
+    pub url_private_download: Option<String>,
+}
+
+fn parse_slack_file(item: nojson::RawJsonValue<'_, '_>) -> Result<SlackFile, Box<dyn StdError>> {
+    let mut channels = Vec::new();
+    for member in ["channels", "groups", "ims"] {
+        if let Some(ids) = item.to_member(member)?.optional() {
+            for id in ids.to_array()? {
+                channels.push(String::try_from(id)?);
+            }
+        }
+    }
+    Ok(SlackFile {
+        id: get_required_string(item, "id")?,
+        name: get_optional_string(item, "name").unwrap_or_default(),
+        title: get_optional_string(item, "title"),
+        mimetype: get_optional_string(item, "mimetype"),
+        user: get_optional_string(item, "user"),
+        created: get_optional_i64(item, "created").unwrap_or_default(),
+        size: get_optional_i64(item, "size"),
+        channels,
+        url_private_download: get_optional_string(item, "url_private_download"),
+    })
+}
+
+/// `files.info` でファイルのメタデータを取得する。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(file_id = %file_id))]
+pub async fn file_info(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    file_id: &str,
+) -> Result<SlackFile, Box<dyn StdError>> {
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "files.info",
+        &[("file", file_id)],
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    parse_slack_file(parsed.value().to_member("file")?.required()?)
+}
+
+/// `files.list` の絞り込み条件。`ts_from` / `ts_to` は Unix 秒。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileListFilter {
+    pub channel: Option<String>,
+    pub ts_from: Option<i64>,
+    pub ts_to: Option<i64>,
+    pub types: Option<String>,
+}
+
+/// `files.list` の `page` ページ目 (1 始まり) を取得し、ファイルと総ページ数を返す。
+#[instrument(skip(client, rate_limiter, slack_bot_token))]
+pub async fn list_files(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    filter: &FileListFilter,
+    page: u32,
+) -> Result<(Vec<SlackFile>, u32), Box<dyn StdError>> {
+    let ts_from = filter.ts_from.map(|ts| ts.to_string());
+    let ts_to = filter.ts_to.map(|ts| ts.to_string());
+    let page = page.to_string();
+    let mut query = vec![("count", "100"), ("page", page.as_str())];
+    query.extend(
+        filter
+            .channel
+            .as_deref()
+            .map(|channel| ("channel", channel)),
+    );
+    query.extend(ts_from.as_deref().map(|ts| ("ts_from", ts)));
+    query.extend(ts_to.as_deref().map(|ts| ("ts_to", ts)));
+    query.extend(filter.types.as_deref().map(|types| ("types", types)));
+
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "files.list",
+        &query,
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    let root = parsed.value();
+
+    let mut files = Vec::new();
+    if let Some(items) = root.to_member("files")?.optional() {
+        for item in items.to_array()? {
+            files.push(parse_slack_file(item)?);
+        }
+    }
+    let pages = root
+        .to_member("paging")
+        .ok()
+        .and_then(|m| m.optional())
+        .and_then(|paging| get_optional_i64(paging, "pages"))
+        .and_then(|pages| u32::try_from(pages).ok())
+        .unwrap_or(1);
+
+    Ok((files, pages))
+}
+
+/// `conversations.history` の1ページ分のメッセージ (新しい順) と次ページのカーソルを返す。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %channel))]
+pub async fn conversation_history(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    range: HistoryRange,
+    cursor: Option<&str>,
+) -> Result<(Vec<ExportedMessage>, Option<String>), Box<dyn StdError>> {
+    let limit = HISTORY_PAGE_LIMIT.to_string();
+    let oldest = range.oldest.map(slack_ts);
+    let latest = range.latest.map(slack_ts);
+    let mut query = vec![("channel", channel), ("limit", limit.as_str())];
+    query.extend(cursor.map(|cursor| ("cursor", cursor)));
+    query.extend(oldest.as_deref().map(|ts| ("oldest", ts)));
+    query.extend(latest.as_deref().map(|ts| ("latest", ts)));
+
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "conversations.history",
+        &query,
+    )
+    .await?;
+    parse_message_page(&response)
+}
+
+/// `conversations.replies` の1ページ分のメッセージ (親メッセージを含む古い順) と次ページのカーソルを返す。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(channel = %channel, thread_ts = %thread_ts))]
+pub async fn conversation_replies(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    channel: &str,
+    thread_ts: &str,
+    cursor: Option<&str>,
+) -> Result<(Vec<ExportedMessage>, Option<String>), Box<dyn StdError>> {
+    let limit = HISTORY_PAGE_LIMIT.to_string();
+    let mut query = vec![
+        ("channel", channel),
+        ("ts", thread_ts),
+        ("limit", limit.as_str()),
+    ];
+    query.extend(cursor.map(|cursor| ("cursor", cursor)));
+
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "conversations.replies",
+        &query,
+    )
+    .await?;
+    parse_message_page(&response)
+}
+
+fn parse_message_page(
+    response: &str,
+) -> Result<(Vec<ExportedMessage>, Option<String>), Box<dyn StdError>> {
+    let parsed = nojson::RawJson::parse(response)?;
+    let root = parsed.value();
+
+    let mut messages = Vec::new();
+    if let Some(items) = root.to_member("messages")?.optional() {
+        for item in items.to_array()? {
+            messages.push(ExportedMessage::parse(item)?);
+        }
+    }
+    let next_cursor = root
+        .to_member("response_metadata")
+        .ok()
+        .and_then(|m| m.optional())
+        .and_then(|metadata| get_optional_string(metadata, "next_cursor"))
+        .filter(|cursor| !cursor.is_empty());
+
+    Ok((messages, next_cursor))
+}
+
+/// `users.info` でユーザーの表示名を引く。`display_name`、`real_name`、`name` の順に空でないものを使う。
+#[instrument(skip(client, rate_limiter, slack_bot_token), fields(user = %user))]
+pub async fn user_name(
+    client: &HttpClient,
+    rate_limiter: &SlackRateLimiter,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    user: &str,
+) -> Result<Option<String>, Box<dyn StdError>> {
+    let response = call_query_api(
+        client,
+        rate_limiter,
+        slack_bot_token,
+        slack_api_base_url,
+        "users.info",
+        &[("user", user)],
+    )
+    .await?;
+    let parsed = nojson::RawJson::parse(&response)?;
+    let user = parsed.value().to_member("user")?.required()?;
+    let profile = user.to_member("profile")?.optional();
+
+    let candidates = [
+        profile.and_then(|profile| get_optional_string(profile, "display_name")),
+        profile.and_then(|profile| get_optional_string(profile, "real_name")),
+        get_optional_string(user, "real_name"),
+        get_optional_string(user, "name"),
+    ];
+    Ok(candidates
+        .into_iter()
+        .flatten()
+        .find(|name| !name.trim().is_empty()))
+}
+
+/// ボットトークンを付けてよいファイルの URL か。`https://files.slack.com` と、設定した API と同じ接続先のみ許可する。
+fn is_slack_file_url(url: &str, slack_api_base_url: &str) -> bool {
+    let origin = |uri: &Uri| {
+        Some((
+            uri.scheme()?.to_ascii_lowercase(),
+            uri.host()?.to_ascii_lowercase(),
+            uri.port(),
+        ))
+    };
+    let Some((scheme, host, port)) = Uri::parse(url).ok().as_ref().and_then(origin) else {
+        return false;
+    };
+    if scheme == "https" && host == "files.slack.com" && port.is_none_or(|port| port == 443) {
+        return true;
+    }
+    Uri::parse(slack_api_base_url)
+        .ok()
+        .as_ref()
+        .and_then(origin)
+        .is_some_and(|api| api == (scheme, host, port))
+}
+
+/// `url_private_download` をボットトークン付きで取得する。`max_size` バイトを超える場合はエラー。
+/// Slack 以外の接続先にトークンを送らないよう、`is_slack_file_url` を満たさない URL は取得しない。
+#[instrument(skip(client, slack_bot_token, url))]
+pub async fn download_file(
+    client: &HttpClient,
+    slack_bot_token: &str,
+    slack_api_base_url: &str,
+    url: &str,
+    max_size: usize,
+) -> Result<Vec<u8>, Box<dyn StdError>> {
+    if !is_slack_file_url(url, slack_api_base_url) {
+        return Err("Refusing to send the bot token to a non-Slack file URL".into());
+    }
+    let mut response = client
+        .send_streaming(HttpRequest {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            headers: vec![(
+                "Authorization".to_string(),
+                format!("Bearer {slack_bot_token}"),
+            )],
+            body: Vec::new(),
+        })
+        .await?;
+
+    if response.status_code != 200 {
+        return Err(format!(
+            "Slack file download failed with status {}",
+            response.status_code
+        )
+        .into());
+    }
+    let mut data = Vec::new();
+    let mut chunk = vec![0_u8; 64 * 1024];
+    loop {
+        let n = response.read_chunk(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        if data.len() + n > max_size {
+            return Err(
+                format!("Slack file exceeds the transfer limit of {max_size} bytes").into(),
+            );
+        }
+        data.extend_from_slice(&chunk[..n]);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SlackApiError, into_send_error, is_slack_file_url};
+    use crate::http_client::{HttpClientError, TimeoutPhase};
+    use std::error::Error as StdError;
+
+    #[test]
+    fn bot_token_is_only_sent_to_slack_file_hosts() {
+        let api = "https://slack.com/api";
+        assert!(is_slack_file_url(
+            "https://files.slack.com/files-pri/T1-F1/download/a.png",
+            api
+        ));
+        assert!(is_slack_file_url(
+            "https://FILES.slack.com/files-pri/T1-F1/a.png",
+            api
+        ));
+        assert!(is_slack_file_url(
+            "http://127.0.0.1:8080/files/F1",
+            "http://127.0.0.1:8080/api"
+        ));
+
+        assert!(!is_slack_file_url(
+            "http://files.slack.com/files-pri/a.png",
+            api
+        ));
+        assert!(!is_slack_file_url(
+            "https://files.slack.com.evil.example/a.png",
+            api
+        ));
+        assert!(!is_slack_file_url(
+            "https://evil.example/files.slack.com/a.png",
+            api
+        ));
+        assert!(!is_slack_file_url("https://slack.com:8443/api/a.png", api));
+        assert!(!is_slack_file_url("not a url", api));
+    }
+
+    #[test]
+    fn upload_errors_keep_their_type_across_tasks() {
+        let slack: Box<dyn StdError> =
+            into_send_error(Box::new(SlackApiError::new("not_in_channel")));
+        assert_eq!(
+            slack
+                .downcast_ref::<SlackApiError>()
+                .map(|e| e.code.as_str()),
+            Some("not_in_channel")
+        );
+
+        let timeout: Box<dyn StdError> =
+            into_send_error(Box::new(HttpClientError::Timeout(TimeoutPhase::Total)));
+        assert!(matches!(
+            timeout.downcast_ref::<HttpClientError>(),
+            Some(HttpClientError::Timeout(TimeoutPhase::Total))
+        ));
+
+        let other: Box<dyn StdError> = into_send_error("boom".into());
+        assert_eq!(other.to_string(), "boom");
     }
 }
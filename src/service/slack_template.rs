@@ -0,0 +1,683 @@
+use std::collections::HashMap;
+
+use nojson::{DisplayJson, JsonFormatter, JsonValueKind, RawJsonOwned, RawJsonValue};
+
+use crate::service::slack_message::escape_mrkdwn;
+
+/// テンプレート名に使える文字 (S3 のキーにそのまま使うため制限する)
+pub fn is_valid_template_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 128
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+}
+
+/// `SLACK_TEMPLATE_S3` (`bucket[/prefix]`) で指定するテンプレートの置き場所。テンプレートは `{prefix}/{name}.json`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateLocation {
+    pub bucket: String,
+    pub prefix: String,
+}
+
+impl TemplateLocation {
+    pub fn parse(raw: &str) -> Result<Self, String> {
+        let raw = raw.trim().trim_start_matches("s3://");
+        let (bucket, prefix) = raw.split_once('/').unwrap_or((raw, ""));
+        if bucket.is_empty() {
+            return Err("bucket is required".to_string());
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+
+    pub fn key(&self, name: &str) -> String {
+        if self.prefix.is_empty() {
+            format!("{name}.json")
+        } else {
+            format!("{}/{name}.json", self.prefix)
+        }
+    }
+}
+
+/// 描画に失敗した箇所。`location` はテンプレート内の JSON の位置 (`blocks[0].text.text` など)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    pub location: String,
+    /// `{{ }}` の中身
+    pub placeholder: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {{{{{}}}}}: {}",
+            self.location, self.placeholder, self.reason
+        )
+    }
+}
+
+/// `SLACK_TEMPLATES` (`{"name": {"text": "...", "blocks": [...]}, ...}`) を読み、構文を検証する。
+pub fn parse_templates(raw: &str) -> Result<HashMap<String, RawJsonOwned>, String> {
+    let json = nojson::RawJson::parse(raw).map_err(|e| format!("invalid JSON: {e}"))?;
+    let members = json
+        .value()
+        .to_object()
+        .map_err(|_| "must be a JSON object".to_string())?;
+
+    let mut templates = HashMap::new();
+    for (name, template) in members {
+        let name = name.to_unquoted_string_str().map_err(|e| e.to_string())?;
+        if !is_valid_template_name(&name) {
+            return Err(format!("invalid template name '{name}'"));
+        }
+        check_template(template).map_err(|e| format!("template '{name}': {e}"))?;
+        templates.insert(name.into_owned(), template.extract().into_owned());
+    }
+    Ok(templates)
+}
+
+/// テンプレートがオブジェクトで、すべての文字列の `{{ }}` が閉じているかを確かめる。
+pub fn check_template(template: RawJsonValue<'_, '_>) -> Result<(), TemplateError> {
+    if template.kind() != JsonValueKind::Object {
+        return Err(TemplateError {
+            location: "$".to_string(),
+            placeholder: String::new(),
+            reason: "template must be a JSON object".to_string(),
+        });
+    }
+    check_value(template, &mut String::new())
+}
+
+fn check_value(value: RawJsonValue<'_, '_>, location: &mut String) -> Result<(), TemplateError> {
+    match value.kind() {
+        JsonValueKind::String => {
+            let text = value.to_unquoted_string_str().unwrap_or_default();
+            parse(&text).map(|_| ()).map_err(|e| e.at(location))
+        }
+        JsonValueKind::Array => {
+            for (i, element) in value.to_array().into_iter().flatten().enumerate() {
+                let len = location.len();
+                location.push_str(&format!("[{i}]"));
+                if let Some((_, path, body)) = directive(element) {
+                    parse_path(path, path).map_err(|e| e.at(location))?;
+                    check_value(body, location)?;
+                } else {
+                    check_value(element, location)?;
+                }
+                location.truncate(len);
+            }
+            Ok(())
+        }
+        JsonValueKind::Object => {
+            for (name, member) in value.to_object().into_iter().flatten() {
+                let len = location.len();
+                push_member(location, &name.to_unquoted_string_str().unwrap_or_default());
+                check_value(member, location)?;
+                location.truncate(len);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// テンプレートの文字列を `context` で置き換えた JSON を返す。
+///
+/// - `{{path.to.value}}`: 値を mrkdwn 用にエスケープして埋め込む。`{{{path}}}` はエスケープしない
+/// - `{{#if path}}...{{else}}...{{/if}}`: 値が無い・`null`・`false`・空文字列・`0`・空配列なら `else` 側
+/// - `{{#each path}}...{{/each}}`: 配列の要素ごとに繰り返す。中では `{{this}}` / `{{this.name}}` / `{{@index}}` と外側の値を参照できる
+/// - 配列の要素を `{"{{#each path}}": {...}}` / `{"{{#if path}}": {...}}` にすると、ブロック単位で繰り返す・省く
+pub fn render(
+    template: RawJsonValue<'_, '_>,
+    context: RawJsonValue<'_, '_>,
+) -> Result<String, TemplateError> {
+    check_template(template)?;
+    let mut scope = vec![Frame {
+        value: context,
+        index: None,
+    }];
+    let rendered = render_value(template, &mut scope, &mut String::new())?;
+    Ok(nojson::Json(&rendered).to_string())
+}
+
+/// 描画後の JSON。数値・真偽値・`null` は元のテンプレートの値をそのまま使う
+enum Rendered<'t, 'r> {
+    Raw(RawJsonValue<'t, 'r>),
+    String(String),
+    Array(Vec<Rendered<'t, 'r>>),
+    Object(Vec<(String, Rendered<'t, 'r>)>),
+}
+
+impl DisplayJson for Rendered<'_, '_> {
+    fn fmt(&self, f: &mut JsonFormatter<'_, '_>) -> std::fmt::Result {
+        match self {
+            Self::Raw(value) => f.value(value),
+            Self::String(text) => f.value(text),
+            Self::Array(elements) => f.array(|f| f.elements(elements)),
+            Self::Object(members) => f.object(|f| f.members(members.iter().map(|(k, v)| (k, v)))),
+        }
+    }
+}
+
+struct Frame<'c, 'r> {
+    value: RawJsonValue<'c, 'r>,
+    /// `{{#each}}` の中での要素の位置
+    index: Option<usize>,
+}
+
+fn render_value<'t, 'r>(
+    value: RawJsonValue<'t, 'r>,
+    scope: &mut Vec<Frame<'_, '_>>,
+    location: &mut String,
+) -> Result<Rendered<'t, 'r>, TemplateError> {
+    match value.kind() {
+        JsonValueKind::String => {
+            let text = value.to_unquoted_string_str().unwrap_or_default();
+            let nodes = parse(&text).map_err(|e| e.at(location))?;
+            let mut out = String::new();
+            render_nodes(&nodes, scope, &mut out).map_err(|e| e.at(location))?;
+            Ok(Rendered::String(out))
+        }
+        JsonValueKind::Array => {
+            let mut elements = Vec::new();
+            for (i, element) in value.to_array().into_iter().flatten().enumerate() {
+                let len = location.len();
+                location.push_str(&format!("[{i}]"));
+                match directive(element) {
+                    Some((Directive::If, path, body)) => {
+                        if truthy(lookup(scope, path)) {
+                            elements.push(render_value(body, scope, location)?);
+                        }
+                    }
+                    Some((Directive::Each, path, body)) => {
+                        let items = each_items(scope, path)
+                            .map_err(|reason| fail(format!("#each {path}"), reason).at(location))?;
+                        for (index, item) in items.into_iter().enumerate() {
+                            scope.push(Frame {
+                                value: item,
+                                index: Some(index),
+                            });
+                            let rendered = render_value(body, scope, location);
+                            scope.pop();
+                            elements.push(rendered?);
+                        }
+                    }
+                    None => elements.push(render_value(element, scope, location)?),
+                }
+                location.truncate(len);
+            }
+            Ok(Rendered::Array(elements))
+        }
+        JsonValueKind::Object => {
+            let mut members = Vec::new();
+            for (name, member) in value.to_object().into_iter().flatten() {
+                let name = name
+                    .to_unquoted_string_str()
+                    .unwrap_or_default()
+                    .into_owned();
+                let len = location.len();
+                push_member(location, &name);
+                let rendered = render_value(member, scope, location)?;
+                location.truncate(len);
+                members.push((name, rendered));
+            }
+            Ok(Rendered::Object(members))
+        }
+        _ => Ok(Rendered::Raw(value)),
+    }
+}
+
+fn push_member(location: &mut String, name: &str) {
+    if !location.is_empty() {
+        location.push('.');
+    }
+    location.push_str(name);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    If,
+    Each,
+}
+
+/// `{"{{#each path}}": {...}}` の形の要素なら、その種類とパスと中身
+fn directive<'t, 'r>(
+    element: RawJsonValue<'t, 'r>,
+) -> Option<(Directive, &'t str, RawJsonValue<'t, 'r>)> {
+    let mut members = element.to_object().ok()?;
+    let (name, body) = members.next()?;
+    if members.next().is_some() {
+        return None;
+    }
+    let tag = name
+        .as_string_str()
+        .ok()?
+        .strip_prefix("{{")?
+        .strip_suffix("}}")?
+        .trim();
+    if let Some(path) = tag.strip_prefix("#each ") {
+        Some((Directive::Each, path.trim(), body))
+    } else {
+        Some((Directive::If, tag.strip_prefix("#if ")?.trim(), body))
+    }
+}
+
+/// 文字列中の `{{ }}` を解析した結果
+#[derive(Debug, PartialEq, Eq)]
+enum Node<'a> {
+    Text(&'a str),
+    Var {
+        path: &'a str,
+        raw: bool,
+    },
+    If {
+        path: &'a str,
+        then: Vec<Node<'a>>,
+        otherwise: Vec<Node<'a>>,
+    },
+    Each {
+        path: &'a str,
+        body: Vec<Node<'a>>,
+    },
+}
+
+/// 位置を付ける前のエラー
+#[derive(Debug)]
+struct Failure {
+    placeholder: String,
+    reason: String,
+}
+
+impl Failure {
+    fn at(self, location: &str) -> TemplateError {
+        TemplateError {
+            location: if location.is_empty() {
+                "$".to_string()
+            } else {
+                location.to_string()
+            },
+            placeholder: self.placeholder,
+            reason: self.reason,
+        }
+    }
+}
+
+fn fail(placeholder: impl Into<String>, reason: impl Into<String>) -> Failure {
+    Failure {
+        placeholder: placeholder.into(),
+        reason: reason.into(),
+    }
+}
+
+#[derive(Debug)]
+enum Token<'a> {
+    Text(&'a str),
+    Tag { inner: &'a str, raw: bool },
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token<'_>>, Failure> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let after = &rest[start..];
+        let (raw, open, close) = if after.starts_with("{{{") {
+            (true, 3, "}}}")
+        } else {
+            (false, 2, "}}")
+        };
+        let Some(end) = after[open..].find(close) else {
+            let snippet = after[open..].chars().take(32).collect::<String>();
+            return Err(fail(snippet, format!("unclosed tag (missing '{close}')")));
+        };
+        let inner = after[open..open + end].trim();
+        if inner.is_empty() {
+            return Err(fail("", "empty tag"));
+        }
+        tokens.push(Token::Tag { inner, raw });
+        rest = &after[open + end + close.len()..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    Ok(tokens)
+}
+
+fn parse(text: &str) -> Result<Vec<Node<'_>>, Failure> {
+    let tokens = tokenize(text)?;
+    let mut pos = 0;
+    let (nodes, end) = parse_nodes(&tokens, &mut pos, None)?;
+    debug_assert!(end.is_none());
+    Ok(nodes)
+}
+
+/// `open` のブロックの終わり (`{{else}}` / `{{/if}}` / `{{/each}}`) まで読む。
+fn parse_nodes<'a>(
+    tokens: &[Token<'a>],
+    pos: &mut usize,
+    open: Option<(&'a str, &'static str)>,
+) -> Result<(Vec<Node<'a>>, Option<&'a str>), Failure> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.get(*pos) {
+        *pos += 1;
+        let (inner, raw) = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag { inner, raw } => (*inner, *raw),
+        };
+
+        if let Some(path) = inner.strip_prefix("#if ") {
+            let path = parse_path(inner, path.trim())?;
+            let (then, end) = parse_nodes(tokens, pos, Some((inner, "if")))?;
+            let otherwise = if end == Some("else") {
+                parse_nodes(tokens, pos, Some((inner, "if"))).and_then(
+                    |(nodes, end)| match end {
+                        Some("else") => Err(fail("else", "duplicate {{else}}")),
+                        _ => Ok(nodes),
+                    },
+                )?
+            } else {
+                Vec::new()
+            };
+            nodes.push(Node::If {
+                path,
+                then,
+                otherwise,
+            });
+        } else if let Some(path) = inner.strip_prefix("#each ") {
+            let path = parse_path(inner, path.trim())?;
+            let (body, end) = parse_nodes(tokens, pos, Some((inner, "each")))?;
+            if end == Some("else") {
+                return Err(fail("else", "{{else}} is not supported in {{#each}}"));
+            }
+            nodes.push(Node::Each { path, body });
+        } else if inner == "else" {
+            return match open {
+                Some((_, "if")) => Ok((nodes, Some("else"))),
+                _ => Err(fail(inner, "{{else}} outside of {{#if}}")),
+            };
+        } else if let Some(block) = inner.strip_prefix('/') {
+            return match open {
+                Some((_, expected)) if expected == block.trim() => Ok((nodes, None)),
+                Some((opened, _)) => {
+                    Err(fail(inner, format!("expected the end of {{{{{opened}}}}}")))
+                }
+                None => Err(fail(inner, "closing tag without an opening tag")),
+            };
+        } else if inner.starts_with('#') {
+            return Err(fail(inner, "unknown block (use #if or #each)"));
+        } else {
+            nodes.push(Node::Var {
+                path: parse_path(inner, inner)?,
+                raw,
+            });
+        }
+    }
+    match open {
+        Some((opened, _)) => Err(fail(opened, "block is not closed")),
+        None => Ok((nodes, None)),
+    }
+}
+
+/// `user.name` / `this` / `this.name` / `@index` を受け付ける。
+fn parse_path<'a>(tag: &str, path: &'a str) -> Result<&'a str, Failure> {
+    let valid = path == "@index"
+        || (!path.is_empty()
+            && path.split('.').all(|segment| {
+                !segment.is_empty()
+                    && segment
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-')
+            }));
+    if valid {
+        Ok(path)
+    } else {
+        Err(fail(tag, format!("invalid path '{path}'")))
+    }
+}
+
+/// 参照した値
+#[derive(Clone, Copy)]
+enum Found<'c, 'r> {
+    Json(RawJsonValue<'c, 'r>),
+    Index(usize),
+}
+
+/// 内側の `{{#each}}` の要素から順に `path` の先頭の名前を探す。
+fn lookup<'c, 'r>(scope: &[Frame<'c, 'r>], path: &str) -> Option<Found<'c, 'r>> {
+    if path == "@index" {
+        return scope.last()?.index.map(Found::Index);
+    }
+    let mut segments = path.split('.');
+    let first = segments.next()?;
+    let start = if first == "this" {
+        scope.last()?.value
+    } else {
+        scope.iter().rev().find_map(|frame| {
+            frame
+                .value
+                .to_member(first)
+                .ok()
+                .and_then(|member| member.optional())
+        })?
+    };
+    segments
+        .try_fold(start, |value, name| value.to_member(name).ok()?.optional())
+        .map(Found::Json)
+}
+
+fn truthy(found: Option<Found<'_, '_>>) -> bool {
+    match found {
+        None => false,
+        Some(Found::Index(index)) => index != 0,
+        Some(Found::Json(value)) => match value.kind() {
+            JsonValueKind::Null => false,
+            JsonValueKind::Boolean => value.as_raw_str() == "true",
+            JsonValueKind::Integer | JsonValueKind::Float => {
+                value.as_raw_str().parse::<f64>().is_ok_and(|n| n != 0.0)
+            }
+            JsonValueKind::String => value.as_raw_str() != "\"\"",
+            JsonValueKind::Array => value
+                .to_array()
+                .is_ok_and(|mut items| items.next().is_some()),
+            JsonValueKind::Object => true,
+        },
+    }
+}
+
+fn each_items<'c, 'r>(
+    scope: &[Frame<'c, 'r>],
+    path: &str,
+) -> Result<Vec<RawJsonValue<'c, 'r>>, String> {
+    match lookup(scope, path) {
+        None => Err(format!("'{path}' is not defined")),
+        Some(Found::Json(value)) if value.kind() == JsonValueKind::Null => Ok(Vec::new()),
+        Some(Found::Json(value)) => value
+            .to_array()
+            .map(|items| items.collect())
+            .map_err(|_| format!("'{path}' is not an array")),
+        Some(Found::Index(_)) => Err(format!("'{path}' is not an array")),
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node<'_>],
+    scope: &mut Vec<Frame<'_, '_>>,
+    out: &mut String,
+) -> Result<(), Failure> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var { path, raw } => {
+                let placeholder = || {
+                    if *raw {
+                        format!("{{{path}}}")
+                    } else {
+                        path.to_string()
+                    }
+                };
+                let text = match lookup(scope, path) {
+                    None => return Err(fail(placeholder(), format!("'{path}' is not defined"))),
+                    Some(Found::Index(index)) => index.to_string(),
+                    Some(Found::Json(value)) => scalar_text(value)
+                        .map_err(|reason| fail(placeholder(), format!("'{path}' {reason}")))?,
+                };
+                if *raw {
+                    out.push_str(&text);
+                } else {
+                    out.push_str(&escape_mrkdwn(&text));
+                }
+            }
+            Node::If {
+                path,
+                then,
+                otherwise,
+            } => {
+                let branch = if truthy(lookup(scope, path)) {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, scope, out)?;
+            }
+            Node::Each { path, body } => {
+                let items = each_items(scope, path)
+                    .map_err(|reason| fail(format!("#each {path}"), reason))?;
+                for (index, item) in items.into_iter().enumerate() {
+                    scope.push(Frame {
+                        value: item,
+                        index: Some(index),
+                    });
+                    let rendered = render_nodes(body, scope, out);
+                    scope.pop();
+                    rendered?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scalar_text(value: RawJsonValue<'_, '_>) -> Result<String, &'static str> {
+    match value.kind() {
+        JsonValueKind::String => Ok(value
+            .to_unquoted_string_str()
+            .map_err(|_| "is not a valid string")?
+            .into_owned()),
+        JsonValueKind::Integer | JsonValueKind::Float | JsonValueKind::Boolean => {
+            Ok(value.as_raw_str().to_string())
+        }
+        JsonValueKind::Null => Err("is null"),
+        JsonValueKind::Array | JsonValueKind::Object => Err("is not a string, number or boolean"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TemplateLocation, parse_templates, render};
+
+    fn render_str(template: &str, context: &str) -> Result<String, String> {
+        let template = nojson::RawJson::parse(template).expect("template");
+        let context = nojson::RawJson::parse(context).expect("context");
+        render(template.value(), context.value()).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn placeholders_conditionals_and_loops_are_rendered() {
+        let rendered = render_str(
+            r#"{"text": "{{service}} {{version}} by {{user.name}}{{#if dry_run}} (dry run){{else}}!{{/if}}{{#each hosts}} {{@index}}:{{this}}{{/each}}", "unfurl_links": false}"#,
+            r#"{"service": "api <prod>", "version": 12, "user": {"name": "ksera"}, "dry_run": false, "hosts": ["a", "b"]}"#,
+        )
+        .expect("rendered");
+        assert_eq!(
+            rendered,
+            r#"{"text":"api &lt;prod&gt; 12 by ksera! 0:a 1:b","unfurl_links":false}"#
+        );
+
+        let raw =
+            render_str(r#"{"text": "{{{link}}}"}"#, r#"{"link": "<https://x|x>"}"#).expect("raw");
+        assert_eq!(raw, r#"{"text":"<https://x|x>"}"#);
+    }
+
+    #[test]
+    fn blocks_can_be_repeated_and_omitted() {
+        let rendered = render_str(
+            r#"{"blocks": [
+                {"type": "header", "text": {"type": "plain_text", "text": "{{title}}"}},
+                {"{{#each steps}}": {"type": "section", "text": {"type": "mrkdwn", "text": "{{name}}: {{status}} ({{title}})"}}},
+                {"{{#if note}}": {"type": "context", "elements": [{"type": "mrkdwn", "text": "{{note}}"}]}}
+            ]}"#,
+            r#"{"title": "Deploy", "steps": [{"name": "build", "status": "ok"}, {"name": "test", "status": "ng"}]}"#,
+        )
+        .expect("rendered");
+        assert_eq!(
+            rendered,
+            concat!(
+                r#"{"blocks":[{"type":"header","text":{"type":"plain_text","text":"Deploy"}},"#,
+                r#"{"type":"section","text":{"type":"mrkdwn","text":"build: ok (Deploy)"}},"#,
+                r#"{"type":"section","text":{"type":"mrkdwn","text":"test: ng (Deploy)"}}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_failing_placeholder() {
+        assert_eq!(
+            render_str(
+                r#"{"blocks": [{"type": "section", "text": {"text": "{{user.email}}"}}]}"#,
+                r#"{"user": {"name": "ksera"}}"#
+            ),
+            Err("blocks[0].text.text: {{user.email}}: 'user.email' is not defined".to_string())
+        );
+        assert_eq!(
+            render_str(r#"{"text": "{{#each user}}x{{/each}}"}"#, r#"{"user": {}}"#),
+            Err("text: {{#each user}}: 'user' is not an array".to_string())
+        );
+        assert_eq!(
+            render_str(r#"{"text": "{{#if a}}x"}"#, "{}"),
+            Err("text: {{#if a}}: block is not closed".to_string())
+        );
+        assert_eq!(
+            render_str(r#"{"text": "{{#if a}}x{{/each}}"}"#, "{}"),
+            Err("text: {{/each}}: expected the end of {{#if a}}".to_string())
+        );
+        assert!(render_str(r#"{"text": "{{user"}"#, "{}").is_err());
+        assert!(render_str(r#"{"text": "{{user name}}"}"#, "{}").is_err());
+        assert!(render_str(r#"{"text": "{{user}}"}"#, r#"{"user": {"a": 1}}"#).is_err());
+    }
+
+    #[test]
+    fn template_location_builds_keys() {
+        let location = TemplateLocation::parse("s3://config/slack/templates/").expect("location");
+        assert_eq!(location.bucket, "config");
+        assert_eq!(
+            location.key("deploy_started"),
+            "slack/templates/deploy_started.json"
+        );
+        let root = TemplateLocation::parse("config").expect("location");
+        assert_eq!(root.key("deploy_started"), "deploy_started.json");
+        assert!(TemplateLocation::parse("/templates").is_err());
+    }
+
+    #[test]
+    fn templates_are_checked_on_load() {
+        let templates =
+            parse_templates(r#"{"deploy_started": {"text": "{{service}} started"}, "empty": {}}"#)
+                .expect("templates");
+        assert_eq!(templates.len(), 2);
+
+        assert!(parse_templates(r#"{"bad": {"text": "{{#each x}}"}}"#).is_err());
+        assert!(parse_templates(r#"{"bad": "text"}"#).is_err());
+        assert!(parse_templates(r#"{"../x": {}}"#).is_err());
+        assert!(parse_templates("[]").is_err());
+    }
+}